@@ -1,6 +1,6 @@
 //! Audio system for DJ Engine.
 //!
-//! Provides BGM and SFX playback with crossfade support for scene transitions.
+//! Provides BGM, SFX and voice playback with crossfade support for scene transitions.
 
 use bevy::prelude::*;
 
@@ -38,6 +38,10 @@ pub enum AudioCommand {
     StopBgm { fade_out: f32 },
     /// Play a one-shot sound effect
     PlaySfx { sound: String },
+    /// Play a voice line, replacing any line still playing
+    PlayVoice { clip: String },
+    /// Stop the current voice line
+    StopVoice,
     /// Set master volume
     SetMasterVolume(f32),
     /// Set BGM volume
@@ -54,6 +58,10 @@ pub struct BgmSource;
 #[derive(Component)]
 pub struct SfxSource;
 
+/// Component marking an entity as the voice line audio source.
+#[derive(Component)]
+pub struct VoiceSource;
+
 /// Plugin providing audio functionality.
 pub struct DJAudioPlugin;
 
@@ -76,6 +84,7 @@ fn handle_audio_commands(
     mut audio_state: ResMut<AudioState>,
     asset_server: Res<AssetServer>,
     bgm_query: Query<Entity, With<BgmSource>>,
+    voice_query: Query<Entity, With<VoiceSource>>,
 ) {
     for cmd in audio_commands.read() {
         match cmd {
@@ -113,6 +122,23 @@ fn handle_audio_commands(
                 ));
                 debug!("Playing SFX: {}", sound);
             }
+            AudioCommand::PlayVoice { clip } => {
+                for entity in voice_query.iter() {
+                    commands.entity(entity).despawn();
+                }
+                let audio_handle: Handle<AudioSource> = asset_server.load(clip.as_str());
+                commands.spawn((
+                    AudioPlayer::<AudioSource>(audio_handle),
+                    PlaybackSettings::DESPAWN,
+                    VoiceSource,
+                ));
+                debug!("Playing voice: {}", clip);
+            }
+            AudioCommand::StopVoice => {
+                for entity in voice_query.iter() {
+                    commands.entity(entity).despawn();
+                }
+            }
             AudioCommand::SetMasterVolume(vol) => {
                 audio_state.master_volume = vol.clamp(0.0, 1.0);
             }
@@ -0,0 +1,49 @@
+//! Write the JSON Schemas of the data files.
+//!
+//! ```text
+//! json_schema [--out <dir>]
+//! ```
+//!
+//! Schemas go to `<dir>`, by default the engine's `schemas` directory
+//! that the schema drift test compares against.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use dj_engine::data::schema::{write_schemas, SCHEMA_DIR};
+
+const USAGE: &str = "usage: json_schema [--out <dir>]";
+
+fn main() -> ExitCode {
+    let mut out_dir = PathBuf::from(SCHEMA_DIR);
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" => match args.next() {
+                Some(dir) => out_dir = PathBuf::from(dir),
+                None => return fail("--out needs a directory"),
+            },
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            _ => return fail(&format!("unexpected argument `{}`", arg)),
+        }
+    }
+
+    match write_schemas(&out_dir) {
+        Ok(paths) => {
+            for path in paths {
+                eprintln!("wrote {}", path.display());
+            }
+            ExitCode::SUCCESS
+        }
+        Err(e) => fail(&format!("{}: {}", out_dir.display(), e)),
+    }
+}
+
+fn fail(message: &str) -> ExitCode {
+    eprintln!("json_schema: {}\n{}", message, USAGE);
+    ExitCode::FAILURE
+}
@@ -0,0 +1,83 @@
+//! Render every story graph in a project as a Graphviz or Mermaid diagram.
+//!
+//! ```text
+//! story_export <project file | project dir> [--format dot|mermaid] [--collapse] [--out <dir>]
+//! ```
+//!
+//! A project dir is searched for `project.json`, `project.ron` or
+//! `project.toml`.
+//! Diagrams are written to `<dir>/<graph id>.dot` (or `.mmd`), or printed
+//! one after another when `--out` is omitted.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use dj_engine::data::export::{export, ExportFormat, ExportOptions};
+use dj_engine::data::bundle::PROJECT_FILE;
+use dj_engine::data::{find_project_file, load_project, load_story_graphs};
+
+const USAGE: &str = "usage: story_export <project file | project dir> [--format dot|mermaid] [--collapse] [--out <dir>]";
+
+fn main() -> ExitCode {
+    let mut project_path = None;
+    let mut format = ExportFormat::Dot;
+    let mut options = ExportOptions::default();
+    let mut out_dir = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => match args.next().as_deref().and_then(ExportFormat::from_name) {
+                Some(f) => format = f,
+                None => return fail("--format must be `dot` or `mermaid`"),
+            },
+            "--collapse" => options.collapse_dialogue = true,
+            "--out" => match args.next() {
+                Some(dir) => out_dir = Some(PathBuf::from(dir)),
+                None => return fail("--out needs a directory"),
+            },
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            _ if project_path.is_none() && !arg.starts_with('-') => project_path = Some(PathBuf::from(arg)),
+            _ => return fail(&format!("unexpected argument `{}`", arg)),
+        }
+    }
+    let Some(mut project_path) = project_path else {
+        return fail("missing project path");
+    };
+    if project_path.is_dir() {
+        project_path = find_project_file(&project_path).map_or_else(|| project_path.join(PROJECT_FILE), |(path, _)| path);
+    }
+
+    let project = match load_project(&project_path) {
+        Ok(project) => project,
+        Err(e) => return fail(&format!("{}: {}", project_path.display(), e)),
+    };
+    let root = project_path.parent().unwrap_or(&project_path).to_path_buf();
+    let graphs = match load_story_graphs(&project, &root) {
+        Ok(graphs) => graphs,
+        Err(e) => return fail(&e.to_string()),
+    };
+
+    for graph in &graphs {
+        let diagram = export(graph, format, &options);
+        match &out_dir {
+            Some(dir) => {
+                let path = dir.join(format!("{}.{}", graph.id, format.extension()));
+                if let Err(e) = std::fs::create_dir_all(dir).and_then(|_| std::fs::write(&path, diagram)) {
+                    return fail(&format!("{}: {}", path.display(), e));
+                }
+                eprintln!("wrote {}", path.display());
+            }
+            None => print!("{}", diagram),
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+fn fail(message: &str) -> ExitCode {
+    eprintln!("story_export: {}\n{}", message, USAGE);
+    ExitCode::FAILURE
+}
@@ -0,0 +1,403 @@
+//! Static analysis of story graphs.
+//!
+//! [`StoryGraphData::validate`] rejects graphs that can't be loaded; the
+//! analyzer looks for graphs that load but misbehave: nodes nothing leads
+//! to, flow that stops without an End node, loops that never wait on the
+//! player (the executor would spin until its per-frame step limit), and
+//! variables that are read but never written.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use super::story::{EffectType, StoryGraphData, StoryNodeVariant};
+use crate::story_graph::markup::{self, MarkupToken};
+
+/// How serious a [`StoryDiagnostic`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Suspicious, but may be intended (or handled outside the graph)
+    Warning,
+    /// The graph will misbehave at runtime
+    Error,
+}
+
+/// What a [`StoryDiagnostic`] found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    /// No path leads to the node from the root or a Call entry
+    UnreachableNode,
+    /// The node has no outgoing edges and isn't an End or Return node
+    DeadEnd,
+    /// Nodes that loop without a Dialogue, Choice or Call to wait on
+    InstantCycle(Vec<String>),
+    /// A condition or text reads a variable nothing writes
+    ///
+    /// Lua scripts can still write it, hence only a warning.
+    UnwrittenVariable(String),
+}
+
+/// A problem found by [`analyze`] or [`analyze_project`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoryDiagnostic {
+    pub severity: Severity,
+    pub graph_id: String,
+    /// Node the problem is reported at
+    pub node_id: String,
+    pub kind: DiagnosticKind,
+}
+
+impl fmt::Display for StoryDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}: ", self.graph_id, self.node_id)?;
+        match &self.kind {
+            DiagnosticKind::UnreachableNode => write!(f, "node is unreachable"),
+            DiagnosticKind::DeadEnd => write!(f, "flow stops without an End node"),
+            DiagnosticKind::InstantCycle(nodes) => {
+                write!(f, "nodes loop without waiting: {}", nodes.join(" -> "))
+            }
+            DiagnosticKind::UnwrittenVariable(name) => {
+                write!(f, "variable '{}' is read but never written", name)
+            }
+        }
+    }
+}
+
+impl StoryGraphData {
+    /// Run the analyzer on this graph alone.
+    pub fn analyze(&self) -> Vec<StoryDiagnostic> {
+        analyze(self)
+    }
+}
+
+/// Analyze a single graph.
+pub fn analyze(graph: &StoryGraphData) -> Vec<StoryDiagnostic> {
+    analyze_project(std::slice::from_ref(graph))
+}
+
+/// Analyze graphs that run together.
+///
+/// Call entry nodes count as reachable, and variables are shared, so a
+/// variable written in one graph may be read in another.
+pub fn analyze_project(graphs: &[StoryGraphData]) -> Vec<StoryDiagnostic> {
+    let mut entries: HashMap<&str, Vec<&str>> = HashMap::new();
+    let mut written: HashSet<&str> = HashSet::new();
+    for graph in graphs {
+        written.extend(graph.variables.keys().map(String::as_str));
+        written.extend(variables_written(graph));
+        for call in graph.called_graphs() {
+            if let Some(entry) = &call.entry_node {
+                entries.entry(call.graph_id.as_str()).or_default().push(entry);
+            }
+        }
+    }
+
+    let mut diagnostics = Vec::new();
+    for graph in graphs {
+        let diagnostic = |severity, node_id: &str, kind| StoryDiagnostic {
+            severity,
+            graph_id: graph.id.clone(),
+            node_id: node_id.to_string(),
+            kind,
+        };
+
+        let mut roots = vec![graph.root_node_id.as_str()];
+        roots.extend(entries.get(graph.id.as_str()).into_iter().flatten());
+        let reachable = reachable_nodes(graph, &roots);
+        for node in &graph.nodes {
+            if !reachable.contains(node.id.as_str()) {
+                diagnostics.push(diagnostic(Severity::Warning, &node.id, DiagnosticKind::UnreachableNode));
+            }
+            if is_dead_end(&node.data, node.next_node_ids().is_empty()) {
+                diagnostics.push(diagnostic(Severity::Error, &node.id, DiagnosticKind::DeadEnd));
+            }
+        }
+
+        for cycle in instant_cycles(graph) {
+            diagnostics.push(diagnostic(Severity::Error, &cycle[0], DiagnosticKind::InstantCycle(cycle.clone())));
+        }
+
+        let mut reported = HashSet::new();
+        for (node_id, variable) in variables_read(graph) {
+            if !written.contains(variable.as_str()) && reported.insert(variable.clone()) {
+                diagnostics.push(diagnostic(Severity::Warning, node_id, DiagnosticKind::UnwrittenVariable(variable)));
+            }
+        }
+    }
+    diagnostics
+}
+
+/// Nodes reachable from any of `roots`.
+pub(crate) fn reachable_nodes<'a>(graph: &'a StoryGraphData, roots: &[&'a str]) -> HashSet<&'a str> {
+    let edges: HashMap<&str, Vec<&str>> =
+        graph.nodes.iter().map(|n| (n.id.as_str(), n.next_node_ids())).collect();
+    let mut reachable = HashSet::new();
+    let mut stack: Vec<&str> = roots.to_vec();
+    while let Some(id) = stack.pop() {
+        if let Some(next) = edges.get(id) {
+            if reachable.insert(id) {
+                stack.extend(next);
+            }
+        }
+    }
+    reachable
+}
+
+pub(crate) fn is_dead_end(data: &StoryNodeVariant, no_edges: bool) -> bool {
+    no_edges && !matches!(data, StoryNodeVariant::End(_) | StoryNodeVariant::Return(_))
+}
+
+/// Nodes where the executor stops for the frame (or may, for Call),
+/// including camera moves that take time.
+fn waits(data: &StoryNodeVariant) -> bool {
+    match data {
+        StoryNodeVariant::Dialogue(_) | StoryNodeVariant::Choice(_) | StoryNodeVariant::Call(_) => true,
+        StoryNodeVariant::Camera(camera) => camera.duration > 0.0,
+        _ => false,
+    }
+}
+
+/// Strongly connected groups of non-waiting nodes that form a loop, each in
+/// graph order.
+fn instant_cycles(graph: &StoryGraphData) -> Vec<Vec<String>> {
+    let index: HashMap<&str, usize> =
+        graph.nodes.iter().enumerate().map(|(i, n)| (n.id.as_str(), i)).collect();
+    let edges: Vec<Vec<usize>> = graph
+        .nodes
+        .iter()
+        .map(|node| {
+            if waits(&node.data) {
+                return Vec::new();
+            }
+            node.next_node_ids()
+                .into_iter()
+                .filter_map(|id| index.get(id).copied())
+                .filter(|&i| !waits(&graph.nodes[i].data))
+                .collect()
+        })
+        .collect();
+
+    let mut cycles = Vec::new();
+    for mut component in strongly_connected(&edges) {
+        let first = component[0];
+        if component.len() > 1 || edges[first].contains(&first) {
+            component.sort_unstable();
+            cycles.push(component.into_iter().map(|i| graph.nodes[i].id.clone()).collect());
+        }
+    }
+    cycles.sort();
+    cycles
+}
+
+/// Tarjan's algorithm.
+fn strongly_connected(edges: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    struct State<'a> {
+        edges: &'a [Vec<usize>],
+        next_index: usize,
+        index: Vec<Option<usize>>,
+        low: Vec<usize>,
+        stack: Vec<usize>,
+        on_stack: Vec<bool>,
+        components: Vec<Vec<usize>>,
+    }
+
+    fn visit(v: usize, s: &mut State) {
+        s.index[v] = Some(s.next_index);
+        s.low[v] = s.next_index;
+        s.next_index += 1;
+        s.stack.push(v);
+        s.on_stack[v] = true;
+
+        for &w in &s.edges[v] {
+            match s.index[w] {
+                None => {
+                    visit(w, s);
+                    s.low[v] = s.low[v].min(s.low[w]);
+                }
+                Some(index) if s.on_stack[w] => s.low[v] = s.low[v].min(index),
+                Some(_) => {}
+            }
+        }
+
+        if Some(s.low[v]) == s.index[v] {
+            let mut component = Vec::new();
+            while let Some(w) = s.stack.pop() {
+                s.on_stack[w] = false;
+                component.push(w);
+                if w == v {
+                    break;
+                }
+            }
+            s.components.push(component);
+        }
+    }
+
+    let n = edges.len();
+    let mut state = State {
+        edges,
+        next_index: 0,
+        index: vec![None; n],
+        low: vec![0; n],
+        stack: Vec::new(),
+        on_stack: vec![false; n],
+        components: Vec::new(),
+    };
+    for v in 0..n {
+        if state.index[v].is_none() {
+            visit(v, &mut state);
+        }
+    }
+    state.components
+}
+
+/// Variables set by choice and Effect node effects.
+fn variables_written(graph: &StoryGraphData) -> impl Iterator<Item = &str> {
+    graph
+        .nodes
+        .iter()
+        .flat_map(|node| match &node.data {
+            StoryNodeVariant::Choice(c) => c.options.iter().flat_map(|o| &o.effects).collect(),
+            StoryNodeVariant::Effect(e) => e.effects.iter().collect(),
+            _ => Vec::new(),
+        })
+        .filter(|effect| matches!(effect.effect_type, EffectType::SetVar | EffectType::AddVar))
+        .filter_map(|effect| effect.params.get("variable").and_then(|v| v.as_str()))
+}
+
+/// `(node id, variable)` for every condition and `{variable}` in text.
+fn variables_read(graph: &StoryGraphData) -> Vec<(&str, String)> {
+    let mut reads = Vec::new();
+    for node in &graph.nodes {
+        let id = node.id.as_str();
+        let mut texts = Vec::new();
+        match &node.data {
+            StoryNodeVariant::Conditional(c) => reads.push((id, c.condition.variable.clone())),
+            StoryNodeVariant::Dialogue(d) => texts.push(&d.text),
+            StoryNodeVariant::Choice(c) => {
+                texts.push(&c.prompt);
+                for option in &c.options {
+                    reads.extend(option.conditions.iter().map(|c| (id, c.variable.clone())));
+                    texts.push(&option.text);
+                }
+            }
+            _ => {}
+        }
+
+        let mut languages: Vec<_> = texts.into_iter().flat_map(|t| t.iter()).collect();
+        languages.sort();
+        for (_, text) in languages {
+            // Malformed markup is reported by `validate`.
+            for token in markup::parse(text).unwrap_or_default() {
+                if let MarkupToken::Variable(name) = token {
+                    reads.push((id, name));
+                }
+            }
+        }
+    }
+    reads
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::story::{CameraNodeData, ConditionalNodeData, StoryCondition, StoryNodeData};
+
+    fn conditional(id: &str, variable: &str, if_true: &str, if_false: &str) -> StoryNodeData {
+        let mut node = StoryNodeData::end(id);
+        node.data = StoryNodeVariant::Conditional(ConditionalNodeData {
+            condition: StoryCondition {
+                variable: variable.to_string(),
+                operator: Default::default(),
+                value: serde_json::json!(true),
+            },
+            true_target_node_id: if_true.to_string(),
+            false_target_node_id: if_false.to_string(),
+        });
+        node
+    }
+
+    fn kinds(diagnostics: &[StoryDiagnostic]) -> Vec<(&str, &DiagnosticKind)> {
+        diagnostics.iter().map(|d| (d.node_id.as_str(), &d.kind)).collect()
+    }
+
+    #[test]
+    fn test_analyze_graph() {
+        let mut graph = StoryGraphData::new("intro", "Intro");
+        graph.root_node_id = "check".to_string();
+        graph.variables.insert("ready".to_string(), serde_json::json!(false));
+        // "check" and "again" bounce between each other without waiting.
+        graph.add_node(conditional("check", "ready", "end", "again"));
+        graph.add_node(conditional("again", "ready", "check", "again"));
+        graph.add_node(StoryNodeData::dialogue("orphan", "Narrator", "Hi {name}"));
+        graph.add_node(StoryNodeData::end("end"));
+
+        let diagnostics = graph.analyze();
+        assert_eq!(
+            kinds(&diagnostics),
+            vec![
+                ("orphan", &DiagnosticKind::UnreachableNode),
+                ("orphan", &DiagnosticKind::DeadEnd),
+                ("check", &DiagnosticKind::InstantCycle(vec!["check".to_string(), "again".to_string()])),
+                ("orphan", &DiagnosticKind::UnwrittenVariable("name".to_string())),
+            ]
+        );
+        assert_eq!(diagnostics[2].severity, Severity::Error);
+        assert_eq!(diagnostics[3].to_string(), "intro/orphan: variable 'name' is read but never written");
+    }
+
+    #[test]
+    fn test_analyze_project_shares_entries_and_variables() {
+        let mut main = StoryGraphData::new("main", "Main");
+        main.root_node_id = "start".to_string();
+        main.variables.insert("gold".to_string(), serde_json::json!(0));
+        let mut call = StoryNodeData::end("start");
+        call.data = StoryNodeVariant::Call(crate::data::story::CallNodeData {
+            graph_id: "shop".to_string(),
+            entry_node: Some("haggle".to_string()),
+            next_node_id: Some("end".to_string()),
+        });
+        main.add_node(call);
+        main.add_node(StoryNodeData::end("end"));
+
+        let mut shop = StoryGraphData::new("shop", "Shop");
+        shop.root_node_id = "done".to_string();
+        shop.add_node(conditional("haggle", "gold", "done", "done"));
+        shop.add_node(StoryNodeData::end("done"));
+
+        assert!(analyze_project(&[main, shop.clone()]).is_empty());
+        assert_eq!(
+            kinds(&analyze(&shop)),
+            vec![
+                ("haggle", &DiagnosticKind::UnreachableNode),
+                ("haggle", &DiagnosticKind::UnwrittenVariable("gold".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_timed_camera_breaks_cycle() {
+        let camera = |id: &str, duration, next: &str| {
+            let mut node = StoryNodeData::end(id);
+            node.data = StoryNodeVariant::Camera(CameraNodeData {
+                duration,
+                next_node_id: Some(next.to_string()),
+                ..Default::default()
+            });
+            node
+        };
+        // An idle pan back and forth until the player is ready.
+        let mut graph = StoryGraphData::new("idle", "Idle");
+        graph.root_node_id = "pan_left".to_string();
+        graph.variables.insert("ready".to_string(), serde_json::json!(false));
+        graph.add_node(camera("pan_left", 2.0, "pan_right"));
+        graph.add_node(camera("pan_right", 2.0, "check"));
+        graph.add_node(conditional("check", "ready", "end", "pan_left"));
+        graph.add_node(StoryNodeData::end("end"));
+        assert!(graph.analyze().is_empty());
+
+        // Cuts take no time, so looping through them never yields.
+        graph.nodes[0] = camera("pan_left", 0.0, "pan_right");
+        graph.nodes[1] = camera("pan_right", 0.0, "check");
+        let cycle = vec!["pan_left".to_string(), "pan_right".to_string(), "check".to_string()];
+        assert_eq!(kinds(&graph.analyze()), vec![("pan_left", &DiagnosticKind::InstantCycle(cycle))]);
+    }
+}
@@ -3,12 +3,14 @@
 //! The asset index catalogs all game assets (sprites, audio, scripts, etc.)
 //! for quick lookup and validation.
 
+use bevy::prelude::Resource;
 use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
 
 use super::scene::Entity;
 
 /// Audio asset type.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum AudioType {
     /// Background music
@@ -21,7 +23,7 @@ pub enum AudioType {
 }
 
 /// A sprite asset reference.
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
 pub struct SpriteAsset {
     /// Unique asset identifier
     pub id: String,
@@ -50,7 +52,7 @@ impl SpriteAsset {
 }
 
 /// An audio asset reference.
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
 pub struct AudioAsset {
     /// Unique asset identifier
     pub id: String,
@@ -73,7 +75,7 @@ impl AudioAsset {
 }
 
 /// A script asset reference.
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
 pub struct ScriptAsset {
     /// Unique asset identifier
     pub id: String,
@@ -92,7 +94,7 @@ impl ScriptAsset {
 }
 
 /// A prefab (reusable entity template).
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Prefab {
     /// Unique prefab identifier
     pub id: String,
@@ -114,7 +116,7 @@ impl Prefab {
 }
 
 /// A story graph asset reference.
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
 pub struct StoryGraphAsset {
     /// Unique asset identifier
     pub id: String,
@@ -133,7 +135,7 @@ impl StoryGraphAsset {
 }
 
 /// A scene asset reference.
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
 pub struct SceneAsset {
     /// Unique asset identifier
     pub id: String,
@@ -152,7 +154,9 @@ impl SceneAsset {
 }
 
 /// Index of all game assets.
-#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+///
+/// Insert it as a resource so story voice lines can be resolved by id.
+#[derive(Resource, Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema)]
 pub struct AssetIndex {
     /// Sprite assets
     #[serde(default)]
@@ -0,0 +1,328 @@
+//! Loading and saving a whole project at once.
+//!
+//! A [`ProjectBundle`] holds a project together with every file it
+//! references: the scenes and story graphs it lists, the database
+//! (`<paths.database>/database.json`) and the asset index
+//! (`<paths.assets>/asset_index.json`). Paths are relative to the project
+//! root, the directory containing `project.json`. The project, database and
+//! asset index files may use any of the extensions [`DataFormat`] knows
+//! instead, e.g. `project.toml`, and so may the scenes and story graphs the
+//! project lists.
+//!
+//! [`load_project_bundle`] reports every file that failed, not just the
+//! first. [`save_project_bundle`] only writes files whose contents changed.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::assets::AssetIndex;
+use super::database::Database;
+use super::format::DataFormat;
+use super::loader::{
+    is_import, load_asset_index, load_database, load_project, load_scene, load_story_graph, story_graph_to_string,
+    DataError, FileError,
+};
+use super::project::Project;
+use super::scene::Scene;
+use super::story::StoryGraphData;
+
+/// Project file name inside the project root, for JSON projects.
+pub const PROJECT_FILE: &str = "project.json";
+/// Database file name inside the database directory, for JSON databases.
+pub const DATABASE_FILE: &str = "database.json";
+/// Asset index file name inside the assets directory, for JSON indexes.
+pub const ASSET_INDEX_FILE: &str = "asset_index.json";
+
+/// A project and everything it references.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectBundle {
+    /// Directory containing `project.json`
+    pub root: PathBuf,
+    pub project: Project,
+    /// Format of the project file
+    pub project_format: DataFormat,
+    /// Format of the database file
+    pub database_format: DataFormat,
+    /// Format of the asset index file
+    pub asset_index_format: DataFormat,
+    /// Scenes by [`SceneRef`](super::project::SceneRef) id
+    pub scenes: BTreeMap<String, Scene>,
+    /// Story graphs by [`StoryGraphRef`](super::project::StoryGraphRef) id
+    pub story_graphs: BTreeMap<String, StoryGraphData>,
+    /// `None` if the project has no database file
+    pub database: Option<Database>,
+    /// `None` if the project has no asset index file
+    pub asset_index: Option<AssetIndex>,
+}
+
+impl ProjectBundle {
+    /// An empty bundle for `project`, to be saved under `root`.
+    pub fn new(root: impl Into<PathBuf>, project: Project) -> Self {
+        Self {
+            root: root.into(),
+            project,
+            project_format: DataFormat::Json,
+            database_format: DataFormat::Json,
+            asset_index_format: DataFormat::Json,
+            scenes: BTreeMap::new(),
+            story_graphs: BTreeMap::new(),
+            database: None,
+            asset_index: None,
+        }
+    }
+
+    pub fn project_path(&self) -> PathBuf {
+        self.root.join(PROJECT_FILE).with_extension(self.project_format.extension())
+    }
+
+    pub fn database_path(&self) -> PathBuf {
+        self.root
+            .join(&self.project.settings.paths.database)
+            .join(DATABASE_FILE)
+            .with_extension(self.database_format.extension())
+    }
+
+    pub fn asset_index_path(&self) -> PathBuf {
+        self.root
+            .join(&self.project.settings.paths.assets)
+            .join(ASSET_INDEX_FILE)
+            .with_extension(self.asset_index_format.extension())
+    }
+
+    /// Path of a listed scene.
+    pub fn scene_path(&self, id: &str) -> Option<PathBuf> {
+        self.project.find_scene(id).map(|scene| self.root.join(&scene.path))
+    }
+
+    /// Path of a listed story graph.
+    pub fn story_graph_path(&self, id: &str) -> Option<PathBuf> {
+        self.project.find_story_graph(id).map(|graph| self.root.join(&graph.path))
+    }
+}
+
+/// The project file in `root` and its format: `project.json`, or the first
+/// of `project.ron` and `project.toml` that exists.
+pub fn find_project_file(root: &Path) -> Option<(PathBuf, DataFormat)> {
+    find_data_file(root, PROJECT_FILE)
+}
+
+/// `file` in `dir` with the first [`DataFormat`] extension that exists.
+fn find_data_file(dir: &Path, file: &str) -> Option<(PathBuf, DataFormat)> {
+    DataFormat::ALL
+        .into_iter()
+        .map(|format| (dir.join(file).with_extension(format.extension()), format))
+        .find(|(path, _)| path.exists())
+}
+
+/// Load the project in `root` and every file it references.
+///
+/// A missing database or asset index file is not an error; a missing
+/// scene or story graph is.
+///
+/// # Returns
+/// The bundle, or [`DataError::Files`] listing every file that failed
+pub fn load_project_bundle(root: &Path) -> Result<ProjectBundle, DataError> {
+    let (project_path, project_format) =
+        find_project_file(root).unwrap_or_else(|| (root.join(PROJECT_FILE), DataFormat::Json));
+    let project = load_project(&project_path)
+        .map_err(|error| DataError::Files(vec![FileError { path: project_path.clone(), error }]))?;
+    let mut bundle = ProjectBundle::new(root, project);
+    bundle.project_format = project_format;
+    let mut errors = Vec::new();
+    let mut record = |path: PathBuf, error: DataError| {
+        errors.push(match error {
+            DataError::File(error) => *error,
+            error => FileError { path, error },
+        })
+    };
+
+    for scene in &bundle.project.scenes {
+        let path = root.join(&scene.path);
+        match load_scene(&path) {
+            Ok(loaded) => {
+                bundle.scenes.insert(scene.id.clone(), loaded);
+            }
+            Err(error) => record(path, error),
+        }
+    }
+    for graph in &bundle.project.story_graphs {
+        let path = root.join(&graph.path);
+        match load_story_graph(&path) {
+            Ok(loaded) => {
+                bundle.story_graphs.insert(graph.id.clone(), loaded);
+            }
+            Err(error) => record(path, error),
+        }
+    }
+
+    let paths = &bundle.project.settings.paths;
+    if let Some((path, format)) = find_data_file(&root.join(&paths.database), DATABASE_FILE) {
+        bundle.database_format = format;
+        match load_database(&path) {
+            Ok(database) => bundle.database = Some(database),
+            Err(error) => record(path, error),
+        }
+    }
+    if let Some((path, format)) = find_data_file(&root.join(&paths.assets), ASSET_INDEX_FILE) {
+        bundle.asset_index_format = format;
+        match load_asset_index(&path) {
+            Ok(index) => bundle.asset_index = Some(index),
+            Err(error) => record(path, error),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(bundle)
+    } else {
+        Err(DataError::Files(errors))
+    }
+}
+
+/// Save a bundle, writing only files whose contents differ from what is on
+/// disk.
+///
+/// Scenes and story graphs must be listed in the project. Graphs kept as
+/// Twine or Yarn sources are never written.
+///
+/// # Returns
+/// The files written, or [`DataError::Files`] listing every file that
+/// failed; the others are still saved
+pub fn save_project_bundle(bundle: &ProjectBundle) -> Result<Vec<PathBuf>, DataError> {
+    let mut files: Vec<(PathBuf, Result<String, DataError>)> = Vec::new();
+    files.push(serialized(bundle.project_path(), &bundle.project));
+
+    for (id, scene) in &bundle.scenes {
+        match bundle.scene_path(id) {
+            Some(path) => files.push(serialized(path, scene)),
+            None => files.push((bundle.root.join(id), Err(unlisted("scene", id)))),
+        }
+    }
+    for (id, graph) in &bundle.story_graphs {
+        match bundle.story_graph_path(id) {
+            Some(path) if is_import(&path) => {}
+            Some(path) => {
+                let content = story_graph_to_string(graph, &path);
+                files.push((path, content));
+            }
+            None => files.push((bundle.root.join(id), Err(unlisted("story graph", id)))),
+        }
+    }
+    if let Some(database) = &bundle.database {
+        files.push(serialized(bundle.database_path(), database));
+    }
+    if let Some(index) = &bundle.asset_index {
+        files.push(serialized(bundle.asset_index_path(), index));
+    }
+
+    let mut written = Vec::new();
+    let mut errors = Vec::new();
+    for (path, content) in files {
+        match content.and_then(|content| write_if_changed(&path, &content)) {
+            Ok(true) => written.push(path),
+            Ok(false) => {}
+            Err(error) => errors.push(FileError { path, error }),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(written)
+    } else {
+        Err(DataError::Files(errors))
+    }
+}
+
+/// `value` in the format of `path`.
+fn serialized<T: Serialize>(path: PathBuf, value: &T) -> (PathBuf, Result<String, DataError>) {
+    let content = DataFormat::from_path(&path).write(value);
+    (path, content)
+}
+
+fn unlisted(kind: &str, id: &str) -> DataError {
+    DataError::InvalidProject(format!("{} '{}' is not listed in the project", kind, id))
+}
+
+/// Write `content` unless the file already holds it, creating parent
+/// directories as needed.
+fn write_if_changed(path: &Path, content: &str) -> Result<bool, DataError> {
+    if fs::read_to_string(path).is_ok_and(|existing| existing == content) {
+        return Ok(false);
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, content)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bundle(root: &Path) -> ProjectBundle {
+        let mut project = Project::new("Bundle Test");
+        project.add_scene("town", "scenes/town.json");
+        project.add_story_graph("intro", "story_graphs/intro.story");
+        let mut bundle = ProjectBundle::new(root, project);
+        bundle.scenes.insert("town".to_string(), Scene::new("town", "Town"));
+        bundle.story_graphs.insert(
+            "intro".to_string(),
+            crate::data::story_script::compile("intro", "=== hello ===\nGuide: Hi!\nEND\n").unwrap(),
+        );
+        bundle.database = Some(Database::new());
+        bundle
+    }
+
+    #[test]
+    fn test_save_and_load_bundle() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let mut bundle = bundle(root);
+
+        assert_eq!(save_project_bundle(&bundle).unwrap().len(), 4);
+        assert_eq!(load_project_bundle(root).unwrap(), bundle);
+        assert!(save_project_bundle(&bundle).unwrap().is_empty());
+
+        bundle.scenes.get_mut("town").unwrap().name = "Old Town".to_string();
+        assert_eq!(save_project_bundle(&bundle).unwrap(), [root.join("scenes/town.json")]);
+    }
+
+    #[test]
+    fn test_load_bundle_reports_every_error() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let mut bundle = bundle(root);
+        bundle.project.add_scene("cave", "scenes/cave.json");
+        save_project_bundle(&bundle).unwrap();
+        fs::write(root.join("story_graphs/intro.story"), "=== hello ===\n-> nowhere\n").unwrap();
+
+        let Err(DataError::Files(errors)) = load_project_bundle(root) else {
+            panic!("expected file errors");
+        };
+        let failed: Vec<_> = errors.iter().map(|e| e.path.strip_prefix(root).unwrap().to_path_buf()).collect();
+        assert_eq!(failed, [PathBuf::from("scenes/cave.json"), PathBuf::from("story_graphs/intro.story")]);
+        assert!(matches!(errors[0].error, DataError::NotFound(_)));
+        assert!(matches!(errors[1].error, DataError::Script(_)));
+    }
+
+    #[test]
+    fn test_bundle_in_other_formats() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let mut bundle = bundle(root);
+        bundle.project_format = DataFormat::Toml;
+        bundle.database_format = DataFormat::Ron;
+        bundle.asset_index = Some(AssetIndex::new());
+        bundle.asset_index_format = DataFormat::Toml;
+        bundle.project.scenes[0].path = "scenes/town.ron".to_string();
+        save_project_bundle(&bundle).unwrap();
+
+        assert_eq!(find_project_file(root), Some((root.join("project.toml"), DataFormat::Toml)));
+        assert!(bundle.database_path().ends_with("database.ron"));
+        assert!(bundle.asset_index_path().ends_with("asset_index.toml"));
+        assert!(!root.join("project.json").exists());
+        assert!(fs::read_to_string(root.join("scenes/town.ron")).unwrap().contains("name: \"Town\""));
+        assert_eq!(load_project_bundle(root).unwrap(), bundle);
+    }
+}
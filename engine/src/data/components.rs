@@ -4,11 +4,12 @@
 //! They map to Bevy ECS components at runtime via the spawner system.
 
 use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
 use std::collections::HashMap;
 use bevy::prelude::*;
 
 /// 3D vector (used for positions, rotations, scales).
-#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize, JsonSchema, Reflect)]
 pub struct Vec3Data {
     pub x: f32,
     pub y: f32,
@@ -27,7 +28,7 @@ impl Vec3Data {
 }
 
 /// RGBA color with float components.
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema, Reflect)]
 pub struct ColorData {
     pub r: f32,
     pub g: f32,
@@ -63,7 +64,7 @@ impl ColorData {
 }
 
 /// Physics body type.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema, Reflect)]
 #[serde(rename_all = "snake_case")]
 pub enum BodyType {
     /// Does not move, affected by nothing
@@ -76,7 +77,7 @@ pub enum BodyType {
 }
 
 /// Collision shape type.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema, Reflect)]
 #[serde(rename_all = "snake_case")]
 pub enum CollisionShape {
     #[default]
@@ -86,7 +87,7 @@ pub enum CollisionShape {
 }
 
 /// Trigger type for interactive objects.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema, Reflect)]
 #[serde(rename_all = "snake_case")]
 pub enum TriggerType {
     #[default]
@@ -98,7 +99,7 @@ pub enum TriggerType {
 }
 
 /// Tower targeting mode (TD-specific).
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema, Reflect)]
 #[serde(rename_all = "snake_case")]
 pub enum TargetingMode {
     /// Target the enemy that entered first
@@ -113,7 +114,7 @@ pub enum TargetingMode {
 }
 
 /// Animation configuration for sprites.
-#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema, Reflect)]
 pub struct AnimationData {
     /// Animation clip asset ID
     pub clip_id: Option<String>,
@@ -129,7 +130,7 @@ fn default_speed() -> f32 { 1.0 }
 fn default_true() -> bool { true }
 
 /// Transform component data.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Component, Reflect)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema, Component, Reflect)]
 #[reflect(Component)]
 pub struct TransformComponent {
     /// World position
@@ -161,7 +162,7 @@ impl Default for TransformComponent {
 }
 
 /// Sprite/visual appearance component data.
-#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, Component, Reflect)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema, Component, Reflect)]
 #[reflect(Component)]
 pub struct SpriteComponent {
     /// Sprite asset ID
@@ -187,7 +188,7 @@ pub struct SpriteComponent {
 }
 
 /// Collision/physics component data.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Component, Reflect)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema, Component, Reflect)]
 #[reflect(Component)]
 pub struct CollisionComponent {
     /// Whether collision is enabled
@@ -240,7 +241,7 @@ impl Default for CollisionComponent {
 }
 
 /// Event hooks for interactive objects.
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema, Reflect)]
 pub struct InteractivityEvents {
     /// Event/script to run on interaction (E key, click, etc.)
     pub on_interact: Option<String>,
@@ -253,7 +254,7 @@ pub struct InteractivityEvents {
 }
 
 /// Interactivity component data.
-#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, Component, Reflect)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema, Component, Reflect)]
 #[reflect(Component)]
 pub struct InteractivityComponent {
     /// Type of trigger
@@ -278,7 +279,7 @@ pub struct InteractivityComponent {
 pub type LocalizedString = HashMap<String, String>;
 
 /// NPC component data.
-#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, Component, Reflect)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema, Component, Reflect)]
 #[reflect(Component)]
 pub struct NpcComponent {
     /// NPC ID (links to database NpcRow)
@@ -301,7 +302,7 @@ pub struct NpcComponent {
 }
 
 /// Enemy component data.
-#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, Component, Reflect)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema, Component, Reflect)]
 #[reflect(Component)]
 pub struct EnemyComponent {
     /// Enemy ID (links to database EnemyRow)
@@ -318,7 +319,7 @@ pub struct EnemyComponent {
 }
 
 /// Combat stats component data.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Component, Reflect)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema, Component, Reflect)]
 #[reflect(Component)]
 pub struct CombatStatsComponent {
     /// Maximum hit points
@@ -368,7 +369,7 @@ impl Default for CombatStatsComponent {
 }
 
 /// Tower component data (TD-specific).
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Component, Reflect)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema, Component, Reflect)]
 #[reflect(Component)]
 pub struct TowerComponent {
     /// Tower ID (links to database TowerRow)
@@ -423,7 +424,7 @@ impl Default for TowerComponent {
 }
 
 /// Wave definition for spawners.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema, Reflect)]
 pub struct SpawnerWave {
     /// Enemy template ID to spawn
     pub enemy_template_id: String,
@@ -437,7 +438,7 @@ pub struct SpawnerWave {
 fn default_spawn_interval() -> f32 { 1.0 }
 
 /// Spawner component data (TD and JRPG).
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Component, Reflect)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema, Component, Reflect)]
 #[reflect(Component)]
 pub struct SpawnerComponent {
     /// Total number of waves
@@ -473,7 +474,7 @@ impl Default for SpawnerComponent {
 }
 
 /// Audio source component data.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Component, Reflect)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema, Component, Reflect)]
 #[reflect(Component)]
 pub struct AudioSourceComponent {
     /// Audio clip asset ID
@@ -503,7 +504,7 @@ impl Default for AudioSourceComponent {
 }
 
 /// Camera bounds for anchoring.
-#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema, Reflect)]
 pub struct CameraBounds {
     pub min_x: f32,
     pub max_x: f32,
@@ -512,7 +513,7 @@ pub struct CameraBounds {
 }
 
 /// Camera anchor component data.
-#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, Component, Reflect)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema, Component, Reflect)]
 #[reflect(Component)]
 pub struct CameraAnchorComponent {
     /// Camera movement bounds
@@ -524,7 +525,7 @@ pub struct CameraAnchorComponent {
 }
 
 /// Container for all possible entity components.
-#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema, Reflect)]
 pub struct EntityComponents {
     /// Transform (always present)
     pub transform: TransformComponent,
@@ -0,0 +1,538 @@
+//! Branch coverage for story graphs.
+//!
+//! [`explore`] walks every path through a graph, taking each choice option
+//! and both sides of each conditional. Variables the graph seeds (or that
+//! [`CoverageOptions::variables`] pins) have known values that effects
+//! update along the way. Any other variable is unknown: a condition on it
+//! forks the path into the case where it holds and the case where it
+//! doesn't, unless that contradicts what the path already assumed. Loops
+//! are unrolled up to [`CoverageOptions::max_node_visits`] times per path.
+//!
+//! The [`CoverageReport`] lists every path with its assumptions, and marks
+//! each node and choice option as reachable unconditionally, only under
+//! some assumptions, or never. Call nodes are stepped over as if the called
+//! graph changed nothing.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+use super::story::{ConditionOperator, EffectType, StoryCondition, StoryEffect, StoryGraphData, StoryNodeVariant};
+use crate::story_graph::effects::apply_effect;
+use crate::story_graph::variables::compare;
+use crate::story_graph::{StoryValue, StoryVariables};
+
+/// Limits and starting state for [`explore`].
+#[derive(Debug, Clone)]
+pub struct CoverageOptions {
+    /// Times a path may enter the same node; bounds loop unrolling
+    pub max_node_visits: usize,
+    /// Paths to record before giving up
+    pub max_paths: usize,
+    /// Known starting values, overriding the graph's own
+    pub variables: HashMap<String, serde_json::Value>,
+    /// Seeded variables to treat as unknown anyway (e.g. ones the game or
+    /// an earlier graph sets first)
+    pub free: HashSet<String>,
+}
+
+impl Default for CoverageOptions {
+    fn default() -> Self {
+        Self {
+            max_node_visits: 2,
+            max_paths: 10_000,
+            variables: HashMap::new(),
+            free: HashSet::new(),
+        }
+    }
+}
+
+/// A condition on an unknown variable that a path assumed to hold (or not).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assumption {
+    pub condition: StoryCondition,
+    pub holds: bool,
+}
+
+impl fmt::Display for Assumption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let operator = match self.condition.operator {
+            ConditionOperator::Equals => "==",
+            ConditionOperator::NotEquals => "!=",
+            ConditionOperator::LessThan => "<",
+            ConditionOperator::LessThanOrEquals => "<=",
+            ConditionOperator::GreaterThan => ">",
+            ConditionOperator::GreaterThanOrEquals => ">=",
+            ConditionOperator::Contains => "contains",
+        };
+        let negation = if self.holds { "" } else { "not " };
+        write!(f, "{}{} {} {}", negation, self.condition.variable, operator, self.condition.value)
+    }
+}
+
+/// One step of a [`StoryPath`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathStep {
+    Node(String),
+    /// The option picked at a choice node
+    Option { node: String, option: String },
+}
+
+/// Why a [`StoryPath`] stopped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathEnd {
+    /// An End or Return node
+    End,
+    /// A node with nowhere to go next
+    DeadEnd,
+    /// A choice with no option available
+    NoOptions,
+    /// The loop bound was hit
+    LoopLimit,
+    /// A jump to a node that doesn't exist
+    BrokenReference(String),
+}
+
+/// A complete path through a graph.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoryPath {
+    pub steps: Vec<PathStep>,
+    /// What the path assumed about unknown variables, in order
+    pub assumptions: Vec<Assumption>,
+    pub end: PathEnd,
+}
+
+/// Under which assumptions a node or option is reached.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Reachability {
+    #[default]
+    Never,
+    /// Some path reaches it without assuming anything
+    Unconditional,
+    /// Reached only when one of these sets of assumptions holds
+    Requires(Vec<Vec<Assumption>>),
+}
+
+impl Reachability {
+    fn reach(&mut self, assumptions: &[Assumption]) {
+        match self {
+            Reachability::Unconditional => {}
+            _ if assumptions.is_empty() => *self = Reachability::Unconditional,
+            Reachability::Never => *self = Reachability::Requires(vec![assumptions.to_vec()]),
+            Reachability::Requires(sets) => {
+                if !sets.iter().any(|s| s == assumptions) {
+                    sets.push(assumptions.to_vec());
+                }
+            }
+        }
+    }
+}
+
+/// Result of [`explore`].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CoverageReport {
+    pub paths: Vec<StoryPath>,
+    /// Every node in the graph, by id
+    pub nodes: BTreeMap<String, Reachability>,
+    /// Every choice option, by node id and option id
+    pub options: BTreeMap<(String, String), Reachability>,
+    /// Exploration stopped at [`CoverageOptions::max_paths`], so nodes and
+    /// options may be reachable on paths that weren't explored
+    pub truncated: bool,
+}
+
+impl fmt::Display for CoverageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let truncated = if self.truncated { " (truncated)" } else { "" };
+        writeln!(f, "{} paths{}", self.paths.len(), truncated)?;
+
+        let nodes = self.nodes.iter().map(|(id, reach)| (format!("node {}", id), reach));
+        let options = self.options.iter().map(|((node, option), reach)| (format!("option {}/{}", node, option), reach));
+        let entries: Vec<_> = nodes.chain(options).collect();
+        for (label, reach) in &entries {
+            if **reach == Reachability::Never {
+                writeln!(f, "never: {}", label)?;
+            }
+        }
+        for (label, reach) in &entries {
+            if let Reachability::Requires(sets) = reach {
+                let sets: Vec<String> = sets
+                    .iter()
+                    .map(|set| set.iter().map(|a| a.to_string()).collect::<Vec<_>>().join(" and "))
+                    .collect();
+                writeln!(f, "only when {}: {}", sets.join(" or "), label)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl StoryGraphData {
+    /// Explore every path through this graph (see [`explore`]).
+    pub fn explore(&self, options: &CoverageOptions) -> CoverageReport {
+        explore(self, options)
+    }
+}
+
+/// Explore every path through `graph` from its root node.
+pub fn explore(graph: &StoryGraphData, options: &CoverageOptions) -> CoverageReport {
+    let mut report = CoverageReport::default();
+    for node in &graph.nodes {
+        report.nodes.insert(node.id.clone(), Reachability::Never);
+        if let StoryNodeVariant::Choice(choice) = &node.data {
+            for option in &choice.options {
+                report.options.insert((node.id.clone(), option.id.clone()), Reachability::Never);
+            }
+        }
+    }
+
+    let mut state = State::default();
+    for (name, value) in graph.variables.iter().chain(&options.variables) {
+        match StoryValue::from_json(value) {
+            Some(value) if !options.free.contains(name) => state.variables.set(name, value),
+            _ => {
+                state.variables.remove(name);
+            }
+        }
+    }
+    state.variables.drain_changes();
+
+    let mut explorer = Explorer { graph, options, report };
+    explorer.walk(state, &graph.root_node_id);
+    explorer.report
+}
+
+#[derive(Debug, Clone, Default)]
+struct State {
+    variables: StoryVariables,
+    /// Assumptions about each unknown variable
+    constraints: HashMap<String, Vec<Assumption>>,
+    assumptions: Vec<Assumption>,
+    visits: HashMap<String, usize>,
+    steps: Vec<PathStep>,
+}
+
+impl State {
+    fn apply(&mut self, effects: &[StoryEffect]) {
+        for effect in effects {
+            let variable = effect.params.get("variable").and_then(|v| v.as_str());
+            match (effect.effect_type, variable) {
+                // Adding to an unknown value leaves it unknown, and earlier
+                // assumptions no longer describe it.
+                (EffectType::AddVar, Some(name)) if self.variables.get(name).is_none() => {
+                    self.constraints.remove(name);
+                }
+                (_, name) => {
+                    apply_effect(effect, &mut self.variables);
+                    if let Some(name) = name {
+                        self.constraints.remove(name);
+                    }
+                }
+            }
+        }
+        self.variables.drain_changes();
+    }
+
+    /// The ways `condition` can turn out on this path.
+    fn decide(self, condition: &StoryCondition) -> Vec<(bool, State)> {
+        if self.variables.get(&condition.variable).is_some() {
+            let holds = self.variables.evaluate(condition);
+            return vec![(holds, self)];
+        }
+
+        let known = self.constraints.get(&condition.variable).cloned().unwrap_or_default();
+        let outcomes: Vec<(bool, Vec<Assumption>)> = [true, false]
+            .into_iter()
+            .filter_map(|holds| {
+                let mut constraints = known.clone();
+                constraints.push(Assumption { condition: condition.clone(), holds });
+                satisfiable(&constraints).then_some((holds, constraints))
+            })
+            .collect();
+        // An outcome that already follows from earlier assumptions isn't a
+        // new one.
+        if let [(holds, _)] = outcomes.as_slice() {
+            return vec![(*holds, self)];
+        }
+        outcomes
+            .into_iter()
+            .map(|(holds, constraints)| {
+                let mut state = self.clone();
+                state.constraints.insert(condition.variable.clone(), constraints);
+                state.assumptions.push(Assumption { condition: condition.clone(), holds });
+                (holds, state)
+            })
+            .collect()
+    }
+
+    /// Split into the states where all `conditions` hold and those where
+    /// one fails.
+    fn split(self, conditions: &[StoryCondition]) -> (Vec<State>, Vec<State>) {
+        let mut holding = vec![self];
+        let mut failing = Vec::new();
+        for condition in conditions {
+            let mut next = Vec::new();
+            for state in holding {
+                for (holds, state) in state.decide(condition) {
+                    if holds {
+                        next.push(state);
+                    } else {
+                        failing.push(state);
+                    }
+                }
+            }
+            holding = next;
+        }
+        (holding, failing)
+    }
+}
+
+/// Whether some value of a variable meets all `constraints`.
+///
+/// Tries the constants the constraints mention and their neighbours,
+/// which covers every comparison the conditions can make. Integers try
+/// their successors; with float constants, the points between and around
+/// the sorted numbers are tried too.
+fn satisfiable(constraints: &[Assumption]) -> bool {
+    let mut candidates = vec![
+        StoryValue::Int(0),
+        StoryValue::Bool(false),
+        StoryValue::Bool(true),
+        StoryValue::String(String::new()),
+    ];
+    let mut numbers = Vec::new();
+    let mut has_float = false;
+    for assumption in constraints {
+        match StoryValue::from_json(&assumption.condition.value) {
+            Some(StoryValue::Int(i)) => {
+                candidates.extend([i.saturating_sub(1), i, i.saturating_add(1)].map(StoryValue::Int));
+                numbers.push(i as f64);
+            }
+            Some(StoryValue::Float(f)) => {
+                numbers.push(f);
+                has_float = true;
+            }
+            Some(value) => candidates.push(value),
+            None => {}
+        }
+    }
+    if has_float {
+        numbers.sort_by(f64::total_cmp);
+        numbers.dedup();
+        let between = numbers.windows(2).map(|pair| pair[0] + (pair[1] - pair[0]) / 2.0);
+        let around = [numbers[0] - 1.0, numbers[numbers.len() - 1] + 1.0];
+        let floats: Vec<f64> = numbers.iter().copied().chain(between).chain(around).collect();
+        candidates.extend(floats.into_iter().map(StoryValue::Float));
+    }
+    candidates.iter().any(|candidate| {
+        constraints.iter().all(|assumption| {
+            let condition = &assumption.condition;
+            StoryValue::from_json(&condition.value)
+                .is_some_and(|expected| compare(candidate, condition.operator, &expected) == assumption.holds)
+        })
+    })
+}
+
+struct Explorer<'a> {
+    graph: &'a StoryGraphData,
+    options: &'a CoverageOptions,
+    report: CoverageReport,
+}
+
+impl Explorer<'_> {
+    fn walk(&mut self, mut state: State, id: &str) {
+        if self.report.truncated {
+            return;
+        }
+        let Some(node) = self.graph.find_node(id) else {
+            return self.finish(state, PathEnd::BrokenReference(id.to_string()));
+        };
+        let visits = state.visits.entry(id.to_string()).or_default();
+        *visits += 1;
+        if *visits > self.options.max_node_visits {
+            return self.finish(state, PathEnd::LoopLimit);
+        }
+        if let Some(reach) = self.report.nodes.get_mut(id) {
+            reach.reach(&state.assumptions);
+        }
+        state.steps.push(PathStep::Node(id.to_string()));
+
+        match &node.data {
+            StoryNodeVariant::Start(d) => self.follow(state, d.next_node_id.as_deref()),
+            StoryNodeVariant::Dialogue(d) => self.follow(state, d.next_node_id.as_deref()),
+            StoryNodeVariant::Action(a) => self.follow(state, a.next_node_id.as_deref()),
+            StoryNodeVariant::Camera(c) => self.follow(state, c.next_node_id.as_deref()),
+            StoryNodeVariant::TimeControl(t) => self.follow(state, t.next_node_id.as_deref()),
+            StoryNodeVariant::Call(c) => self.follow(state, c.next_node_id.as_deref()),
+            StoryNodeVariant::Effect(e) => {
+                state.apply(&e.effects);
+                self.follow(state, e.next_node_id.as_deref());
+            }
+            StoryNodeVariant::Conditional(c) => {
+                for (holds, state) in state.decide(&c.condition) {
+                    let next = if holds { &c.true_target_node_id } else { &c.false_target_node_id };
+                    self.walk(state, next);
+                }
+            }
+            StoryNodeVariant::Choice(choice) => {
+                for option in &choice.options {
+                    for mut state in state.clone().split(&option.conditions).0 {
+                        if let Some(reach) = self.report.options.get_mut(&(id.to_string(), option.id.clone())) {
+                            reach.reach(&state.assumptions);
+                        }
+                        state.apply(&option.effects);
+                        state.steps.push(PathStep::Option { node: id.to_string(), option: option.id.clone() });
+                        self.walk(state, &option.target_node_id);
+                    }
+                }
+                let mut stuck = vec![state];
+                for option in &choice.options {
+                    stuck = stuck.into_iter().flat_map(|s| s.split(&option.conditions).1).collect();
+                }
+                for state in stuck {
+                    self.finish(state, PathEnd::NoOptions);
+                }
+            }
+            StoryNodeVariant::Return(_) | StoryNodeVariant::End(_) => self.finish(state, PathEnd::End),
+        }
+    }
+
+    fn follow(&mut self, state: State, next: Option<&str>) {
+        match next {
+            Some(next) => self.walk(state, next),
+            None => self.finish(state, PathEnd::DeadEnd),
+        }
+    }
+
+    fn finish(&mut self, state: State, end: PathEnd) {
+        if self.report.paths.len() >= self.options.max_paths {
+            self.report.truncated = true;
+            return;
+        }
+        self.report.paths.push(StoryPath { steps: state.steps, assumptions: state.assumptions, end });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::story_script::compile;
+
+    #[test]
+    fn test_explore_forks_on_unknown_variables() {
+        let graph = compile(
+            "gate",
+            "=== gate ===\n\
+             if gold >= 10 -> rich else -> poor\n\
+             === rich ===\n\
+             if gold < 5 -> impossible else -> done\n\
+             === poor ===\n\
+             Guard: Come back with more gold.\n\
+             -> done\n\
+             === impossible ===\n\
+             Guard: How?\n\
+             -> done\n\
+             === done ===\n\
+             END\n",
+        )
+        .unwrap();
+
+        let report = graph.explore(&CoverageOptions::default());
+        assert_eq!(report.paths.len(), 2);
+        assert_eq!(report.nodes["gate"], Reachability::Unconditional);
+        assert_eq!(report.nodes["done"], Reachability::Requires(vec![
+            vec![Assumption { condition: condition(&graph, "gate"), holds: true }],
+            vec![Assumption { condition: condition(&graph, "gate"), holds: false }],
+        ]));
+        assert_eq!(report.nodes["impossible"], Reachability::Never);
+        let Reachability::Requires(sets) = &report.nodes["poor"] else { panic!() };
+        assert_eq!(sets[0][0].to_string(), "not gold >= 10");
+
+        // Knowing the value leaves only one way through.
+        let options = CoverageOptions {
+            variables: HashMap::from([("gold".to_string(), serde_json::json!(12))]),
+            ..Default::default()
+        };
+        let report = graph.explore(&options);
+        assert_eq!(report.paths.len(), 1);
+        assert_eq!(report.nodes["poor"], Reachability::Never);
+        assert_eq!(report.nodes["done"], Reachability::Unconditional);
+    }
+
+    fn condition(graph: &StoryGraphData, id: &str) -> StoryCondition {
+        let StoryNodeVariant::Conditional(c) = &graph.find_node(id).unwrap().data else { panic!() };
+        c.condition.clone()
+    }
+
+    #[test]
+    fn test_explore_choices_and_loops() {
+        let graph = compile(
+            "shop",
+            "VAR gold = 10\n\
+             === shop ===\n\
+             > What'll it be?\n\
+             * Buy <<if gold >= 5>> <<set gold -= 5>> -> shop\n\
+             * Bribe <<if gold >= 100>> -> END\n\
+             * Leave -> END\n",
+        )
+        .unwrap();
+
+        let options = CoverageOptions::default();
+        let report = graph.explore(&options);
+        assert!(!report.truncated);
+        assert_eq!(report.options[&("shop".to_string(), "1".to_string())], Reachability::Unconditional);
+        assert_eq!(report.options[&("shop".to_string(), "2".to_string())], Reachability::Never);
+        // Buying twice hits the loop bound; leaving after one herb or none ends.
+        let ends: Vec<&PathEnd> = report.paths.iter().map(|p| &p.end).collect();
+        assert_eq!(ends, vec![&PathEnd::LoopLimit, &PathEnd::End, &PathEnd::End]);
+        assert!(report.to_string().contains("never: option shop/2"));
+
+        let truncated = graph.explore(&CoverageOptions { max_paths: 2, ..options });
+        assert!(truncated.truncated);
+        assert_eq!(truncated.paths.len(), 2);
+    }
+
+    #[test]
+    fn test_explore_integer_bounds() {
+        let graph = compile(
+            "bounds",
+            "=== bounds ===\n\
+             if gold >= 9223372036854775807 -> rich else -> done\n\
+             === rich ===\n\
+             if gold < -9223372036854775808 -> impossible else -> done\n\
+             === impossible ===\n\
+             END\n\
+             === done ===\n\
+             END\n",
+        )
+        .unwrap();
+
+        let report = graph.explore(&CoverageOptions::default());
+        assert_eq!(report.nodes["rich"], Reachability::Requires(vec![vec![Assumption {
+            condition: condition(&graph, "bounds"),
+            holds: true,
+        }]]));
+        assert_eq!(report.nodes["impossible"], Reachability::Never);
+    }
+
+    #[test]
+    fn test_explore_narrow_float_range() {
+        let graph = compile(
+            "range",
+            "=== range ===\n\
+             if speed > 1.0 -> fast else -> done\n\
+             === fast ===\n\
+             if speed < 1.2 -> narrow else -> done\n\
+             === narrow ===\n\
+             if speed >= 1.2 -> impossible else -> done\n\
+             === impossible ===\n\
+             END\n\
+             === done ===\n\
+             END\n",
+        )
+        .unwrap();
+
+        let report = graph.explore(&CoverageOptions::default());
+        assert!(matches!(report.nodes["narrow"], Reachability::Requires(_)));
+        assert_eq!(report.nodes["impossible"], Reachability::Never);
+    }
+}
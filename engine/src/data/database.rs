@@ -3,14 +3,19 @@
 //! The database contains static game data that is referenced by entities
 //! and story graphs. Data is stored in JSON and loaded at startup.
 
+use bevy::prelude::Resource;
 use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
 use std::collections::HashMap;
 
+use super::migration::SchemaVersion;
+use super::story::ConditionOperator;
+
 /// Localized string (text in multiple languages).
 pub type LocalizedString = HashMap<String, String>;
 
 /// Item type categorization.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ItemType {
     Weapon,
@@ -23,7 +28,7 @@ pub enum ItemType {
 }
 
 /// Item rarity tier.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum Rarity {
     #[default]
@@ -35,7 +40,7 @@ pub enum Rarity {
 }
 
 /// Script hooks for items.
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
 pub struct ItemScripts {
     /// Script to run when item is used
     #[serde(default)]
@@ -49,7 +54,7 @@ pub struct ItemScripts {
 }
 
 /// An item definition in the database.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct ItemRow {
     /// Unique item identifier
     pub id: String,
@@ -132,7 +137,7 @@ impl ItemRow {
 }
 
 /// An NPC definition in the database.
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
 pub struct NpcRow {
     /// Unique NPC identifier
     pub id: String,
@@ -172,7 +177,7 @@ impl NpcRow {
 }
 
 /// A tower definition in the database (TD-specific).
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct TowerRow {
     /// Unique tower identifier
     pub id: String,
@@ -242,7 +247,7 @@ impl TowerRow {
 }
 
 /// An enemy definition in the database.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct EnemyRow {
     /// Unique enemy identifier
     pub id: String,
@@ -300,7 +305,7 @@ impl EnemyRow {
 }
 
 /// A loot table entry.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct LootEntry {
     /// Item ID to drop
     pub item_id: String,
@@ -320,7 +325,7 @@ fn default_min_qty() -> u32 { 1 }
 fn default_max_qty() -> u32 { 1 }
 
 /// A loot table definition.
-#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema)]
 pub struct LootTableRow {
     /// Unique loot table identifier
     pub id: String,
@@ -350,7 +355,7 @@ impl LootTableRow {
 }
 
 /// Item reward for quests.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct ItemReward {
     /// Item ID
     pub item_id: String,
@@ -358,8 +363,62 @@ pub struct ItemReward {
     pub quantity: u32,
 }
 
+/// Progress of a quest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QuestState {
+    /// Not started yet
+    #[default]
+    Inactive,
+    Active,
+    Completed,
+    Failed,
+}
+
+/// A condition for starting or completing a quest.
+///
+/// Completion conditions are the quest's objectives.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum QuestCondition {
+    /// The player carries at least `quantity` of an item
+    HasItem {
+        item_id: String,
+        #[serde(default = "default_quantity")]
+        quantity: u32,
+    },
+    /// A story variable compares to `value`
+    Variable {
+        variable: String,
+        #[serde(default)]
+        operator: ConditionOperator,
+        value: serde_json::Value,
+    },
+    /// A story flag has `value`
+    Flag {
+        flag: String,
+        #[serde(default = "default_true")]
+        value: bool,
+    },
+    /// Another quest is in `state`
+    Quest { quest_id: String, state: QuestState },
+    /// A condition the engine doesn't evaluate, kept as written; it never
+    /// holds. Migration wraps the free-form conditions of older databases
+    /// in one.
+    Custom { condition: serde_json::Value },
+}
+
+
+fn default_quantity() -> u32 {
+    1
+}
+
+fn default_true() -> bool {
+    true
+}
+
 /// Quest rewards.
-#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema)]
 pub struct QuestRewards {
     /// Gold reward
     #[serde(default)]
@@ -376,7 +435,7 @@ pub struct QuestRewards {
 }
 
 /// A quest definition.
-#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema)]
 pub struct QuestRow {
     /// Unique quest identifier
     pub id: String,
@@ -385,12 +444,13 @@ pub struct QuestRow {
     /// Description per language
     #[serde(default)]
     pub description: LocalizedString,
-    /// Conditions to start the quest
+    /// Conditions that start the quest once all hold; with none it only
+    /// starts through a `set_quest_state` effect
     #[serde(default)]
-    pub start_conditions: Vec<serde_json::Value>,
-    /// Conditions to complete the quest
+    pub start_conditions: Vec<QuestCondition>,
+    /// Objectives; the quest completes once all hold
     #[serde(default)]
-    pub completion_conditions: Vec<serde_json::Value>,
+    pub completion_conditions: Vec<QuestCondition>,
     /// Rewards on completion
     #[serde(default)]
     pub rewards: QuestRewards,
@@ -410,8 +470,13 @@ impl QuestRow {
 }
 
 /// The complete game database.
-#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+///
+/// Insert it as a resource to make row names available to story markup.
+#[derive(Resource, Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema)]
 pub struct Database {
+    /// Schema version; loading upgrades older files to the current one
+    #[serde(default)]
+    pub schema_version: SchemaVersion,
     /// Item definitions
     #[serde(default)]
     pub items: Vec<ItemRow>,
@@ -487,6 +552,40 @@ mod tests {
         assert!(parsed.find_item("sword_01").is_some());
     }
 
+    #[test]
+    fn test_quest_conditions() {
+        let quest: QuestRow = serde_json::from_value(serde_json::json!({
+            "id": "quest_fetch_herbs",
+            "name": { "en": "Lost Herbs" },
+            "start_conditions": [{ "type": "flag", "flag": "met_merchant" }],
+            "completion_conditions": [
+                { "type": "has_item", "item_id": "quest_herbs" },
+                { "type": "variable", "variable": "gold", "operator": "greater_than", "value": 10 },
+                { "type": "quest", "quest_id": "quest_intro", "state": "completed" }
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(quest.start_conditions, [QuestCondition::Flag { flag: "met_merchant".to_string(), value: true }]);
+        assert_eq!(
+            quest.completion_conditions[0],
+            QuestCondition::HasItem { item_id: "quest_herbs".to_string(), quantity: 1 }
+        );
+        assert_eq!(
+            quest.completion_conditions[2],
+            QuestCondition::Quest { quest_id: "quest_intro".to_string(), state: QuestState::Completed }
+        );
+
+        // Misspelled types are errors rather than conditions that never hold.
+        let typo = serde_json::json!({ "type": "has_itme", "item_id": "quest_herbs" });
+        assert!(serde_json::from_value::<QuestCondition>(typo).is_err());
+
+        let custom = serde_json::json!({ "type": "custom", "condition": { "type": "teleport", "to": "town" } });
+        let condition: QuestCondition = serde_json::from_value(custom.clone()).unwrap();
+        assert_eq!(condition, QuestCondition::Custom { condition: custom["condition"].clone() });
+        assert_eq!(serde_json::to_value(&condition).unwrap(), custom);
+    }
+
     #[test]
     fn test_loot_table() {
         let mut loot = LootTableRow::new("common_loot");
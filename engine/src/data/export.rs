@@ -0,0 +1,362 @@
+//! Story graph diagrams for Graphviz and Mermaid.
+//!
+//! [`to_dot`] and [`to_mermaid`] draw one box per node and one arrow per
+//! transition. Boxes and arrows are colored by the type of node they leave
+//! from; choice arrows carry the option text and conditions, conditional
+//! arrows the condition. With [`ExportOptions::collapse_dialogue`], runs of
+//! dialogue lines that can only be entered from the top are drawn as one
+//! box.
+//!
+//! Texts are shown in the default language, conditions and effects in
+//! [`story_script`](super::story_script) syntax.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use super::story::{StoryGraphData, StoryNodeData, StoryNodeType, StoryNodeVariant};
+use super::story_script::{line_text, print_condition, print_effect};
+
+/// Diagram language to export to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Dot,
+    Mermaid,
+}
+
+impl ExportFormat {
+    /// Parse `dot` or `mermaid`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "dot" => Some(ExportFormat::Dot),
+            "mermaid" => Some(ExportFormat::Mermaid),
+            _ => None,
+        }
+    }
+
+    /// File extension for diagrams in this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Dot => "dot",
+            ExportFormat::Mermaid => "mmd",
+        }
+    }
+}
+
+/// Options for [`export`].
+#[derive(Debug, Clone, Default)]
+pub struct ExportOptions {
+    /// Draw linear runs of dialogue as a single box
+    pub collapse_dialogue: bool,
+}
+
+/// Longest text shown for a line before it is cut off.
+const MAX_TEXT: usize = 60;
+
+/// Export a graph in the given format.
+pub fn export(graph: &StoryGraphData, format: ExportFormat, options: &ExportOptions) -> String {
+    match format {
+        ExportFormat::Dot => to_dot(graph, options),
+        ExportFormat::Mermaid => to_mermaid(graph, options),
+    }
+}
+
+/// Export a graph as a Graphviz `digraph`.
+pub fn to_dot(graph: &StoryGraphData, options: &ExportOptions) -> String {
+    let diagram = Diagram::new(graph, options);
+    let mut out = String::new();
+    writeln!(out, "digraph {} {{", dot_string(&graph.id)).unwrap();
+    writeln!(out, "    node [shape=box, style=rounded];").unwrap();
+    for block in &diagram.blocks {
+        writeln!(
+            out,
+            "    {} [label={}, color=\"{}\"];",
+            dot_string(&block.id),
+            dot_string(&block.lines.join("\n")),
+            color(block.node_type)
+        )
+        .unwrap();
+    }
+    for edge in &diagram.edges {
+        let label = match &edge.label {
+            Some(label) => format!("label={}, ", dot_string(label)),
+            None => String::new(),
+        };
+        writeln!(
+            out,
+            "    {} -> {} [{}color=\"{}\"];",
+            dot_string(&edge.from),
+            dot_string(&edge.to),
+            label,
+            color(edge.node_type)
+        )
+        .unwrap();
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Export a graph as a Mermaid flowchart.
+pub fn to_mermaid(graph: &StoryGraphData, options: &ExportOptions) -> String {
+    let diagram = Diagram::new(graph, options);
+    // Mermaid ids can't contain most punctuation, so number the boxes.
+    let mut ids: HashMap<&str, String> = HashMap::new();
+    for block in &diagram.blocks {
+        let n = ids.len();
+        ids.insert(&block.id, format!("n{}", n));
+    }
+
+    let mut out = String::from("flowchart TD\n");
+    for block in &diagram.blocks {
+        writeln!(out, "    {}[\"{}\"]", ids[block.id.as_str()], mermaid_text(&block.lines.join("\n"))).unwrap();
+    }
+    for edge in &diagram.edges {
+        if !ids.contains_key(edge.to.as_str()) {
+            let n = ids.len();
+            ids.insert(&edge.to, format!("n{}", n));
+            writeln!(out, "    {}[\"{} (missing)\"]", ids[edge.to.as_str()], mermaid_text(&edge.to)).unwrap();
+        }
+        let arrow = match &edge.label {
+            Some(label) => format!("-->|\"{}\"|", mermaid_text(label)),
+            None => "-->".to_string(),
+        };
+        writeln!(out, "    {} {} {}", ids[edge.from.as_str()], arrow, ids[edge.to.as_str()]).unwrap();
+    }
+    for block in &diagram.blocks {
+        writeln!(out, "    style {} stroke:{}", ids[block.id.as_str()], color(block.node_type)).unwrap();
+    }
+    for (n, edge) in diagram.edges.iter().enumerate() {
+        writeln!(out, "    linkStyle {} stroke:{}", n, color(edge.node_type)).unwrap();
+    }
+    out
+}
+
+/// Color for boxes of, and arrows leaving, a node type.
+fn color(node_type: StoryNodeType) -> &'static str {
+    match node_type {
+        StoryNodeType::Start => "#2e7d32",
+        StoryNodeType::Dialogue => "#1565c0",
+        StoryNodeType::Choice => "#ef6c00",
+        StoryNodeType::Action => "#6a1b9a",
+        StoryNodeType::Conditional => "#c62828",
+        StoryNodeType::Effect => "#00897b",
+        StoryNodeType::Camera => "#5d4037",
+        StoryNodeType::TimeControl => "#546e7a",
+        StoryNodeType::Call => "#283593",
+        StoryNodeType::Return => "#757575",
+        StoryNodeType::End => "#212121",
+    }
+}
+
+fn dot_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
+
+fn mermaid_text(text: &str) -> String {
+    text.replace('"', "#quot;").replace('<', "#lt;").replace('>', "#gt;").replace('\n', "<br/>")
+}
+
+/// One box: a node, or a run of dialogue named after its first node.
+struct Block {
+    id: String,
+    node_type: StoryNodeType,
+    lines: Vec<String>,
+}
+
+struct Edge {
+    from: String,
+    to: String,
+    label: Option<String>,
+    node_type: StoryNodeType,
+}
+
+struct Diagram {
+    blocks: Vec<Block>,
+    edges: Vec<Edge>,
+}
+
+impl Diagram {
+    fn new(graph: &StoryGraphData, options: &ExportOptions) -> Self {
+        let mut incoming: HashMap<&str, usize> = HashMap::new();
+        for node in &graph.nodes {
+            for next in node.next_node_ids() {
+                *incoming.entry(next).or_default() += 1;
+            }
+        }
+
+        // The dialogue node each dialogue node continues, when collapsing.
+        let continues = |node: &StoryNodeData| -> Option<&str> {
+            let StoryNodeVariant::Dialogue(d) = &node.data else { return None };
+            let next = graph.find_node(d.next_node_id.as_deref()?)?;
+            let mergeable = options.collapse_dialogue
+                && matches!(next.data, StoryNodeVariant::Dialogue(_))
+                && incoming.get(next.id.as_str()) == Some(&1)
+                && next.id != graph.root_node_id
+                && next.id != node.id;
+            mergeable.then_some(next.id.as_str())
+        };
+        let mut merged: HashMap<&str, &str> =
+            graph.nodes.iter().filter_map(|node| Some((continues(node)?, node.id.as_str()))).collect();
+        // A loop of single-entry lines continues into itself all the way
+        // round; its first node in graph order heads the block instead.
+        for node in &graph.nodes {
+            let mut id = node.id.as_str();
+            for _ in 0..graph.nodes.len() {
+                match merged.get(id) {
+                    Some(&previous) if previous == node.id => {
+                        merged.remove(node.id.as_str());
+                        break;
+                    }
+                    Some(&previous) => id = previous,
+                    None => break,
+                }
+            }
+        }
+
+        let mut diagram = Diagram { blocks: Vec::new(), edges: Vec::new() };
+        for node in graph.nodes.iter().filter(|node| !merged.contains_key(node.id.as_str())) {
+            let mut lines = vec![node.id.clone()];
+            let mut last = node;
+            lines.extend(summary(last));
+            while let Some(next) = continues(last).and_then(|id| graph.find_node(id)) {
+                // A loop of lines ends where it comes back round.
+                if next.id == node.id {
+                    break;
+                }
+                last = next;
+                lines.extend(summary(last));
+            }
+            diagram.blocks.push(Block { id: node.id.clone(), node_type: node.node_type(), lines });
+            diagram.add_edges(&node.id, last);
+        }
+        diagram
+    }
+
+    fn add_edges(&mut self, from: &str, node: &StoryNodeData) {
+        let mut edge = |to: &str, label: Option<String>| {
+            self.edges.push(Edge {
+                from: from.to_string(),
+                to: to.to_string(),
+                label,
+                node_type: node.node_type(),
+            });
+        };
+        match &node.data {
+            StoryNodeVariant::Choice(c) => {
+                for option in &c.options {
+                    let mut label = shorten(line_text(&option.text));
+                    if !option.conditions.is_empty() {
+                        let conditions: Vec<String> = option.conditions.iter().map(print_condition).collect();
+                        label.push_str(&format!(" [{}]", conditions.join(", ")));
+                    }
+                    edge(&option.target_node_id, Some(label));
+                }
+            }
+            StoryNodeVariant::Conditional(c) => {
+                edge(&c.true_target_node_id, Some(print_condition(&c.condition)));
+                edge(&c.false_target_node_id, Some("else".to_string()));
+            }
+            _ => {
+                for next in node.next_node_ids() {
+                    edge(next, None);
+                }
+            }
+        }
+    }
+}
+
+/// Lines describing a node's content.
+fn summary(node: &StoryNodeData) -> Vec<String> {
+    match &node.data {
+        StoryNodeVariant::Start(_) => vec!["start".to_string()],
+        StoryNodeVariant::Dialogue(d) => vec![format!("{}: {}", d.speaker_id, shorten(line_text(&d.text)))],
+        StoryNodeVariant::Choice(c) => {
+            let prompt = line_text(&c.prompt);
+            let mut line = if prompt.is_empty() { "choice".to_string() } else { shorten(prompt) };
+            if let Some(timeout) = c.timeout {
+                line.push_str(&format!(" ({}s)", timeout));
+            }
+            vec![line]
+        }
+        StoryNodeVariant::Action(a) => vec![format!("action {}", a.lua_script_id)],
+        StoryNodeVariant::Conditional(c) => vec![format!("if {}", print_condition(&c.condition))],
+        StoryNodeVariant::Effect(e) => e
+            .effects
+            .iter()
+            .map(|effect| print_effect(effect).unwrap_or_else(|| format!("{:?}", effect.effect_type)))
+            .collect(),
+        StoryNodeVariant::Camera(_) => vec!["camera".to_string()],
+        StoryNodeVariant::TimeControl(_) => vec!["time control".to_string()],
+        StoryNodeVariant::Call(c) => match &c.entry_node {
+            Some(entry) => vec![format!("call {}:{}", c.graph_id, entry)],
+            None => vec![format!("call {}", c.graph_id)],
+        },
+        StoryNodeVariant::Return(_) => vec!["return".to_string()],
+        StoryNodeVariant::End(_) => vec!["end".to_string()],
+    }
+}
+
+fn shorten(text: &str) -> String {
+    match text.char_indices().nth(MAX_TEXT) {
+        Some((cut, _)) => format!("{}...", &text[..cut]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::story_script::compile;
+
+    fn graph() -> StoryGraphData {
+        compile(
+            "shop",
+            "=== shop ===\n\
+             Merchant: Welcome!\n\
+             Merchant: Take a \"look\".\n\
+             > What'll it be?\n\
+             * Buy <<if gold >= 5>> <<set gold -= 5>> -> shop\n\
+             * Leave -> END\n",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let dot = to_dot(&graph(), &ExportOptions::default());
+        assert!(dot.starts_with("digraph \"shop\" {\n"));
+        assert!(dot.contains("\"shop.1\" [label=\"shop.1\\nMerchant: Take a \\\"look\\\".\", color=\"#1565c0\"];"));
+        assert!(dot.contains("\"shop\" -> \"shop.1\" [color=\"#1565c0\"];"));
+        assert!(dot.contains("\"shop.2\" -> \"shop\" [label=\"Buy [gold >= 5]\", color=\"#ef6c00\"];"));
+
+        let collapsed = to_dot(&graph(), &ExportOptions { collapse_dialogue: true });
+        assert!(collapsed.contains("[label=\"shop\\nMerchant: Welcome!\\nMerchant: Take a \\\"look\\\".\""));
+        assert!(!collapsed.contains("\"shop.1\""));
+        assert!(collapsed.contains("\"shop\" -> \"shop.2\" [color=\"#1565c0\"];"));
+    }
+
+    #[test]
+    fn test_to_mermaid() {
+        let mermaid = to_mermaid(&graph(), &ExportOptions { collapse_dialogue: true });
+        assert!(mermaid.starts_with("flowchart TD\n    n0[\"shop<br/>Merchant: Welcome!<br/>Merchant: Take a #quot;look#quot;.\"]\n"));
+        assert!(mermaid.contains("    n1 -->|\"Buy [gold #gt;= 5]\"| n0\n"));
+        assert!(mermaid.contains("    linkStyle 0 stroke:#1565c0\n"));
+    }
+
+    #[test]
+    fn test_collapse_unreachable_dialogue_loop() {
+        let mut graph = StoryGraphData::new("echo", "Echo");
+        graph.root_node_id = "end".to_string();
+        graph.add_node(StoryNodeData::end("end"));
+        for (id, text, next) in [("ping", "Ping?", "pong"), ("pong", "Pong!", "ping")] {
+            let mut node = StoryNodeData::dialogue(id, "Echo", text);
+            let StoryNodeVariant::Dialogue(d) = &mut node.data else { unreachable!() };
+            d.next_node_id = Some(next.to_string());
+            graph.add_node(node);
+        }
+
+        let dot = to_dot(&graph, &ExportOptions { collapse_dialogue: true });
+        assert!(dot.contains("\"ping\" [label=\"ping\\nEcho: Ping?\\nEcho: Pong!\""));
+        assert!(dot.contains("\"ping\" -> \"ping\""));
+        assert!(!dot.contains("\"pong\""));
+    }
+}
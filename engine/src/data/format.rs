@@ -0,0 +1,708 @@
+//! On-disk formats for data files.
+//!
+//! Projects, scenes, databases, story graphs and asset indexes can be
+//! stored as JSON, RON or TOML; the loaders and savers in
+//! [`loader`](super::loader) pick the format from the file extension.
+//! Files with any other extension are JSON.
+//!
+//! Every format is read into the same JSON data model before migration and
+//! deserialization, so the field names, the `schema_version` handling,
+//! lenient parsing and the [JSON Schemas](super::schema) are the same for
+//! all of them. RON files are written with struct syntax and bare enum
+//! variants, e.g. `(id: "gate", entity_type: trigger)`. Reading drops struct
+//! names, so `Scene(...)` and `(...)` are the same, and a bare variant reads
+//! as its name. TOML has no null: unset optional fields are left out, but a
+//! `None` in an array or a null inside a JSON value can't be written.
+//!
+//! Errors in every format carry a line and column and a JSON pointer to the
+//! value that failed.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Number, Value};
+use std::collections::HashMap;
+use std::path::Path;
+use toml_edit::{ImDocument, Item, Table};
+
+use super::loader::DataError;
+use super::parse::{deserialize, locate, ParseError};
+
+/// A data file format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DataFormat {
+    #[default]
+    Json,
+    Ron,
+    Toml,
+}
+
+impl DataFormat {
+    pub const ALL: [DataFormat; 3] = [DataFormat::Json, DataFormat::Ron, DataFormat::Toml];
+
+    /// The format of `path`, by extension.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("ron") => DataFormat::Ron,
+            Some("toml") => DataFormat::Toml,
+            _ => DataFormat::Json,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            DataFormat::Json => "json",
+            DataFormat::Ron => "ron",
+            DataFormat::Toml => "toml",
+        }
+    }
+
+    /// Read a file's contents into the JSON data model.
+    pub fn read(self, content: &str) -> Result<Value, ParseError> {
+        self.parse(content).map(|parsed| parsed.value)
+    }
+
+    /// Read a file's contents, keeping where each value is.
+    pub(crate) fn parse(self, content: &str) -> Result<Parsed<'_>, ParseError> {
+        match self {
+            DataFormat::Json => {
+                let value = serde_json::from_str(content).map_err(|e| {
+                    ParseError::syntax(Some(e.line()), Some(e.column()), strip_location(e.to_string()))
+                })?;
+                Ok(Parsed { value, content, offsets: None })
+            }
+            DataFormat::Ron => {
+                let mut reader = RonReader { content, pos: 0, offsets: HashMap::new() };
+                let value = reader.document()?;
+                Ok(Parsed { value, content, offsets: Some(reader.offsets) })
+            }
+            DataFormat::Toml => {
+                let document = ImDocument::parse(content).map_err(|e| {
+                    let (line, column) = e.span().map(|span| line_column(content, span.start)).unzip();
+                    ParseError::syntax(line, column, e.message().to_string())
+                })?;
+                let mut reader = TomlReader { content, offsets: HashMap::from([(String::new(), 0)]) };
+                let value = reader.table(document.as_table(), "")?;
+                Ok(Parsed { value, content, offsets: Some(reader.offsets) })
+            }
+        }
+    }
+
+    /// Write `value` in this format.
+    pub fn write<T: Serialize>(self, value: &T) -> Result<String, DataError> {
+        match self {
+            DataFormat::Json => Ok(serde_json::to_string_pretty(value)?),
+            DataFormat::Ron => Ok(ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())? + "\n"),
+            DataFormat::Toml => Ok(toml::to_string_pretty(value)?),
+        }
+    }
+}
+
+/// A file read into the JSON data model.
+pub(crate) struct Parsed<'a> {
+    pub value: Value,
+    content: &'a str,
+    /// Byte offset of each value by JSON pointer; JSON files are scanned
+    /// with [`locate`] instead.
+    offsets: Option<HashMap<String, usize>>,
+}
+
+impl Parsed<'_> {
+    /// Deserialize the value.
+    pub fn deserialize<T: DeserializeOwned>(&self) -> Result<T, ParseError> {
+        deserialize(self.value.clone()).map_err(|(pointer, message)| self.error(pointer, message))
+    }
+
+    /// An error at `pointer`, located at the value or its deepest ancestor
+    /// in the file.
+    pub fn error(&self, pointer: String, message: String) -> ParseError {
+        let location = match &self.offsets {
+            None => locate(self.content, &pointer),
+            Some(offsets) => {
+                let mut prefix = pointer.as_str();
+                loop {
+                    if let Some(&offset) = offsets.get(prefix) {
+                        break Some(line_column(self.content, offset));
+                    }
+                    match prefix.rsplit_once('/') {
+                        Some((parent, _)) => prefix = parent,
+                        None => break None,
+                    }
+                }
+            }
+        };
+        let (line, column) = location.unzip();
+        ParseError { path: None, line, column, pointer, message }
+    }
+}
+
+/// serde_json appends the location to its messages; it's reported separately.
+fn strip_location(message: String) -> String {
+    match message.rsplit_once(" at line ") {
+        Some((message, _)) => message.to_string(),
+        None => message,
+    }
+}
+
+/// 1-based line and column of a byte offset.
+pub(crate) fn line_column(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap_or_default().chars().count() + 1;
+    (line, column)
+}
+
+fn pointer_token(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Reads TOML into the JSON data model, recording where each value starts.
+/// Tables that only appear in a dotted header, like `a` in `[a.b]`, have
+/// no position of their own.
+struct TomlReader<'a> {
+    content: &'a str,
+    offsets: HashMap<String, usize>,
+}
+
+impl TomlReader<'_> {
+    fn item(&mut self, item: &Item, pointer: String) -> Result<Value, ParseError> {
+        if let Some(span) = item.span() {
+            self.offsets.insert(pointer.clone(), span.start);
+        }
+        match item {
+            Item::None => Ok(Value::Null),
+            Item::Value(value) => self.value(value, pointer),
+            Item::Table(table) => self.table(table, &pointer),
+            Item::ArrayOfTables(tables) => {
+                let mut items = Vec::new();
+                for table in tables.iter() {
+                    let pointer = format!("{}/{}", pointer, items.len());
+                    if let Some(span) = table.span() {
+                        self.offsets.insert(pointer.clone(), span.start);
+                    }
+                    items.push(self.table(table, &pointer)?);
+                }
+                Ok(Value::Array(items))
+            }
+        }
+    }
+
+    fn table(&mut self, table: &Table, pointer: &str) -> Result<Value, ParseError> {
+        let mut object = Map::new();
+        for (key, item) in table.iter() {
+            let value = self.item(item, format!("{}/{}", pointer, pointer_token(key)))?;
+            object.insert(key.to_string(), value);
+        }
+        Ok(Value::Object(object))
+    }
+
+    fn value(&mut self, value: &toml_edit::Value, pointer: String) -> Result<Value, ParseError> {
+        let start = value.span().map(|span| span.start);
+        if let Some(start) = start {
+            self.offsets.insert(pointer.clone(), start);
+        }
+        Ok(match value {
+            toml_edit::Value::String(string) => Value::from(string.value().as_str()),
+            toml_edit::Value::Integer(integer) => Value::from(*integer.value()),
+            toml_edit::Value::Float(float) => match Number::from_f64(*float.value()) {
+                Some(number) => Value::Number(number),
+                None => {
+                    let (line, column) = start.map(|start| line_column(self.content, start)).unzip();
+                    let message = format!("{} can't be stored", float.value());
+                    return Err(ParseError { pointer, ..ParseError::syntax(line, column, message) });
+                }
+            },
+            toml_edit::Value::Boolean(boolean) => Value::from(*boolean.value()),
+            toml_edit::Value::Datetime(datetime) => Value::from(datetime.value().to_string()),
+            toml_edit::Value::Array(array) => {
+                let mut items = Vec::new();
+                for value in array.iter() {
+                    items.push(self.value(value, format!("{}/{}", pointer, items.len()))?);
+                }
+                Value::Array(items)
+            }
+            toml_edit::Value::InlineTable(table) => {
+                let mut object = Map::new();
+                for (key, value) in table.iter() {
+                    let value = self.value(value, format!("{}/{}", pointer, pointer_token(key)))?;
+                    object.insert(key.to_string(), value);
+                }
+                Value::Object(object)
+            }
+        })
+    }
+}
+
+/// Reads RON into the JSON data model, recording where each value starts.
+///
+/// Structs and maps become objects, tuples and lists arrays, `()` and
+/// `None` null, and a bare enum variant its name. `(x)` is a newtype and
+/// reads as `x`.
+struct RonReader<'a> {
+    content: &'a str,
+    pos: usize,
+    offsets: HashMap<String, usize>,
+}
+
+impl<'a> RonReader<'a> {
+    fn document(&mut self) -> Result<Value, ParseError> {
+        self.whitespace()?;
+        // Extensions, e.g. `#![enable(implicit_some)]`
+        while self.rest().starts_with("#!") {
+            let end = self.rest().find(']').ok_or_else(|| self.error("unclosed attribute"))?;
+            self.pos += end + 1;
+            self.whitespace()?;
+        }
+        let value = self.value(Some(""))?;
+        self.whitespace()?;
+        if self.pos < self.content.len() {
+            return Err(self.error("trailing characters"));
+        }
+        Ok(value)
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.content[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        let (line, column) = line_column(self.content, self.pos);
+        ParseError::syntax(Some(line), Some(column), message.into())
+    }
+
+    /// Skip whitespace and comments.
+    fn whitespace(&mut self) -> Result<(), ParseError> {
+        loop {
+            let rest = self.rest();
+            self.pos += rest.len() - rest.trim_start().len();
+            let rest = self.rest();
+            if rest.starts_with("//") {
+                self.pos += rest.find('\n').unwrap_or(rest.len());
+            } else if rest.starts_with("/*") {
+                // Block comments nest.
+                let mut depth = 0;
+                loop {
+                    let rest = self.rest();
+                    if rest.starts_with("/*") {
+                        depth += 1;
+                        self.pos += 2;
+                    } else if rest.starts_with("*/") {
+                        depth -= 1;
+                        self.pos += 2;
+                        if depth == 0 {
+                            break;
+                        }
+                    } else if let Some(c) = rest.chars().next() {
+                        self.pos += c.len_utf8();
+                    } else {
+                        return Err(self.error("unclosed comment"));
+                    }
+                }
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ParseError> {
+        self.whitespace()?;
+        if self.peek() != Some(expected) {
+            return Err(self.error(format!("expected `{}`", expected)));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    /// Consume `close` if it's next.
+    fn close(&mut self, close: char) -> Result<bool, ParseError> {
+        self.whitespace()?;
+        match self.peek() {
+            Some(c) if c == close => {
+                self.pos += 1;
+                Ok(true)
+            }
+            Some(_) => Ok(false),
+            None => Err(self.error(format!("expected `{}`", close))),
+        }
+    }
+
+    /// Consume the comma after an element, returning whether there was one.
+    fn separator(&mut self, close: char) -> Result<bool, ParseError> {
+        self.whitespace()?;
+        match self.peek() {
+            Some(',') => {
+                self.pos += 1;
+                Ok(true)
+            }
+            Some(c) if c == close => Ok(false),
+            _ => Err(self.error(format!("expected `,` or `{}`", close))),
+        }
+    }
+
+    /// Read a value at `pointer`, or a map key when it's `None`.
+    fn value(&mut self, pointer: Option<&str>) -> Result<Value, ParseError> {
+        self.whitespace()?;
+        if let Some(pointer) = pointer {
+            self.offsets.entry(pointer.to_string()).or_insert(self.pos);
+        }
+        let rest = self.rest();
+        match self.peek() {
+            Some('"' | '\'') => self.string().map(Value::String),
+            Some('r') if rest[1..].starts_with(['"', '#']) => self.raw_string().map(Value::String),
+            Some('[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                while !self.close(']')? {
+                    let child = pointer.map(|pointer| format!("{}/{}", pointer, items.len()));
+                    items.push(self.value(child.as_deref())?);
+                    self.separator(']')?;
+                }
+                Ok(Value::Array(items))
+            }
+            Some('{') => {
+                self.pos += 1;
+                let mut object = Map::new();
+                while !self.close('}')? {
+                    let key = match self.value(None)? {
+                        Value::String(key) => key,
+                        key @ (Value::Number(_) | Value::Bool(_)) => key.to_string(),
+                        _ => return Err(self.error("map keys must be strings, numbers or booleans")),
+                    };
+                    self.expect(':')?;
+                    let child = pointer.map(|pointer| format!("{}/{}", pointer, pointer_token(&key)));
+                    let value = self.value(child.as_deref())?;
+                    object.insert(key, value);
+                    self.separator('}')?;
+                }
+                Ok(Value::Object(object))
+            }
+            Some('(') => self.group(pointer),
+            Some(c) if c.is_ascii_digit() || matches!(c, '-' | '+' | '.') => self.number(),
+            Some(c) if c.is_alphabetic() || c == '_' => match self.identifier() {
+                "true" => Ok(Value::Bool(true)),
+                "false" => Ok(Value::Bool(false)),
+                "None" => Ok(Value::Null),
+                "Some" => {
+                    self.expect('(')?;
+                    let value = self.value(pointer)?;
+                    self.separator(')')?;
+                    self.expect(')')?;
+                    Ok(value)
+                }
+                name => {
+                    self.whitespace()?;
+                    if self.peek() == Some('(') {
+                        self.group(pointer)
+                    } else {
+                        Ok(Value::String(name.to_string()))
+                    }
+                }
+            },
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    /// A unit, tuple, newtype or struct in parentheses.
+    fn group(&mut self, pointer: Option<&str>) -> Result<Value, ParseError> {
+        self.pos += 1;
+        if self.is_field()? {
+            let mut object = Map::new();
+            while !self.close(')')? {
+                let key = self.identifier();
+                if key.is_empty() {
+                    return Err(self.error("expected a field name"));
+                }
+                self.expect(':')?;
+                let child = pointer.map(|pointer| format!("{}/{}", pointer, pointer_token(key)));
+                object.insert(key.to_string(), self.value(child.as_deref())?);
+                self.separator(')')?;
+            }
+            return Ok(Value::Object(object));
+        }
+
+        let mut items = Vec::new();
+        let mut trailing_comma = false;
+        while !self.close(')')? {
+            let child = pointer.map(|pointer| format!("{}/{}", pointer, items.len()));
+            items.push(self.value(child.as_deref())?);
+            trailing_comma = self.separator(')')?;
+        }
+        Ok(match items.len() {
+            0 => Value::Null,
+            1 if !trailing_comma => items.remove(0),
+            _ => Value::Array(items),
+        })
+    }
+
+    /// Whether a struct field, `name:`, comes next.
+    fn is_field(&mut self) -> Result<bool, ParseError> {
+        self.whitespace()?;
+        let start = self.pos;
+        let found = !self.identifier().is_empty() && {
+            self.whitespace()?;
+            self.rest().starts_with(':')
+        };
+        self.pos = start;
+        Ok(found)
+    }
+
+    fn identifier(&mut self) -> &'a str {
+        let rest = self.rest();
+        let len = rest
+            .char_indices()
+            .find(|&(i, c)| !(c.is_alphanumeric() || c == '_') || (i == 0 && c.is_ascii_digit()))
+            .map_or(rest.len(), |(i, _)| i);
+        self.pos += len;
+        &rest[..len]
+    }
+
+    fn number(&mut self) -> Result<Value, ParseError> {
+        let rest = self.rest();
+        let len = rest
+            .char_indices()
+            .find(|&(i, c)| {
+                let sign = matches!(c, '-' | '+') && (i == 0 || rest[..i].ends_with(['e', 'E']));
+                !(c.is_ascii_alphanumeric() || matches!(c, '_' | '.') || sign)
+            })
+            .map_or(rest.len(), |(i, _)| i);
+        let text: String = rest[..len].chars().filter(|&c| c != '_').collect();
+        let (sign, digits) = match text.strip_prefix('-') {
+            Some(digits) => ("-", digits),
+            None => ("", text.strip_prefix('+').unwrap_or(&text)),
+        };
+        let radix = match digits.get(..2) {
+            Some("0x") => 16,
+            Some("0o") => 8,
+            Some("0b") => 2,
+            _ => 10,
+        };
+        let number = if radix != 10 {
+            i64::from_str_radix(&format!("{}{}", sign, &digits[2..]), radix).ok().map(Number::from)
+        } else if digits.contains(['.', 'e', 'E']) {
+            text.parse().ok().and_then(Number::from_f64)
+        } else {
+            text.parse::<i64>().map(Number::from).or_else(|_| text.parse::<u64>().map(Number::from)).ok()
+        };
+        let number = number.ok_or_else(|| self.error(format!("invalid number `{}`", &rest[..len])))?;
+        self.pos += len;
+        Ok(Value::Number(number))
+    }
+
+    /// A string in double quotes or a char in single quotes.
+    fn string(&mut self) -> Result<String, ParseError> {
+        let quote = self.peek();
+        self.pos += 1;
+        let mut string = String::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error("unclosed string")),
+                Some(c) if Some(c) == quote => {
+                    self.pos += 1;
+                    return Ok(string);
+                }
+                Some('\\') => {
+                    self.pos += 1;
+                    string.push(self.escape()?);
+                }
+                Some(c) => {
+                    self.pos += c.len_utf8();
+                    string.push(c);
+                }
+            }
+        }
+    }
+
+    fn escape(&mut self) -> Result<char, ParseError> {
+        let c = self.peek().ok_or_else(|| self.error("unclosed string"))?;
+        self.pos += c.len_utf8();
+        let code = match c {
+            'n' => return Ok('\n'),
+            'r' => return Ok('\r'),
+            't' => return Ok('\t'),
+            '0' => return Ok('\0'),
+            'b' => return Ok('\u{8}'),
+            'f' => return Ok('\u{c}'),
+            '\\' | '"' | '\'' | '/' => return Ok(c),
+            'x' => self.rest().get(..2).inspect(|_| self.pos += 2),
+            'u' if self.rest().starts_with('{') => self.rest()[1..].split_once('}').map(|(code, _)| {
+                self.pos += code.len() + 2;
+                code
+            }),
+            'u' => self.rest().get(..4).inspect(|_| self.pos += 4),
+            _ => return Err(self.error(format!("unknown escape `\\{}`", c))),
+        };
+        code.and_then(|code| u32::from_str_radix(code, 16).ok())
+            .and_then(char::from_u32)
+            .ok_or_else(|| self.error("invalid escape"))
+    }
+
+    /// `r"..."` or `r#"..."#`, with any number of `#`.
+    fn raw_string(&mut self) -> Result<String, ParseError> {
+        let hashes = self.rest()[1..].len() - self.rest()[1..].trim_start_matches('#').len();
+        self.pos += 1 + hashes;
+        if self.peek() != Some('"') {
+            return Err(self.error("expected `\"`"));
+        }
+        self.pos += 1;
+        let end = format!("\"{}", "#".repeat(hashes));
+        let len = self.rest().find(&end).ok_or_else(|| self.error("unclosed string"))?;
+        let string = self.rest()[..len].to_string();
+        self.pos += len + end.len();
+        Ok(string)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::assets::{AssetIndex, AudioAsset, AudioType, SpriteAsset};
+    use crate::data::migration::{document_version, DocumentKind};
+    use crate::data::parse::{parse_data, parse_document, parse_document_lenient, Document};
+    use crate::data::{Database, EntityType, Project, QuestCondition, QuestState, Scene, StoryGraphData};
+    use serde::de::DeserializeOwned;
+    use serde::Deserialize;
+    use std::fmt::Debug;
+
+    fn round_trip<T: Document + Serialize + PartialEq + Debug>(value: &T) {
+        for format in DataFormat::ALL {
+            let written = format.write(value).unwrap();
+            let read: T = parse_document(format, &written).unwrap_or_else(|e| panic!("{:?}: {}\n{}", format, e, written));
+            assert_eq!(&read, value, "{:?} round trip", format);
+        }
+    }
+
+    fn example<T: DeserializeOwned>(json: &str) -> T {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip_every_format() {
+        let mut project = Project::new("Formats");
+        project.add_scene("town", "scenes/town.ron");
+        project.add_story_graph("intro", "story_graphs/intro.toml");
+        round_trip(&project);
+
+        round_trip::<Scene>(&example(include_str!("../../examples/jrpg_scene.json")));
+        round_trip::<Scene>(&example(include_str!("../../examples/td_scene.json")));
+        round_trip::<Database>(&example(include_str!("../../examples/database.json")));
+        round_trip::<StoryGraphData>(&example(include_str!("../../examples/story_graph.json")));
+
+        let mut index = AssetIndex::new();
+        index.sprites.push(SpriteAsset::new("hero", "sprites/hero.png").with_tags(vec!["player".to_string()]));
+        index.audio.push(AudioAsset::new("town_theme", "music/town.ogg", AudioType::Music));
+        for format in DataFormat::ALL {
+            let read: AssetIndex = parse_data(format, &format.write(&index).unwrap()).unwrap();
+            assert_eq!(read, index, "{:?} round trip", format);
+        }
+    }
+
+    #[test]
+    fn test_hand_written_ron_and_toml() {
+        let ron = r#"// Comments, struct names and trailing commas are fine.
+Scene(
+    id: "town",
+    name: "Town",
+    entities: [
+        (
+            id: "gate",
+            name: "Gate",
+            entity_type: trigger,
+            components: (transform: (position: (x: 1, y: 2.0))),
+        ),
+        (id: "typo", name: "Typo", entity_type: trigerr),
+    ],
+)"#;
+        // Files without a version count as version 0, as JSON ones do.
+        assert_eq!(document_version(DocumentKind::Scene, &DataFormat::Ron.read(ron).unwrap()).unwrap(), 0);
+        let Err(DataError::Parse(error)) = parse_document::<Scene>(DataFormat::Ron, ron) else {
+            panic!("expected a parse error");
+        };
+        assert_eq!((error.line, error.pointer.as_str()), (Some(12), "/entities/1/entity_type"));
+
+        let scene = parse_document_lenient::<Scene>(DataFormat::Ron, ron).unwrap();
+        assert_eq!(scene.value.entities.len(), 1);
+        assert_eq!(scene.value.entities[0].components.transform.position.y, 2.0);
+        assert_eq!(scene.value.entities[0].entity_type, EntityType::Trigger);
+        assert_eq!(scene.skipped[0].line, Some(12));
+
+        // Bare variants work inside internally tagged enums too.
+        let ron = r#"(quests: [(id: "ending", name: {"en": "Ending"}, start_conditions: [
+            (type: "quest", quest_id: "intro", state: completed),
+        ])])"#;
+        let database: Database = parse_document(DataFormat::Ron, ron).unwrap();
+        assert_eq!(
+            database.quests[0].start_conditions,
+            [QuestCondition::Quest { quest_id: "intro".to_string(), state: QuestState::Completed }]
+        );
+
+        let toml = "[[items]]\nid = \"sword\"\nprice = 10\n\n[items.name]\nen = \"Sword\"\n";
+        let database: Database = parse_document(DataFormat::Toml, toml).unwrap();
+        assert_eq!(database.items[0].name["en"], "Sword");
+
+        let Err(DataError::Parse(error)) = parse_document::<Database>(DataFormat::Toml, &toml.replace("10", "\"ten\"")) else {
+            panic!("expected a parse error");
+        };
+        assert_eq!((error.line, error.column, error.pointer.as_str()), (Some(3), Some(9), "/items/0/price"));
+
+        let Err(DataError::Parse(error)) = parse_document::<Database>(DataFormat::Toml, "[[items]]\nid = \n") else {
+            panic!("expected a syntax error");
+        };
+        assert_eq!(error.line, Some(2));
+    }
+
+    #[test]
+    fn test_ron_syntax() {
+        let ron = r##"#![enable(implicit_some)]
+/* Block /* nested */ comment */
+(
+    text: r#"Say "hi""#,
+    escaped: "tab\tand \u{e9}",
+    letter: 'x',
+    numbers: [0x1F, -3, 1_000, 2.5e1, +4],
+    tuple: (1, "a"),
+    newtype: (7),
+    unit: (),
+    option: Some(None),
+    map: {1: true, "two": false},
+)"##;
+        let value = DataFormat::Ron.read(ron).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "text": "Say \"hi\"",
+                "escaped": "tab\tand \u{e9}",
+                "letter": "x",
+                "numbers": [31, -3, 1000, 25.0, 4],
+                "tuple": [1, "a"],
+                "newtype": 7,
+                "unit": null,
+                "option": null,
+                "map": { "1": true, "two": false },
+            })
+        );
+
+        let error = DataFormat::Ron.read("(\n    a: 1,\n    b: [1 2],\n)").unwrap_err();
+        assert_eq!((error.line, error.column), (Some(3), Some(11)));
+        assert!(DataFormat::Ron.read("(a: 1) 2").is_err());
+        assert!(DataFormat::Ron.read("\"open").is_err());
+    }
+
+    #[test]
+    fn test_none_in_array() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Slots {
+            slots: Vec<Option<u32>>,
+        }
+
+        let slots = Slots { slots: vec![Some(1), None, Some(3)] };
+        for format in [DataFormat::Json, DataFormat::Ron] {
+            let read: Slots = parse_data(format, &format.write(&slots).unwrap()).unwrap();
+            assert_eq!(read, slots, "{:?} round trip", format);
+        }
+        // TOML has nothing to write for the `None`.
+        assert!(matches!(DataFormat::Toml.write(&slots), Err(DataError::Toml(_))));
+    }
+}
@@ -1,17 +1,25 @@
 //! Loading functions for project data.
 //!
 //! Provides functions to load projects, scenes, databases, and story graphs
-//! from JSON files.
+//! from JSON, RON or TOML files, picked by extension (see
+//! [`format`](super::format)). Story graphs can also be `.story` scripts (see
+//! [`story_script`](super::story_script)) or Twine/Yarn sources (see
+//! [`story_import`](super::story_import)).
 
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 use super::project::Project;
 use super::scene::Scene;
 use super::database::Database;
 use super::story::StoryGraphData;
+use super::story_script::{self, ScriptError};
+use super::story_import::{self, ImportError, ImportedGraph};
 use super::assets::AssetIndex;
+use super::migration::DocumentKind;
+use super::format::DataFormat;
+use super::parse::{parse_data, parse_document, parse_document_lenient, Document, Lenient, ParseError};
 
 /// Error type for data loading operations.
 #[derive(Debug, Error)]
@@ -22,17 +30,76 @@ pub enum DataError {
     #[error("JSON parse error: {0}")]
     Json(#[from] serde_json::Error),
 
+    #[error("RON error: {0}")]
+    Ron(#[from] ron::Error),
+
+    #[error("TOML error: {0}")]
+    Toml(#[from] toml::ser::Error),
+
+    #[error("{0}")]
+    Parse(ParseError),
+
     #[error("File not found: {0}")]
     NotFound(String),
 
     #[error("Invalid project structure: {0}")]
     InvalidProject(String),
+
+    #[error("Story script error: {0}")]
+    Script(#[from] ScriptError),
+
+    #[error("Story import error: {0}")]
+    Import(#[from] ImportError),
+
+    #[error("Unsupported file format: {0}")]
+    UnsupportedFormat(String),
+
+    #[error("Unsupported {kind} schema version {found} (this build reads up to {supported})")]
+    UnsupportedVersion { kind: DocumentKind, found: u32, supported: u32 },
+
+    #[error("Invalid {kind} schema version {found} (must be a whole number)")]
+    InvalidVersion { kind: DocumentKind, found: String },
+
+    #[error("Failed to migrate {kind} from schema version {from}: {message}")]
+    Migration { kind: DocumentKind, from: u32, message: String },
+
+    #[error("{} project file(s) failed:{}", .0.len(), list_errors(.0))]
+    Files(Vec<FileError>),
+
+    /// An error that doesn't carry its own path, tagged with its file
+    #[error("{0}")]
+    File(Box<FileError>),
+}
+
+/// A [`DataError`] for one file of a project.
+#[derive(Debug, Error)]
+#[error("{}: {error}", path.display())]
+pub struct FileError {
+    pub path: PathBuf,
+    pub error: DataError,
+}
+
+impl DataError {
+    /// Attach the file a parse, script or import error came from.
+    pub fn in_file(self, path: &Path) -> Self {
+        match self {
+            DataError::Parse(error) => DataError::Parse(ParseError { path: Some(path.to_path_buf()), ..error }),
+            error @ (DataError::Script(_) | DataError::Import(_)) => {
+                DataError::File(Box::new(FileError { path: path.to_path_buf(), error }))
+            }
+            other => other,
+        }
+    }
+}
+
+fn list_errors(errors: &[FileError]) -> String {
+    errors.iter().map(|e| format!("\n  {}", e)).collect()
 }
 
-/// Load a project from a JSON file.
+/// Load a project from a JSON, RON or TOML file.
 ///
 /// # Arguments
-/// * `path` - Path to the project.json file
+/// * `path` - Path to the project file
 ///
 /// # Returns
 /// The loaded project or an error
@@ -42,14 +109,13 @@ pub fn load_project(path: &Path) -> Result<Project, DataError> {
     }
 
     let content = fs::read_to_string(path)?;
-    let project: Project = serde_json::from_str(&content)?;
-    Ok(project)
+    parse_document(DataFormat::from_path(path), &content).map_err(|e| e.in_file(path))
 }
 
-/// Load a scene from a JSON file.
+/// Load a scene from a JSON, RON or TOML file.
 ///
 /// # Arguments
-/// * `path` - Path to the scene JSON file
+/// * `path` - Path to the scene file
 ///
 /// # Returns
 /// The loaded scene or an error
@@ -59,14 +125,13 @@ pub fn load_scene(path: &Path) -> Result<Scene, DataError> {
     }
 
     let content = fs::read_to_string(path)?;
-    let scene: Scene = serde_json::from_str(&content)?;
-    Ok(scene)
+    parse_document(DataFormat::from_path(path), &content).map_err(|e| e.in_file(path))
 }
 
-/// Load a database from a JSON file.
+/// Load a database from a JSON, RON or TOML file.
 ///
 /// # Arguments
-/// * `path` - Path to the database JSON file
+/// * `path` - Path to the database file
 ///
 /// # Returns
 /// The loaded database or an error
@@ -76,31 +141,124 @@ pub fn load_database(path: &Path) -> Result<Database, DataError> {
     }
 
     let content = fs::read_to_string(path)?;
-    let database: Database = serde_json::from_str(&content)?;
-    Ok(database)
+    parse_document(DataFormat::from_path(path), &content).map_err(|e| e.in_file(path))
 }
 
-/// Load a story graph from a JSON file.
+/// Load a story graph from a JSON, RON or TOML file, a `.story` script or
+/// a Twine (`.twee`, `.tw`) or Yarn (`.yarn`) source.
+///
+/// Import warnings are logged; use [`import_story_graph`] to get them.
 ///
 /// # Arguments
-/// * `path` - Path to the story graph JSON file
+/// * `path` - Path to the story graph file; scripts and imports take the
+///   file stem as their graph id
 ///
 /// # Returns
 /// The loaded story graph or an error
 pub fn load_story_graph(path: &Path) -> Result<StoryGraphData, DataError> {
+    if is_import(path) {
+        let imported = import_story_graph(path)?;
+        for warning in &imported.warnings {
+            bevy::log::warn!("{}: {}", path.display(), warning);
+        }
+        return Ok(imported.graph);
+    }
     if !path.exists() {
         return Err(DataError::NotFound(path.display().to_string()));
     }
 
     let content = fs::read_to_string(path)?;
-    let graph: StoryGraphData = serde_json::from_str(&content)?;
-    Ok(graph)
+    if is_story_script(path) {
+        return story_script::compile(file_stem(path), &content).map_err(|e| DataError::from(e).in_file(path));
+    }
+    parse_document(DataFormat::from_path(path), &content).map_err(|e| e.in_file(path))
+}
+
+/// Load a project, scene, database or story graph, skipping the
+/// entities, rows or nodes that fail to parse.
+///
+/// # Arguments
+/// * `path` - Path to the JSON, RON or TOML file
+///
+/// # Returns
+/// The document with an error for each skipped row, or an error if the
+/// file can't be read or something other than a row is wrong
+pub fn load_lenient<T: Document>(path: &Path) -> Result<Lenient<T>, DataError> {
+    if !path.exists() {
+        return Err(DataError::NotFound(path.display().to_string()));
+    }
+
+    let content = fs::read_to_string(path)?;
+    let mut loaded = parse_document_lenient::<T>(DataFormat::from_path(path), &content).map_err(|e| e.in_file(path))?;
+    for error in &mut loaded.skipped {
+        error.path = Some(path.to_path_buf());
+    }
+    Ok(loaded)
+}
+
+/// Import a Twine (`.twee`, `.tw`) or Yarn (`.yarn`) story as a story graph.
+///
+/// # Arguments
+/// * `path` - Path to the source; the file stem becomes the graph id
+///
+/// # Returns
+/// The imported graph with the warnings for anything that was skipped, or
+/// an error
+pub fn import_story_graph(path: &Path) -> Result<ImportedGraph, DataError> {
+    if !path.exists() {
+        return Err(DataError::NotFound(path.display().to_string()));
+    }
+
+    let content = fs::read_to_string(path)?;
+    let imported = match extension(path) {
+        "twee" | "tw" => story_import::import_twee(file_stem(path), &content),
+        "yarn" => story_import::import_yarn(file_stem(path), &content),
+        other => return Err(DataError::UnsupportedFormat(other.to_string())),
+    };
+    imported.map_err(|e| DataError::from(e).in_file(path))
+}
+
+fn extension(path: &Path) -> &str {
+    path.extension().and_then(|ext| ext.to_str()).unwrap_or_default()
 }
 
-/// Load an asset index from a JSON file.
+fn file_stem(path: &Path) -> &str {
+    path.file_stem().and_then(|s| s.to_str()).unwrap_or_default()
+}
+
+fn is_story_script(path: &Path) -> bool {
+    extension(path) == "story"
+}
+
+pub(crate) fn is_import(path: &Path) -> bool {
+    matches!(extension(path), "twee" | "tw" | "yarn")
+}
+
+/// Whether [`load_story_graph`] reads `path`, judging by its extension.
+pub(crate) fn is_story_graph_file(path: &Path) -> bool {
+    is_import(path) || is_story_script(path) || extension(path) == DataFormat::from_path(path).extension()
+}
+
+/// Load every story graph listed in a project's `story_graphs`.
+///
+/// # Arguments
+/// * `project` - The project listing the graphs
+/// * `root_path` - The project root directory (graph paths are relative to it)
+///
+/// # Returns
+/// The loaded story graphs, in listing order, or the first error
+pub fn load_story_graphs(project: &Project, root_path: &Path) -> Result<Vec<StoryGraphData>, DataError> {
+    project
+        .story_graphs
+        .iter()
+        .map(|graph_ref| load_story_graph(&root_path.join(&graph_ref.path)))
+        .collect()
+}
+
+/// Load an asset index from a JSON, RON or TOML file.
 ///
 /// # Arguments
-/// * `path` - Path to the asset index JSON file
+/// * `path` - Path to the asset index file
 ///
 /// # Returns
 /// The loaded asset index or an error
@@ -110,38 +268,53 @@ pub fn load_asset_index(path: &Path) -> Result<AssetIndex, DataError> {
     }
 
     let content = fs::read_to_string(path)?;
-    let index: AssetIndex = serde_json::from_str(&content)?;
-    Ok(index)
+    parse_data(DataFormat::from_path(path), &content).map_err(|e| e.in_file(path))
 }
 
-/// Save a project to a JSON file.
+/// Save a project to a JSON, RON or TOML file, by extension.
 pub fn save_project(project: &Project, path: &Path) -> Result<(), DataError> {
-    let content = serde_json::to_string_pretty(project)?;
+    let content = DataFormat::from_path(path).write(project)?;
     fs::write(path, content)?;
     Ok(())
 }
 
-/// Save a scene to a JSON file.
+/// Save a scene to a JSON, RON or TOML file, by extension.
 pub fn save_scene(scene: &Scene, path: &Path) -> Result<(), DataError> {
-    let content = serde_json::to_string_pretty(scene)?;
+    let content = DataFormat::from_path(path).write(scene)?;
     fs::write(path, content)?;
     Ok(())
 }
 
-/// Save a database to a JSON file.
+/// Save a database to a JSON, RON or TOML file, by extension.
 pub fn save_database(database: &Database, path: &Path) -> Result<(), DataError> {
-    let content = serde_json::to_string_pretty(database)?;
+    let content = DataFormat::from_path(path).write(database)?;
     fs::write(path, content)?;
     Ok(())
 }
 
-/// Save a story graph to a JSON file.
-pub fn save_story_graph(graph: &StoryGraphData, path: &Path) -> Result<(), DataError> {
-    let content = serde_json::to_string_pretty(graph)?;
+/// Save an asset index to a JSON, RON or TOML file, by extension.
+pub fn save_asset_index(index: &AssetIndex, path: &Path) -> Result<(), DataError> {
+    let content = DataFormat::from_path(path).write(index)?;
     fs::write(path, content)?;
     Ok(())
 }
 
+/// Save a story graph to a JSON, RON or TOML file, by extension, or as a
+/// script if the path ends in `.story`.
+pub fn save_story_graph(graph: &StoryGraphData, path: &Path) -> Result<(), DataError> {
+    fs::write(path, story_graph_to_string(graph, path)?)?;
+    Ok(())
+}
+
+/// What [`save_story_graph`] writes to `path`.
+pub(crate) fn story_graph_to_string(graph: &StoryGraphData, path: &Path) -> Result<String, DataError> {
+    if is_story_script(path) {
+        Ok(story_script::to_script(graph)?)
+    } else {
+        DataFormat::from_path(path).write(graph)
+    }
+}
+
 /// Save the entire project structure to a directory.
 ///
 /// This creates the necessary subdirectories (scenes, assets, etc.) and saves the `project.json` file.
@@ -172,6 +345,7 @@ pub fn save_project_structure(project: &Project, root_path: &Path) -> Result<(),
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::data::migration::{SchemaVersion, SCHEMA_VERSION};
     use std::io::Write;
     use tempfile::NamedTempFile;
 
@@ -187,6 +361,55 @@ mod tests {
         assert_eq!(project.name, loaded.name);
     }
 
+    #[test]
+    fn test_load_versioned_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("shop.json");
+
+        // Files from before versioning load as the current version.
+        fs::write(&path, r#"{ "id": "shop", "name": "Shop", "nodes": [] }"#).unwrap();
+        let graph = load_story_graph(&path).unwrap();
+        assert_eq!(graph.schema_version, SchemaVersion(SCHEMA_VERSION));
+        save_story_graph(&graph, &path).unwrap();
+        let saved = fs::read_to_string(&path).unwrap();
+        assert!(saved.contains(&format!("\"schema_version\": {}", SCHEMA_VERSION)));
+
+        let newer = format!(r#"{{ "schema_version": {}, "items": [] }}"#, SCHEMA_VERSION + 1);
+        fs::write(&path, newer).unwrap();
+        assert!(matches!(
+            load_database(&path),
+            Err(DataError::UnsupportedVersion { kind: DocumentKind::Database, .. })
+        ));
+    }
+
+    #[test]
+    fn test_load_errors_name_file_and_row() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("database.json");
+        fs::write(
+            &path,
+            r#"{
+  "items": [
+    { "id": "sword", "name": { "en": "Sword" }, "price": 10 },
+    { "id": "shield", "name": { "en": "Shield" }, "price": "cheap" }
+  ]
+}"#,
+        )
+        .unwrap();
+
+        let Err(DataError::Parse(error)) = load_database(&path) else {
+            panic!("expected a parse error");
+        };
+        assert_eq!(error.path.as_deref(), Some(path.as_path()));
+        assert_eq!(error.pointer, "/items/1/price");
+        assert!(error.to_string().starts_with(&format!("{}:4:60: at /items/1/price: ", path.display())));
+
+        let database = load_lenient::<Database>(&path).unwrap();
+        assert_eq!(database.value.items.len(), 1);
+        assert_eq!(database.skipped.len(), 1);
+        assert_eq!(database.skipped[0].path.as_deref(), Some(path.as_path()));
+    }
+
     #[test]
     fn test_load_not_found() {
         let result = load_project(Path::new("/nonexistent/path.json"));
@@ -208,4 +431,40 @@ mod tests {
         assert!(root_path.join("database").exists());
         assert!(root_path.join("assets").exists());
     }
+
+    #[test]
+    fn test_load_story_script() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("greeting.story");
+        fs::write(&path, "=== hello ===\nNarrator: Hi!\nEND\n").unwrap();
+
+        let graph = load_story_graph(&path).unwrap();
+        assert_eq!(graph.id, "greeting");
+        assert_eq!(graph.root_node_id, "hello");
+
+        save_story_graph(&graph, &path).unwrap();
+        assert_eq!(load_story_graph(&path).unwrap(), graph);
+
+        fs::write(&path, "=== hello ===\n-> nowhere\n").unwrap();
+        let error = load_story_graph(&path).unwrap_err();
+        assert!(error.to_string().starts_with(&path.display().to_string()), "{}", error);
+        let DataError::File(error) = error else { panic!("expected a file error, got {:?}", error) };
+        assert!(matches!(error.error, DataError::Script(_)));
+    }
+
+    #[test]
+    fn test_import_story_graph() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("gate.yarn");
+        fs::write(&path, "title: Start\n---\nGuard: Halt!\n<<shake>>\n===\n").unwrap();
+
+        let imported = import_story_graph(&path).unwrap();
+        assert_eq!(imported.graph.id, "gate");
+        assert_eq!(imported.warnings.len(), 1);
+        assert_eq!(load_story_graph(&path).unwrap(), imported.graph);
+
+        let path = temp_dir.path().join("gate.ink");
+        fs::write(&path, "Hello").unwrap();
+        assert!(matches!(import_story_graph(&path), Err(DataError::UnsupportedFormat(_))));
+    }
 }
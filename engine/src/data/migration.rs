@@ -0,0 +1,251 @@
+//! Schema versions and migrations for data files.
+//!
+//! Project, scene, database and story graph files carry a `schema_version`.
+//! Loading reads a file into a JSON [`Value`] first and runs the
+//! [`MigrationRegistry`] steps between the file's version and
+//! [`SCHEMA_VERSION`], so renamed or restructured fields are carried over
+//! instead of failing to parse or silently falling back to their defaults.
+//! Files written before versioning have no `schema_version` and count as
+//! version 0. Files newer than [`SCHEMA_VERSION`] are rejected with
+//! [`DataError::UnsupportedVersion`].
+//!
+//! When a change to one of the document types needs existing files
+//! rewritten, bump [`SCHEMA_VERSION`] and add a step from the previous
+//! version to [`MIGRATIONS`].
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+use serde_json::{json, Value};
+use std::fmt;
+
+use super::database::QuestCondition;
+use super::loader::DataError;
+
+/// The schema version this build reads and writes.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Name of the version field in every document.
+pub const VERSION_FIELD: &str = "schema_version";
+
+/// A document's schema version; defaults to [`SCHEMA_VERSION`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, JsonSchema, Reflect)]
+#[serde(transparent)]
+pub struct SchemaVersion(pub u32);
+
+impl Default for SchemaVersion {
+    fn default() -> Self {
+        SchemaVersion(SCHEMA_VERSION)
+    }
+}
+
+impl fmt::Display for SchemaVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The kinds of versioned documents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DocumentKind {
+    Project,
+    Scene,
+    Database,
+    StoryGraph,
+}
+
+impl fmt::Display for DocumentKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            DocumentKind::Project => "project",
+            DocumentKind::Scene => "scene",
+            DocumentKind::Database => "database",
+            DocumentKind::StoryGraph => "story graph",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Rewrites a document in place, or explains why it can't.
+pub type MigrationFn = fn(&mut Value) -> Result<(), String>;
+
+/// Upgrades one kind of document from version `from` to `from + 1`.
+#[derive(Clone, Copy)]
+pub struct Migration {
+    pub kind: DocumentKind,
+    pub from: u32,
+    pub migrate: MigrationFn,
+}
+
+impl fmt::Debug for Migration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Migration").field("kind", &self.kind).field("from", &self.from).finish()
+    }
+}
+
+/// The engine's migration steps.
+///
+/// Version 1 introduced `schema_version`; unversioned files upgrade to it
+/// unchanged. Version 2 made quest conditions strict: databases wrap the
+/// conditions they can't read in `custom` ones.
+pub const MIGRATIONS: &[Migration] =
+    &[Migration { kind: DocumentKind::Database, from: 1, migrate: wrap_custom_quest_conditions }];
+
+/// Wrap quest conditions that don't read as a [`QuestCondition`] in a
+/// `custom` condition, warning once per quest.
+fn wrap_custom_quest_conditions(value: &mut Value) -> Result<(), String> {
+    let Some(quests) = value.get_mut("quests").and_then(Value::as_array_mut) else {
+        return Ok(());
+    };
+    for quest in quests {
+        let id = quest.get("id").and_then(Value::as_str).unwrap_or_default().to_string();
+        let mut wrapped = 0;
+        for field in ["start_conditions", "completion_conditions"] {
+            let Some(conditions) = quest.get_mut(field).and_then(Value::as_array_mut) else {
+                continue;
+            };
+            for condition in conditions {
+                if serde_json::from_value::<QuestCondition>(condition.clone()).is_err() {
+                    *condition = json!({ "type": "custom", "condition": condition.take() });
+                    wrapped += 1;
+                }
+            }
+        }
+        if wrapped > 0 {
+            warn!(
+                "Migration: quest '{}' has {} condition(s) the engine can't read; kept as `custom`, which never holds",
+                id, wrapped
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Migration steps by document kind and version.
+#[derive(Debug, Clone)]
+pub struct MigrationRegistry {
+    steps: Vec<Migration>,
+    target: u32,
+}
+
+impl Default for MigrationRegistry {
+    fn default() -> Self {
+        Self { steps: MIGRATIONS.to_vec(), target: SCHEMA_VERSION }
+    }
+}
+
+impl MigrationRegistry {
+    /// A registry without steps that upgrades to `target`.
+    pub fn empty(target: u32) -> Self {
+        Self { steps: Vec::new(), target }
+    }
+
+    /// Add a step upgrading `kind` documents from `from` to `from + 1`.
+    /// Versions without a step for a kind leave those documents unchanged.
+    pub fn register(&mut self, kind: DocumentKind, from: u32, migrate: MigrationFn) -> &mut Self {
+        self.steps.push(Migration { kind, from, migrate });
+        self
+    }
+
+    /// The version documents are upgraded to.
+    pub fn target(&self) -> u32 {
+        self.target
+    }
+
+    /// Upgrade `value` to the target version and stamp it with it.
+    pub fn migrate(&self, kind: DocumentKind, mut value: Value) -> Result<Value, DataError> {
+        let version = document_version(kind, &value)?;
+        if version > self.target {
+            return Err(DataError::UnsupportedVersion { kind, found: version, supported: self.target });
+        }
+
+        for from in version..self.target {
+            for step in self.steps.iter().filter(|step| step.kind == kind && step.from == from) {
+                (step.migrate)(&mut value).map_err(|message| DataError::Migration { kind, from, message })?;
+            }
+        }
+        if let Value::Object(object) = &mut value {
+            object.insert(VERSION_FIELD.to_string(), Value::from(self.target));
+        }
+        Ok(value)
+    }
+}
+
+/// The `schema_version` of a `kind` document, 0 if it has none.
+pub fn document_version(kind: DocumentKind, value: &Value) -> Result<u32, DataError> {
+    match value.get(VERSION_FIELD) {
+        None => Ok(0),
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| DataError::InvalidVersion { kind, found: version.to_string() }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rename_title(value: &mut Value) -> Result<(), String> {
+        let object = value.as_object_mut().ok_or("not an object")?;
+        if let Some(title) = object.remove("title") {
+            object.insert("name".to_string(), title);
+        }
+        Ok(())
+    }
+
+    fn add_suffix(value: &mut Value) -> Result<(), String> {
+        let name = value["name"].as_str().ok_or("name is missing")?;
+        value["name"] = json!(format!("{} (v2)", name));
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_steps_in_order() {
+        let mut registry = MigrationRegistry::empty(2);
+        registry.register(DocumentKind::Scene, 1, add_suffix).register(DocumentKind::Scene, 0, rename_title);
+
+        let migrated = registry.migrate(DocumentKind::Scene, json!({ "id": "town", "title": "Town" })).unwrap();
+        assert_eq!(migrated, json!({ "id": "town", "name": "Town (v2)", "schema_version": 2 }));
+
+        // Steps for other kinds and older versions don't run.
+        let database = json!({ "schema_version": 1, "title": "Items" });
+        assert_eq!(registry.migrate(DocumentKind::Database, database.clone()).unwrap()["title"], "Items");
+        let scene = json!({ "schema_version": 1, "name": "Town" });
+        assert_eq!(registry.migrate(DocumentKind::Scene, scene).unwrap()["name"], "Town (v2)");
+
+        assert!(matches!(
+            registry.migrate(DocumentKind::Scene, json!({ "schema_version": 1 })),
+            Err(DataError::Migration { from: 1, .. })
+        ));
+        assert!(matches!(
+            registry.migrate(DocumentKind::Scene, json!({ "schema_version": 3 })),
+            Err(DataError::UnsupportedVersion { found: 3, supported: 2, .. })
+        ));
+        assert!(matches!(
+            registry.migrate(DocumentKind::Scene, json!({ "schema_version": "2" })),
+            Err(DataError::InvalidVersion { kind: DocumentKind::Scene, .. })
+        ));
+    }
+
+    #[test]
+    fn test_migrate_legacy_quest_conditions() {
+        let database = json!({
+            "schema_version": 1,
+            "quests": [{
+                "id": "quest_fetch_herbs",
+                "name": { "en": "Lost Herbs" },
+                "start_conditions": [{ "type": "flag", "flag": "met_merchant" }],
+                "completion_conditions": [{ "type": "teleport", "to": "town" }]
+            }]
+        });
+        let migrated = MigrationRegistry::default().migrate(DocumentKind::Database, database).unwrap();
+        let quest = &migrated["quests"][0];
+        assert_eq!(quest["start_conditions"][0], json!({ "type": "flag", "flag": "met_merchant" }));
+        assert_eq!(
+            quest["completion_conditions"][0],
+            json!({ "type": "custom", "condition": { "type": "teleport", "to": "town" } })
+        );
+    }
+}
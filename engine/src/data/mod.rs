@@ -7,27 +7,44 @@
 //! - Game databases (items, NPCs, towers, enemies, etc.)
 //! - Asset indexing and prefabs
 //!
-//! These types are designed to be stored as JSON and loaded by both
-//! the editor and runtime. They are intentionally separate from Bevy
+//! These types are designed to be stored as JSON, RON or TOML and loaded
+//! by both the editor and runtime. They are intentionally separate from Bevy
 //! ECS components to maintain a clean data transfer layer.
 
 pub mod project;
 pub mod scene;
 pub mod components;
 pub mod story;
+pub mod story_script;
+pub mod story_import;
 pub mod database;
 pub mod assets;
+pub mod bundle;
+pub mod format;
 pub mod loader;
+pub mod migration;
+pub mod parse;
+pub mod schema;
 pub mod spawner;
+pub mod analysis;
+pub mod coverage;
+pub mod export;
 
 // Re-export commonly used types
 pub use project::{Project, ProjectSettings, EditorPreferences};
 pub use scene::{Scene, Layer, Entity, SceneType, EntityType};
 pub use components::*;
 pub use story::{StoryGraphData, StoryNodeData, StoryNodeType};
-pub use database::{Database, ItemRow, NpcRow, TowerRow, EnemyRow, LootTableRow, QuestRow};
+pub use database::{Database, ItemRow, NpcRow, TowerRow, EnemyRow, LootTableRow, QuestRow, QuestCondition, QuestRewards, QuestState};
 pub use assets::{AssetIndex, Prefab};
-pub use loader::{load_project, load_scene, load_database, load_story_graph, DataError};
+pub use analysis::{analyze_project, DiagnosticKind, Severity, StoryDiagnostic};
+pub use coverage::{CoverageOptions, CoverageReport, Reachability};
+pub use export::{ExportFormat, ExportOptions};
+pub use bundle::{find_project_file, load_project_bundle, save_project_bundle, ProjectBundle};
+pub use migration::{DocumentKind, MigrationRegistry, SchemaVersion, SCHEMA_VERSION};
+pub use loader::{load_project, load_scene, load_database, load_story_graph, load_story_graphs, import_story_graph, load_lenient, DataError, FileError};
+pub use format::DataFormat;
+pub use parse::{Lenient, ParseError};
 
 use bevy::prelude::*;
 
@@ -71,8 +88,11 @@ impl Plugin for DataPlugin {
            .register_type::<story::ChoiceOption>()
            .register_type::<story::ActionNodeData>()
            .register_type::<story::ConditionalNodeData>()
+           .register_type::<story::EffectNodeData>()
            .register_type::<story::CameraNodeData>()
            .register_type::<story::TimeControlNodeData>()
+           .register_type::<story::CallNodeData>()
+           .register_type::<story::ReturnNodeData>()
            .register_type::<story::EndNodeData>()
            .register_type::<scene::SceneType>()
            .register_type::<scene::EntityType>()
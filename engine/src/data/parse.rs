@@ -0,0 +1,324 @@
+//! Parsing data files with precise error locations.
+//!
+//! Errors name the file, the line and column, and a JSON pointer (RFC 6901,
+//! e.g. `/entities/17/components/transform`) to the value that failed.
+//!
+//! Lenient parsing skips bad rows instead of failing: a scene's entities, a
+//! database's rows, a story graph's nodes or a project's scene and graph
+//! references. Each skipped row is reported and the rest load as usual.
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::fmt;
+use std::path::PathBuf;
+use thiserror::Error;
+
+use super::database::Database;
+use super::format::{line_column, DataFormat, Parsed};
+use super::loader::DataError;
+use super::migration::{DocumentKind, MigrationRegistry};
+use super::project::Project;
+use super::scene::Scene;
+use super::story::StoryGraphData;
+
+/// A problem with a value in a data file.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub struct ParseError {
+    /// The file, when parsed from one
+    pub path: Option<PathBuf>,
+    /// 1-based line and column of the value, when known
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    /// JSON pointer to the value; empty for the whole document
+    pub pointer: String,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(path) = &self.path {
+            write!(f, "{}:", path.display())?;
+        }
+        if let (Some(line), Some(column)) = (self.line, self.column) {
+            write!(f, "{}:{}:", line, column)?;
+        }
+        if self.path.is_some() || self.line.is_some() {
+            f.write_str(" ")?;
+        }
+        if !self.pointer.is_empty() {
+            write!(f, "at {}: ", self.pointer)?;
+        }
+        f.write_str(&self.message)
+    }
+}
+
+impl ParseError {
+    /// A syntax error, which has no pointer.
+    pub(crate) fn syntax(line: Option<usize>, column: Option<usize>, message: String) -> Self {
+        Self { path: None, line, column, pointer: String::new(), message }
+    }
+}
+
+/// A document parsed leniently, with the rows that were skipped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lenient<T> {
+    pub value: T,
+    pub skipped: Vec<ParseError>,
+}
+
+/// A versioned data file.
+pub trait Document: DeserializeOwned {
+    const KIND: DocumentKind;
+    /// Arrays whose elements lenient parsing may skip
+    const ROWS: &'static [&'static str];
+}
+
+impl Document for Project {
+    const KIND: DocumentKind = DocumentKind::Project;
+    const ROWS: &'static [&'static str] = &["scenes", "story_graphs"];
+}
+
+impl Document for Scene {
+    const KIND: DocumentKind = DocumentKind::Scene;
+    const ROWS: &'static [&'static str] = &["entities", "layers"];
+}
+
+impl Document for Database {
+    const KIND: DocumentKind = DocumentKind::Database;
+    const ROWS: &'static [&'static str] = &["items", "npcs", "towers", "enemies", "loot_tables", "quests"];
+}
+
+impl Document for StoryGraphData {
+    const KIND: DocumentKind = DocumentKind::StoryGraph;
+    const ROWS: &'static [&'static str] = &["nodes"];
+}
+
+/// Parse a document, upgrading it to the current schema first (see
+/// [`migration`](super::migration)).
+pub fn parse_document<T: Document>(format: DataFormat, content: &str) -> Result<T, DataError> {
+    migrated::<T>(format, content)?.deserialize().map_err(DataError::Parse)
+}
+
+/// Parse a document, skipping rows that fail.
+///
+/// Fails only on syntax errors and on problems outside of the rows.
+pub fn parse_document_lenient<T: Document>(format: DataFormat, content: &str) -> Result<Lenient<T>, DataError> {
+    let mut parsed = migrated::<T>(format, content)?;
+    // Original index of every row still in `value`, by field.
+    let mut remaining: Vec<Vec<usize>> = T::ROWS
+        .iter()
+        .map(|field| (0..parsed.value.get(field).and_then(Value::as_array).map_or(0, Vec::len)).collect())
+        .collect();
+    let mut skipped = Vec::new();
+
+    loop {
+        let (pointer, message) = match deserialize(parsed.value.clone()) {
+            Ok(value) => return Ok(Lenient { value, skipped }),
+            Err(error) => error,
+        };
+        let row = T::ROWS.iter().enumerate().find_map(|(field_index, field)| {
+            let rest = pointer.strip_prefix('/')?.strip_prefix(*field)?.strip_prefix('/')?;
+            let index: usize = rest.split('/').next()?.parse().ok()?;
+            Some((field_index, field, index, rest.split_once('/').map_or("", |(_, inner)| inner)))
+        });
+        let Some((field_index, field, index, inner)) = row else {
+            return Err(DataError::Parse(parsed.error(pointer, message)));
+        };
+
+        let original = remaining[field_index].remove(index);
+        if let Some(rows) = parsed.value.get_mut(*field).and_then(Value::as_array_mut) {
+            rows.remove(index);
+        }
+        let pointer = match inner {
+            "" => format!("/{}/{}", field, original),
+            inner => format!("/{}/{}/{}", field, original, inner),
+        };
+        skipped.push(parsed.error(pointer, message));
+    }
+}
+
+/// Parse a file that isn't versioned.
+pub fn parse_data<T: DeserializeOwned>(format: DataFormat, content: &str) -> Result<T, DataError> {
+    format.parse(content).and_then(|parsed| parsed.deserialize()).map_err(DataError::Parse)
+}
+
+fn migrated<T: Document>(format: DataFormat, content: &str) -> Result<Parsed<'_>, DataError> {
+    let mut parsed = format.parse(content).map_err(DataError::Parse)?;
+    parsed.value = MigrationRegistry::default().migrate(T::KIND, parsed.value)?;
+    Ok(parsed)
+}
+
+/// Deserialize `value`, returning the pointer and message of a failure.
+pub(crate) fn deserialize<T: DeserializeOwned>(value: Value) -> Result<T, (String, String)> {
+    serde_path_to_error::deserialize(value).map_err(|e| (json_pointer(e.path()), e.inner().to_string()))
+}
+
+/// The JSON pointer for a deserializer path.
+pub fn json_pointer(path: &serde_path_to_error::Path) -> String {
+    use serde_path_to_error::Segment;
+
+    path.iter()
+        .filter_map(|segment| match segment {
+            Segment::Seq { index } => Some(index.to_string()),
+            Segment::Map { key } => Some(key.replace('~', "~0").replace('/', "~1")),
+            Segment::Enum { .. } | Segment::Unknown => None,
+        })
+        .map(|token| format!("/{}", token))
+        .collect()
+}
+
+/// 1-based line and column of the value at `pointer` in `json`, or of its
+/// deepest ancestor that exists.
+pub fn locate(json: &str, pointer: &str) -> Option<(usize, usize)> {
+    let target: Vec<String> =
+        pointer.split('/').skip(1).map(|token| token.replace("~1", "/").replace("~0", "~")).collect();
+    let mut scanner = Scanner { bytes: json.as_bytes(), pos: 0, found: None };
+    scanner.value(Some(&target));
+    Some(line_column(json, scanner.found?))
+}
+
+/// Walks JSON text, remembering where the deepest value on a path starts.
+struct Scanner<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    found: Option<usize>,
+}
+
+impl Scanner<'_> {
+    /// Skip one value. `target` is the rest of the path when this value is
+    /// on it. Returns `false` once the target is found, to stop scanning.
+    fn value(&mut self, target: Option<&[String]>) -> bool {
+        self.whitespace();
+        if let Some(target) = target {
+            self.found = Some(self.pos);
+            if target.is_empty() {
+                return false;
+            }
+        }
+        let child = |key: &str| target.and_then(|t| (t[0] == key).then(|| &t[1..]));
+
+        match self.bytes.get(self.pos) {
+            Some(b'{') => {
+                self.pos += 1;
+                loop {
+                    self.whitespace();
+                    match self.bytes.get(self.pos) {
+                        Some(b'}') | None => break,
+                        Some(b',') => self.pos += 1,
+                        _ => {
+                            let key = self.string();
+                            self.whitespace();
+                            self.pos += 1; // ':'
+                            if !self.value(child(&key)) {
+                                return false;
+                            }
+                        }
+                    }
+                }
+                self.pos += 1;
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                let mut index = 0;
+                loop {
+                    self.whitespace();
+                    match self.bytes.get(self.pos) {
+                        Some(b']') | None => break,
+                        Some(b',') => self.pos += 1,
+                        _ => {
+                            if !self.value(child(&index.to_string())) {
+                                return false;
+                            }
+                            index += 1;
+                        }
+                    }
+                }
+                self.pos += 1;
+            }
+            Some(b'"') => {
+                self.string();
+            }
+            Some(_) => {
+                while self.bytes.get(self.pos).is_some_and(|b| !b",}] \t\r\n".contains(b)) {
+                    self.pos += 1;
+                }
+            }
+            None => {}
+        }
+        true
+    }
+
+    /// Read a string, returning its raw contents (escapes aren't decoded).
+    fn string(&mut self) -> String {
+        self.pos += 1;
+        let start = self.pos;
+        while let Some(&byte) = self.bytes.get(self.pos) {
+            match byte {
+                b'\\' => self.pos += 2,
+                b'"' => break,
+                _ => self.pos += 1,
+            }
+        }
+        let contents = String::from_utf8_lossy(&self.bytes[start..self.pos.min(self.bytes.len())]).into_owned();
+        self.pos += 1;
+        contents
+    }
+
+    fn whitespace(&mut self) {
+        while self.bytes.get(self.pos).is_some_and(u8::is_ascii_whitespace) {
+            self.pos += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCENE: &str = r#"{
+  "id": "town",
+  "name": "Town",
+  "entities": [
+    { "id": "a", "name": "A", "entity_type": "npc", "components": { "transform": { "position": { "x": 0, "y": 0 } } } },
+    { "id": "b", "name": "B", "entity_type": "npcc", "components": { "transform": { "position": { "x": 0, "y": 0 } } } },
+    { "id": "c", "name": "C", "components": { "transform": { "position": { "x": 0, "y": 0 } } } },
+    { "name": "D", "components": { "transform": { "position": { "x": 0, "y": 0 } } } }
+  ]
+}"#;
+
+    #[test]
+    fn test_locate() {
+        assert_eq!(locate(SCENE, ""), Some((1, 1)));
+        assert_eq!(locate(SCENE, "/entities/1/entity_type"), Some((6, 46)));
+        // Missing keys point at the closest parent.
+        assert_eq!(locate(SCENE, "/entities/3/id"), Some((8, 5)));
+    }
+
+    #[test]
+    fn test_strict_error_location() {
+        let Err(DataError::Parse(error)) = parse_document::<Scene>(DataFormat::Json, SCENE) else {
+            panic!("expected a parse error");
+        };
+        assert_eq!(error.pointer, "/entities/1/entity_type");
+        assert_eq!((error.line, error.column), (Some(6), Some(46)));
+        assert!(error.to_string().starts_with("6:46: at /entities/1/entity_type: unknown variant `npcc`"));
+
+        let Err(DataError::Parse(error)) = parse_document::<Scene>(DataFormat::Json, "{\n  \"id\": \"town\",,\n}") else {
+            panic!("expected a syntax error");
+        };
+        assert_eq!((error.line, error.column, error.pointer.as_str()), (Some(2), Some(16), ""));
+    }
+
+    #[test]
+    fn test_lenient_skips_bad_rows() {
+        let scene = parse_document_lenient::<Scene>(DataFormat::Json, SCENE).unwrap();
+        let ids: Vec<_> = scene.value.entities.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, ["a", "c"]);
+        let pointers: Vec<_> = scene.skipped.iter().map(|e| e.pointer.as_str()).collect();
+        assert_eq!(pointers, ["/entities/1/entity_type", "/entities/3"]);
+        assert_eq!(scene.skipped[1].line, Some(8));
+
+        // Problems outside the rows still fail.
+        assert!(parse_document_lenient::<Scene>(DataFormat::Json, r#"{ "id": 5, "name": "Town" }"#).is_err());
+    }
+}
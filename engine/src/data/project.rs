@@ -4,10 +4,13 @@
 //! referencing all scenes, story graphs, databases, and assets.
 
 use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
 use std::collections::HashMap;
 
+use super::migration::SchemaVersion;
+
 /// Input profile for the game (determines default control schemes).
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum InputProfile {
     /// JRPG-style keyboard/gamepad input
@@ -20,7 +23,7 @@ pub enum InputProfile {
 }
 
 /// Editor theme preference.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum EditorTheme {
     Light,
@@ -29,7 +32,7 @@ pub enum EditorTheme {
 }
 
 /// Editor gizmo mode for transform manipulation.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum GizmoMode {
     #[default]
@@ -39,7 +42,7 @@ pub enum GizmoMode {
 }
 
 /// Editor layout preset.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum LayoutPreset {
     /// Optimized for JRPG map editing
@@ -52,21 +55,21 @@ pub enum LayoutPreset {
 }
 
 /// 2D size with integer dimensions.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
 pub struct Size2i {
     pub width: u32,
     pub height: u32,
 }
 
 /// 2D size with float dimensions.
-#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize, JsonSchema)]
 pub struct Size2f {
     pub x: f32,
     pub y: f32,
 }
 
 /// Localization settings.
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
 pub struct LocalizationSettings {
     /// Available languages (e.g., ["en", "fr", "jp"])
     pub languages: Vec<String>,
@@ -75,7 +78,7 @@ pub struct LocalizationSettings {
 }
 
 /// File path configuration for project assets.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct ProjectPaths {
     /// Path to scenes directory
     pub scenes: String,
@@ -99,7 +102,7 @@ impl Default for ProjectPaths {
 }
 
 /// Autosave configuration.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct AutosaveSettings {
     /// Whether autosave is enabled
     pub enabled: bool,
@@ -120,7 +123,7 @@ impl Default for AutosaveSettings {
 }
 
 /// Editor snap settings for transform gizmos.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct SnapSettings {
     /// Position snap increment
     pub position: f32,
@@ -144,7 +147,7 @@ impl Default for SnapSettings {
 }
 
 /// Project-wide settings that affect both editor and runtime.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct ProjectSettings {
     /// Target platforms
     pub platforms: Vec<String>,
@@ -186,7 +189,7 @@ impl Default for ProjectSettings {
 }
 
 /// Per-user editor preferences (not stored in project).
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct EditorPreferences {
     /// Editor color theme
     pub theme: EditorTheme,
@@ -222,7 +225,7 @@ impl Default for EditorPreferences {
 }
 
 /// Reference to a scene file.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct SceneRef {
     /// Unique scene identifier
     pub id: String,
@@ -231,7 +234,7 @@ pub struct SceneRef {
 }
 
 /// Reference to a story graph file.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct StoryGraphRef {
     /// Unique story graph identifier
     pub id: String,
@@ -243,8 +246,11 @@ pub struct StoryGraphRef {
 ///
 /// A project encompasses all game content: scenes, story graphs,
 /// databases, and asset references.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Project {
+    /// Schema version; loading upgrades older files to the current one
+    #[serde(default)]
+    pub schema_version: SchemaVersion,
     /// Unique project identifier
     pub id: String,
     /// Human-readable project name
@@ -267,6 +273,7 @@ pub struct Project {
 impl Default for Project {
     fn default() -> Self {
         Self {
+            schema_version: SchemaVersion::default(),
             id: uuid::Uuid::new_v4().to_string(),
             name: "New Project".to_string(),
             version: "0.1.0".to_string(),
@@ -4,13 +4,15 @@
 //! Scenes can be JRPG maps, TD maps, or shared between both game types.
 
 use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
 use bevy::prelude::*;
 
 
 use super::components::{EntityComponents, Vec3Data, ColorData};
+use super::migration::SchemaVersion;
 
 /// Scene type categorization.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema, Reflect)]
 #[serde(rename_all = "snake_case")]
 pub enum SceneType {
     /// JRPG-style map (tilemap, NPCs, story triggers)
@@ -23,7 +25,7 @@ pub enum SceneType {
 }
 
 /// Entity type categorization.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema, Reflect)]
 #[serde(rename_all = "snake_case")]
 pub enum EntityType {
     /// Non-player character (friendly)
@@ -48,14 +50,14 @@ pub enum EntityType {
 }
 
 /// 2D size with integer dimensions.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema, Reflect)]
 pub struct TileSize {
     pub width: u32,
     pub height: u32,
 }
 
 /// Default spawn points for player and camera.
-#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema, Reflect)]
 pub struct DefaultSpawn {
     /// Player spawn position
     #[serde(default)]
@@ -66,7 +68,7 @@ pub struct DefaultSpawn {
 }
 
 /// Scene audio settings.
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema, Reflect)]
 pub struct SceneAudio {
     /// Background music track ID
     #[serde(default)]
@@ -79,7 +81,7 @@ pub struct SceneAudio {
 fn default_true() -> bool { true }
 
 /// Scene script hooks.
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema, Reflect)]
 pub struct SceneScripts {
     /// Script to run when entering the scene
     #[serde(default)]
@@ -90,7 +92,7 @@ pub struct SceneScripts {
 }
 
 /// Layer in a scene (for organizing entities).
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema, Reflect)]
 pub struct Layer {
     /// Unique layer identifier
     pub id: String,
@@ -145,7 +147,7 @@ impl Layer {
 }
 
 /// Pathfinding cell for TD maps.
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema, Reflect)]
 pub struct PathfindingCell {
     /// Cell X coordinate
     pub x: i32,
@@ -160,7 +162,7 @@ pub struct PathfindingCell {
 }
 
 /// Pathfinding grid for TD maps.
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema, Reflect)]
 pub struct PathfindingGrid {
     /// Grid width in cells
     pub width: u32,
@@ -172,7 +174,7 @@ pub struct PathfindingGrid {
 }
 
 /// Scene pathfinding configuration.
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema, Reflect)]
 pub struct ScenePathfinding {
     /// Whether pathfinding is enabled for this scene
     #[serde(default)]
@@ -183,7 +185,7 @@ pub struct ScenePathfinding {
 }
 
 /// An entity in a scene.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema, Reflect)]
 pub struct Entity {
     /// Unique entity identifier
     pub id: String,
@@ -249,8 +251,11 @@ impl Entity {
 }
 
 /// A complete scene/map.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema, Reflect)]
 pub struct Scene {
+    /// Schema version; loading upgrades older files to the current one
+    #[serde(default)]
+    pub schema_version: SchemaVersion,
     /// Unique scene identifier
     pub id: String,
     /// Human-readable scene name
@@ -294,6 +299,7 @@ fn default_tile_size() -> TileSize {
 impl Default for Scene {
     fn default() -> Self {
         Self {
+            schema_version: SchemaVersion::default(),
             id: String::new(),
             name: "New Scene".to_string(),
             scene_type: SceneType::default(),
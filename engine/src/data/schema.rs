@@ -0,0 +1,87 @@
+//! JSON Schemas for the data files.
+//!
+//! Generated from the Rust types, so they list every field, enum variant
+//! and serde default. The engine keeps a copy in `engine/schemas`; point
+//! an editor at them (in VS Code, through the `json.schemas` setting) to
+//! get completion and validation while editing data by hand.
+//!
+//! Regenerate the copies with `cargo run -p dj_engine --bin json_schema`
+//! after changing a data type; a test fails while they are out of date.
+
+use schemars::schema::RootSchema;
+use schemars::schema_for;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::assets::AssetIndex;
+use super::database::Database;
+use super::loader::DataError;
+use super::project::Project;
+use super::scene::Scene;
+use super::story::StoryGraphData;
+
+/// Where the engine keeps its generated schemas.
+pub const SCHEMA_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/schemas");
+
+/// The schema of each kind of data file, by file name.
+pub fn schemas() -> Vec<(&'static str, RootSchema)> {
+    vec![
+        ("project.schema.json", schema_for!(Project)),
+        ("scene.schema.json", schema_for!(Scene)),
+        ("database.schema.json", schema_for!(Database)),
+        ("story_graph.schema.json", schema_for!(StoryGraphData)),
+        ("asset_index.schema.json", schema_for!(AssetIndex)),
+    ]
+}
+
+/// A schema as written to disk.
+pub fn to_json(schema: &RootSchema) -> Result<String, DataError> {
+    Ok(serde_json::to_string_pretty(schema)? + "\n")
+}
+
+/// Write every schema into `dir`.
+///
+/// # Returns
+/// The files written
+pub fn write_schemas(dir: &Path) -> Result<Vec<PathBuf>, DataError> {
+    fs::create_dir_all(dir)?;
+    schemas()
+        .into_iter()
+        .map(|(name, schema)| {
+            let path = dir.join(name);
+            fs::write(&path, to_json(&schema)?)?;
+            Ok(path)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schemas_up_to_date() {
+        for (name, schema) in schemas() {
+            let path = Path::new(SCHEMA_DIR).join(name);
+            let committed = fs::read_to_string(&path).unwrap_or_default().replace("\r\n", "\n");
+            assert!(
+                committed == to_json(&schema).unwrap(),
+                "{} is out of date; run `cargo run -p dj_engine --bin json_schema`",
+                path.display()
+            );
+        }
+    }
+
+    #[test]
+    fn test_schema_covers_variants_and_defaults() {
+        let schema = serde_json::to_value(schema_for!(Scene)).unwrap();
+        let definitions = &schema["definitions"];
+        let entity_types = definitions["EntityType"]["oneOf"].as_array().unwrap();
+        assert!(entity_types.iter().any(|variant| variant["enum"][0] == "npc"));
+        assert_eq!(definitions["CollisionShape"]["enum"], serde_json::json!(["box", "circle", "polygon"]));
+        assert_eq!(schema["properties"]["schema_version"]["default"], crate::data::SCHEMA_VERSION);
+
+        let schema = serde_json::to_value(schema_for!(StoryGraphData)).unwrap();
+        assert!(schema["definitions"]["StoryNodeVariant"].is_object());
+    }
+}
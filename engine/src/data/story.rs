@@ -4,14 +4,16 @@
 //! the existing `story_graph::StoryNode` runtime types with JSON support.
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use schemars::JsonSchema;
+use std::collections::{BTreeMap, HashMap};
 use bevy::prelude::*;
 
 use super::components::Vec3Data;
+use super::migration::SchemaVersion;
 use super::scene::{Scene, EntityType};
 
 /// Story graph type categorization.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema, Reflect)]
 #[serde(rename_all = "snake_case")]
 pub enum StoryGraphType {
     /// Dialogue/conversation
@@ -24,7 +26,7 @@ pub enum StoryGraphType {
 }
 
 /// Story node type enumeration.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Reflect)]
 #[serde(rename_all = "snake_case")]
 pub enum StoryNodeType {
     Start,
@@ -36,10 +38,16 @@ pub enum StoryNodeType {
     Action,
     /// Conditional branch
     Conditional,
+    /// Apply effects (set variables, give items, ...)
+    Effect,
     /// Camera movement
     Camera,
     /// Time/pause control
     TimeControl,
+    /// Run another story graph, then continue
+    Call,
+    /// Return from a called graph
+    Return,
     /// End of branch
     End,
 }
@@ -52,7 +60,7 @@ pub enum StoryNodeType {
 
 
 /// Condition operator for story conditions.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema, Reflect)]
 #[serde(rename_all = "snake_case")]
 pub enum ConditionOperator {
     #[default]
@@ -66,7 +74,7 @@ pub enum ConditionOperator {
 }
 
 /// End node behavior.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema, Reflect)]
 #[serde(rename_all = "snake_case")]
 pub enum EndType {
     /// Return to normal gameplay
@@ -79,7 +87,7 @@ pub enum EndType {
 }
 
 /// Effect type for story effects.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Reflect)]
 #[serde(rename_all = "snake_case")]
 pub enum EffectType {
     /// Set a variable
@@ -95,7 +103,7 @@ pub enum EffectType {
 }
 
 /// Requirement: Entity must exist in the scene.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Reflect)]
 pub struct RequiredEntity {
     /// Entity ID that must exist
     pub entity_id: String,
@@ -105,7 +113,7 @@ pub struct RequiredEntity {
 }
 
 /// Requirement: Item must exist in inventory (or be available).
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Reflect)]
 pub struct RequiredItem {
     /// Item ID that is required
     pub item_id: String,
@@ -120,7 +128,7 @@ fn default_one() -> u32 { 1 }
 pub type LocalizedString = HashMap<String, String>;
 
 /// A condition for story branching.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema, Reflect)]
 pub struct StoryCondition {
     /// Variable name to check
     pub variable: String,
@@ -133,7 +141,7 @@ pub struct StoryCondition {
 }
 
 /// An effect/action that modifies game state.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema, Reflect)]
 pub struct StoryEffect {
     /// Effect type
     #[serde(rename = "type")]
@@ -145,7 +153,7 @@ pub struct StoryEffect {
 }
 
 /// Dialogue node data.
-#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema, Reflect)]
 pub struct DialogueNodeData {
     /// Speaker ID (NPC, party member, or "narrator")
     pub speaker_id: String,
@@ -166,7 +174,7 @@ pub struct DialogueNodeData {
 }
 
 /// A choice option in a choice node.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema, Reflect)]
 pub struct ChoiceOption {
     /// Unique option identifier
     pub id: String,
@@ -174,26 +182,38 @@ pub struct ChoiceOption {
     pub text: LocalizedString,
     /// Target node ID when selected
     pub target_node_id: String,
+    /// Story flag that must be set to show this option
+    #[serde(default)]
+    pub flag_required: Option<String>,
     /// Conditions to show this option
     #[serde(default)]
     pub conditions: Vec<StoryCondition>,
     /// Effects when this option is selected
     #[serde(default)]
     pub effects: Vec<StoryEffect>,
+    /// Show greyed-out instead of hiding when conditions fail
+    #[serde(default)]
+    pub show_when_unavailable: bool,
 }
 
 /// Choice node data.
-#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema, Reflect)]
 pub struct ChoiceNodeData {
     /// Optional prompt text per language
     #[serde(default)]
     pub prompt: LocalizedString,
     /// Available choice options
     pub options: Vec<ChoiceOption>,
+    /// Seconds the player has to choose before `default_option` is picked
+    #[serde(default)]
+    pub timeout: Option<f32>,
+    /// Option picked when `timeout` runs out (else the first available one)
+    #[serde(default)]
+    pub default_option: Option<String>,
 }
 
 /// Action node data.
-#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema, Reflect)]
 pub struct ActionNodeData {
     /// Lua script ID to execute
     pub lua_script_id: String,
@@ -207,7 +227,7 @@ pub struct ActionNodeData {
 }
 
 /// Conditional node data.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema, Reflect)]
 pub struct ConditionalNodeData {
     /// Condition to evaluate
     pub condition: StoryCondition,
@@ -217,8 +237,18 @@ pub struct ConditionalNodeData {
     pub false_target_node_id: String,
 }
 
+/// Effect node data.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema, Reflect)]
+pub struct EffectNodeData {
+    /// Effects to apply, in order
+    pub effects: Vec<StoryEffect>,
+    /// Next node ID
+    #[serde(default)]
+    pub next_node_id: Option<String>,
+}
+
 /// Camera node data.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema, Reflect)]
 pub struct CameraNodeData {
     /// Camera preset ID
     #[serde(default)]
@@ -261,7 +291,7 @@ impl Default for CameraNodeData {
 }
 
 /// Time control node data.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema, Reflect)]
 pub struct TimeControlNodeData {
     /// Whether to pause gameplay
     #[serde(default)]
@@ -287,7 +317,7 @@ impl Default for TimeControlNodeData {
 }
 
 /// End node data.
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema, Reflect)]
 pub struct EndNodeData {
     /// End behavior type
     #[serde(default)]
@@ -297,7 +327,24 @@ pub struct EndNodeData {
     pub target_scene_id: Option<String>,
 }
 
-#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, Reflect)]
+/// Call node data.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema, Reflect)]
+pub struct CallNodeData {
+    /// Story graph to run (see `Project::story_graphs`)
+    pub graph_id: String,
+    /// Node to enter at (defaults to the graph's root node)
+    #[serde(default)]
+    pub entry_node: Option<String>,
+    /// Node ID to continue at once the called graph returns
+    #[serde(default)]
+    pub next_node_id: Option<String>,
+}
+
+/// Return node data.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema, Reflect)]
+pub struct ReturnNodeData {}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema, Reflect)]
 pub struct StartNodeData {
     /// Next node ID logic should flow to
     #[serde(default)]
@@ -305,7 +352,7 @@ pub struct StartNodeData {
 }
 
 /// Story node variant data (tagged union).
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema, Reflect)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum StoryNodeVariant {
     Start(StartNodeData),
@@ -313,13 +360,16 @@ pub enum StoryNodeVariant {
     Choice(ChoiceNodeData),
     Action(ActionNodeData),
     Conditional(ConditionalNodeData),
+    Effect(EffectNodeData),
     Camera(CameraNodeData),
     TimeControl(TimeControlNodeData),
+    Call(CallNodeData),
+    Return(ReturnNodeData),
     End(EndNodeData),
 }
 
 /// A node in a story graph.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema, Reflect)]
 pub struct StoryNodeData {
     /// Unique node identifier
     pub id: String,
@@ -387,8 +437,11 @@ impl StoryNodeData {
             StoryNodeVariant::Choice(_) => StoryNodeType::Choice,
             StoryNodeVariant::Action(_) => StoryNodeType::Action,
             StoryNodeVariant::Conditional(_) => StoryNodeType::Conditional,
+            StoryNodeVariant::Effect(_) => StoryNodeType::Effect,
             StoryNodeVariant::Camera(_) => StoryNodeType::Camera,
             StoryNodeVariant::TimeControl(_) => StoryNodeType::TimeControl,
+            StoryNodeVariant::Call(_) => StoryNodeType::Call,
+            StoryNodeVariant::Return(_) => StoryNodeType::Return,
             StoryNodeVariant::End(_) => StoryNodeType::End,
         }
     }
@@ -401,9 +454,11 @@ impl StoryNodeData {
             StoryNodeVariant::Choice(c) => c.options.iter().map(|o| o.target_node_id.as_str()).collect(),
             StoryNodeVariant::Action(a) => a.next_node_id.as_deref().into_iter().collect(),
             StoryNodeVariant::Conditional(c) => vec![c.true_target_node_id.as_str(), c.false_target_node_id.as_str()],
+            StoryNodeVariant::Effect(e) => e.next_node_id.as_deref().into_iter().collect(),
             StoryNodeVariant::Camera(c) => c.next_node_id.as_deref().into_iter().collect(),
             StoryNodeVariant::TimeControl(t) => t.next_node_id.as_deref().into_iter().collect(),
-            StoryNodeVariant::End(_) => vec![],
+            StoryNodeVariant::Call(c) => c.next_node_id.as_deref().into_iter().collect(),
+            StoryNodeVariant::Return(_) | StoryNodeVariant::End(_) => vec![],
         }
     }
 }
@@ -419,6 +474,16 @@ pub enum ValidationError {
     DeadEnd(String),
     /// Unreachable node
     UnreachableNode(String),
+    /// A Call node targets a graph that doesn't exist
+    UnknownGraph { from_node: String, graph_id: String },
+    /// A Call node enters a graph at a node that doesn't exist
+    UnknownEntryNode { from_node: String, graph_id: String, node_id: String },
+    /// Graphs call each other in a cycle (graph ids, first repeated last)
+    RecursiveCall(Vec<String>),
+    /// Text has malformed markup (`key` as in [`StoryGraphData::missing_translations`])
+    MalformedMarkup { key: String, language: String, message: String },
+    /// A timed choice's default option doesn't exist
+    UnknownDefaultOption { node_id: String, option_id: String },
 }
 
 /// Validation error when checking against a scene.
@@ -431,8 +496,11 @@ pub enum SceneValidationError {
 }
 
 /// A complete story graph.
-#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, Reflect)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema, Reflect)]
 pub struct StoryGraphData {
+    /// Schema version; loading upgrades older files to the current one
+    #[serde(default)]
+    pub schema_version: SchemaVersion,
     /// Unique graph identifier
     pub id: String,
     /// Human-readable name
@@ -458,6 +526,7 @@ impl StoryGraphData {
     /// Create a new empty story graph.
     pub fn new(id: impl Into<String>, name: impl Into<String>) -> Self {
         Self {
+            schema_version: SchemaVersion::default(),
             id: id.into(),
             name: name.into(),
             description: String::new(),
@@ -499,17 +568,96 @@ impl StoryGraphData {
                 }
             }
 
-            // Check for dead ends (nodes with no outgoing edges that aren't End nodes)
-            if node.next_node_ids().is_empty() && !matches!(node.data, StoryNodeVariant::End(_)) {
+            // Check for dead ends (nodes with no outgoing edges that aren't End/Return nodes)
+            if super::analysis::is_dead_end(&node.data, node.next_node_ids().is_empty()) {
                 errors.push(ValidationError::DeadEnd(node.id.clone()));
             }
+
+            if let StoryNodeVariant::Choice(c) = &node.data {
+                if let Some(default) = &c.default_option {
+                    if !c.options.iter().any(|o| &o.id == default) {
+                        errors.push(ValidationError::UnknownDefaultOption {
+                            node_id: node.id.clone(),
+                            option_id: default.clone(),
+                        });
+                    }
+                }
+            }
         }
 
-        // TODO: Check for unreachable nodes (would need graph traversal)
+        // Unreachable nodes aren't errors here: other graphs may call into
+        // them. `analysis::analyze_project` reports them with those entries
+        // taken into account.
+
+        // Check text markup in every language
+        for (key, text) in self.localized_texts() {
+            let mut languages: Vec<_> = text.iter().collect();
+            languages.sort();
+            for (language, text) in languages {
+                if let Err(e) = crate::story_graph::markup::parse(text) {
+                    errors.push(ValidationError::MalformedMarkup {
+                        key: key.clone(),
+                        language: language.clone(),
+                        message: e.to_string(),
+                    });
+                }
+            }
+        }
 
         errors
     }
 
+    /// Every player-facing text, keyed `<node>.text`, `<node>.prompt` or
+    /// `<node>.<option id>`. Unauthored prompts are skipped.
+    fn localized_texts(&self) -> Vec<(String, &LocalizedString)> {
+        let mut texts = Vec::new();
+        for node in &self.nodes {
+            match &node.data {
+                StoryNodeVariant::Dialogue(d) => texts.push((format!("{}.text", node.id), &d.text)),
+                StoryNodeVariant::Choice(c) => {
+                    if !c.prompt.is_empty() {
+                        texts.push((format!("{}.prompt", node.id), &c.prompt));
+                    }
+                    for option in &c.options {
+                        texts.push((format!("{}.{}", node.id, option.id), &option.text));
+                    }
+                }
+                _ => {}
+            }
+        }
+        texts
+    }
+
+    /// Data of every Call node in this graph.
+    pub fn called_graphs(&self) -> impl Iterator<Item = &CallNodeData> {
+        self.nodes.iter().filter_map(|n| match &n.data {
+            StoryNodeVariant::Call(c) => Some(c),
+            _ => None,
+        })
+    }
+
+    /// Translation keys missing for each of `languages`.
+    ///
+    /// Keys are `<node>.text`, `<node>.prompt` and `<node>.<option id>`.
+    /// Prompts that were never authored are skipped; languages with
+    /// nothing missing are left out.
+    pub fn missing_translations(&self, languages: &[String]) -> BTreeMap<String, Vec<String>> {
+        let texts = self.localized_texts();
+
+        let mut missing = BTreeMap::new();
+        for language in languages {
+            let keys: Vec<String> = texts
+                .iter()
+                .filter(|(_, text)| text.get(language).is_none_or(|t| t.is_empty()))
+                .map(|(key, _)| key.clone())
+                .collect();
+            if !keys.is_empty() {
+                missing.insert(language.clone(), keys);
+            }
+        }
+        missing
+    }
+
     /// Validate the story graph against a specific scene.
     pub fn validate_against_scene(&self, scene: &Scene) -> Vec<SceneValidationError> {
         let mut errors = Vec::new();
@@ -545,6 +693,70 @@ impl StoryGraphData {
 
 }
 
+/// Validate Call nodes across a set of graphs.
+///
+/// Reports calls to missing graphs or entry nodes, and each cycle of graphs
+/// that (directly or indirectly) call themselves.
+pub fn validate_calls(graphs: &[StoryGraphData]) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    let by_id: HashMap<&str, &StoryGraphData> = graphs.iter().map(|g| (g.id.as_str(), g)).collect();
+
+    for graph in graphs {
+        for node in &graph.nodes {
+            let StoryNodeVariant::Call(call) = &node.data else { continue };
+            match by_id.get(call.graph_id.as_str()) {
+                None => errors.push(ValidationError::UnknownGraph {
+                    from_node: node.id.clone(),
+                    graph_id: call.graph_id.clone(),
+                }),
+                Some(target) => {
+                    if let Some(entry) = &call.entry_node {
+                        if target.find_node(entry).is_none() {
+                            errors.push(ValidationError::UnknownEntryNode {
+                                from_node: node.id.clone(),
+                                graph_id: call.graph_id.clone(),
+                                node_id: entry.clone(),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Depth-first search over the call graph; a back edge is a cycle.
+    fn visit<'a>(
+        id: &'a str,
+        by_id: &HashMap<&str, &'a StoryGraphData>,
+        path: &mut Vec<&'a str>,
+        done: &mut std::collections::HashSet<&'a str>,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        if let Some(start) = path.iter().position(|p| *p == id) {
+            let mut cycle: Vec<String> = path[start..].iter().map(|s| s.to_string()).collect();
+            cycle.push(id.to_string());
+            errors.push(ValidationError::RecursiveCall(cycle));
+            return;
+        }
+        if !done.insert(id) {
+            return;
+        }
+        let Some(graph) = by_id.get(id) else { return };
+        path.push(id);
+        for call in graph.called_graphs() {
+            visit(call.graph_id.as_str(), by_id, path, done, errors);
+        }
+        path.pop();
+    }
+
+    let mut done = std::collections::HashSet::new();
+    for graph in graphs {
+        visit(graph.id.as_str(), &by_id, &mut Vec::new(), &mut done, &mut errors);
+    }
+
+    errors
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -565,6 +777,7 @@ mod tests {
     #[test]
     fn test_validation_missing_root() {
         let graph = StoryGraphData {
+            schema_version: SchemaVersion::default(),
             id: "test".to_string(),
             name: "Test".to_string(),
             description: String::new(),
@@ -579,6 +792,100 @@ mod tests {
         assert!(errors.iter().any(|e| matches!(e, ValidationError::MissingRootNode(_))));
     }
 
+    #[test]
+    fn test_validation_allows_other_entries() {
+        // `side` is only entered by a Call from another graph.
+        let mut graph = StoryGraphData::new("library", "Library");
+        graph.root_node_id = "end".to_string();
+        graph.add_node(StoryNodeData::end("end"));
+        graph.add_node(StoryNodeData::end("side"));
+
+        assert!(graph.validate().is_empty());
+    }
+
+    #[test]
+    fn test_missing_translations() {
+        let mut graph = StoryGraphData::new("intro", "Intro");
+        let mut hello = StoryNodeData::dialogue("hello", "Narrator", "Hello");
+        if let StoryNodeVariant::Dialogue(d) = &mut hello.data {
+            d.text.insert("fr".to_string(), "Bonjour".to_string());
+        }
+        graph.add_node(hello);
+        graph.add_node(StoryNodeData::dialogue("bye", "Narrator", "Bye"));
+
+        let languages = ["en".to_string(), "fr".to_string(), "jp".to_string()];
+        let missing = graph.missing_translations(&languages);
+        assert!(!missing.contains_key("en"));
+        assert_eq!(missing["fr"], vec!["bye.text".to_string()]);
+        assert_eq!(missing["jp"], vec!["hello.text".to_string(), "bye.text".to_string()]);
+    }
+
+    #[test]
+    fn test_validate_markup() {
+        let mut graph = StoryGraphData::new("intro", "Intro");
+        graph.root_node_id = "hello".to_string();
+        let mut hello = StoryNodeData::dialogue("hello", "Narrator", "[color=gold]{name}[/color]!");
+        if let StoryNodeVariant::Dialogue(d) = &mut hello.data {
+            d.text.insert("fr".to_string(), "[shake]{name}!".to_string());
+            d.next_node_id = Some("end".to_string());
+        }
+        graph.add_node(hello);
+        graph.add_node(StoryNodeData::end("end"));
+
+        let errors = graph.validate();
+        assert_eq!(errors.len(), 2);
+        assert!(matches!(
+            &errors[0],
+            ValidationError::MalformedMarkup { key, language, .. } if key == "hello.text" && language == "en"
+        ));
+        assert!(matches!(&errors[1], ValidationError::MalformedMarkup { language, .. } if language == "fr"));
+    }
+
+    fn call_node(id: &str, graph_id: &str, next: Option<&str>) -> StoryNodeData {
+        let mut node = StoryNodeData::end(id);
+        node.data = StoryNodeVariant::Call(CallNodeData {
+            graph_id: graph_id.to_string(),
+            entry_node: None,
+            next_node_id: next.map(str::to_string),
+        });
+        node
+    }
+
+    #[test]
+    fn test_validate_calls() {
+        let mut main = StoryGraphData::new("main", "Main");
+        main.add_node(call_node("shop", "shop", Some("end")));
+        main.add_node(call_node("missing", "nowhere", Some("end")));
+        main.add_node(StoryNodeData::end("end"));
+
+        let mut shop = StoryGraphData::new("shop", "Shop");
+        shop.add_node(call_node("haggle", "haggle", Some("done")));
+        let mut done = StoryNodeData::end("done");
+        done.data = StoryNodeVariant::Return(ReturnNodeData {});
+        shop.add_node(done);
+
+        let mut haggle = StoryGraphData::new("haggle", "Haggle");
+        haggle.add_node(call_node("again", "shop", Some("end")));
+        haggle.add_node(StoryNodeData::end("end"));
+
+        let errors = validate_calls(&[main.clone(), shop.clone(), haggle]);
+        assert!(errors.contains(&ValidationError::UnknownGraph {
+            from_node: "missing".to_string(),
+            graph_id: "nowhere".to_string(),
+        }));
+        let cycles: Vec<_> = errors
+            .iter()
+            .filter_map(|e| match e {
+                ValidationError::RecursiveCall(cycle) => Some(cycle.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(cycles, vec![vec!["shop".to_string(), "haggle".to_string(), "shop".to_string()]]);
+
+        // Return nodes are not dead ends.
+        assert!(shop.validate().iter().all(|e| !matches!(e, ValidationError::DeadEnd(_))));
+    }
+
     #[test]
     fn test_validate_against_scene() {
         use crate::data::scene::{Scene, Entity, EntityType};
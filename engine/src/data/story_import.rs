@@ -0,0 +1,1133 @@
+//! Importers for Twine (Twee 3) and Yarn Spinner stories.
+//!
+//! Both formats are lowered onto the usual story nodes:
+//!
+//! - Text becomes Dialogue nodes. Twine text is spoken by `narrator`; Yarn
+//!   lines may start with `Speaker:`. `$var` (Twine) and `{$var}` (Yarn)
+//!   become `{var}` markup.
+//! - Links (`[[Target]]`, `[[text|Target]]`, `[[text->Target]]`,
+//!   `[[Target<-text]]`) and Yarn `-> option` lines become
+//!   [`ChoiceOption`]s. Twine setter links (`[[text|Target][$x to 1]]`)
+//!   carry their assignments as option effects.
+//! - `<<set>>` becomes `SetVar`/`AddVar` effects and `<<if>>`, `<<elseif>>`
+//!   and `<<else>>` become Conditional nodes. Links inside an `<<if>>` get
+//!   its condition as an option condition.
+//! - `<<goto>>` (Twine) and `<<jump>>` (Yarn) jump, `<<stop>>` ends.
+//! - Twine's `StoryInit` assignments and Yarn's `<<declare>>` give the
+//!   graph's initial variables.
+//!
+//! Twine macros use SugarCube's `<<macro>>` syntax. Other macros and
+//! commands are skipped and reported as [`ImportWarning`]s, as are passages
+//! that can't be reached from the start, so imported graphs pass
+//! [`StoryGraphData::validate`]. Expressions the graph can't represent
+//! (`or`, arithmetic, comparing two variables) are [`ImportError`]s.
+//!
+//! A passage's first node is named after it and later ones `Title.1`,
+//! `Title.2`, ...
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use thiserror::Error;
+
+use super::analysis::reachable_nodes;
+use super::story::{
+    ChoiceNodeData, ChoiceOption, ConditionOperator, ConditionalNodeData, DialogueNodeData,
+    EffectNodeData, EffectType, EndNodeData, LocalizedString, StoryCondition, StoryEffect,
+    StoryGraphData, StoryNodeVariant,
+};
+use super::story_script::{node, text as localized};
+
+/// Speaker of text that doesn't name one.
+pub const NARRATOR: &str = "narrator";
+
+/// Errors that stop an import.
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ImportError {
+    /// 1-based line in the source
+    #[error("line {line}: {message}")]
+    Syntax { line: usize, message: String },
+
+    #[error("no passages or nodes found")]
+    Empty,
+}
+
+/// Something the importer skipped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportWarning {
+    /// 1-based line in the source
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ImportWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// An imported graph and what was left out of it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedGraph {
+    pub graph: StoryGraphData,
+    pub warnings: Vec<ImportWarning>,
+}
+
+/// Import a Twee 3 source as a story graph with the given id.
+///
+/// The start passage comes from `StoryData`, then a passage named `Start`,
+/// then the first passage. `StoryTitle` names the graph.
+pub fn import_twee(id: &str, source: &str) -> Result<ImportedGraph, ImportError> {
+    build(id, parse_twee(source)?)
+}
+
+/// Import a Yarn Spinner source as a story graph with the given id.
+///
+/// The start node is the one titled `Start`, or else the first node.
+pub fn import_yarn(id: &str, source: &str) -> Result<ImportedGraph, ImportError> {
+    build(id, parse_yarn(source)?)
+}
+
+fn error(line: usize, message: impl Into<String>) -> ImportError {
+    ImportError::Syntax { line, message: message.into() }
+}
+
+// --- Shared structure ------------------------------------------------------
+
+#[derive(Debug, Clone)]
+enum Item {
+    Line { speaker: String, text: String },
+    Set(StoryEffect),
+    If { branches: Vec<(Vec<StoryCondition>, Vec<Item>)>, otherwise: Vec<Item> },
+    Options(Vec<Opt>),
+    Jump { target: String, line: usize },
+    Stop,
+}
+
+#[derive(Debug, Clone)]
+struct Opt {
+    text: String,
+    conditions: Vec<StoryCondition>,
+    effects: Vec<StoryEffect>,
+    /// Passage to go to; without one `body` runs and flow continues after
+    /// the options
+    target: Option<(String, usize)>,
+    body: Vec<Item>,
+}
+
+/// A Twine passage or Yarn node.
+#[derive(Debug)]
+struct Section {
+    title: String,
+    line: usize,
+    items: Vec<Item>,
+}
+
+#[derive(Debug, Default)]
+struct Story {
+    name: Option<String>,
+    start: Option<(String, usize)>,
+    variables: Vec<(String, serde_json::Value)>,
+    sections: Vec<Section>,
+    warnings: Vec<ImportWarning>,
+}
+
+/// What closed a block.
+#[derive(Debug)]
+enum Terminator {
+    ElseIf(Vec<StoryCondition>),
+    Else,
+    EndIf,
+}
+
+type Block = (Vec<Item>, Option<(Terminator, usize)>);
+
+/// Parse the rest of an `<<if>>` chain with `block`, which is given the
+/// conditions links in each branch need (or `None` if they can't be
+/// expressed as option conditions).
+fn if_chain(
+    first: Vec<StoryCondition>,
+    line: usize,
+    path: Option<&[StoryCondition]>,
+    mut block: impl FnMut(Option<&[StoryCondition]>) -> Result<Block, ImportError>,
+) -> Result<Item, ImportError> {
+    let mut branches = Vec::new();
+    let mut conditions = first;
+    // Conditions that hold once every branch so far was skipped.
+    let mut skipped = path.map(<[_]>::to_vec);
+    loop {
+        let branch_path = skipped.as_ref().map(|p| [p.as_slice(), conditions.as_slice()].concat());
+        let (body, end) = block(branch_path.as_deref())?;
+        skipped = skipped.zip(negate(&conditions)).map(|(mut p, c)| {
+            p.push(c);
+            p
+        });
+        branches.push((std::mem::take(&mut conditions), body));
+        match end {
+            Some((Terminator::ElseIf(next), _)) => conditions = next,
+            Some((Terminator::Else, _)) => {
+                return match block(skipped.as_deref())? {
+                    (otherwise, Some((Terminator::EndIf, _))) => Ok(Item::If { branches, otherwise }),
+                    (_, Some((_, l))) => Err(error(l, "expected the `<<if>>` to close after `<<else>>`")),
+                    (_, None) => Err(error(line, "`<<if>>` is never closed")),
+                };
+            }
+            Some((Terminator::EndIf, _)) => return Ok(Item::If { branches, otherwise: Vec::new() }),
+            None => return Err(error(line, "`<<if>>` is never closed")),
+        }
+    }
+}
+
+// --- Expressions -----------------------------------------------------------
+
+const OPERATORS: [(&str, ConditionOperator); 17] = [
+    ("===", ConditionOperator::Equals),
+    ("!==", ConditionOperator::NotEquals),
+    ("==", ConditionOperator::Equals),
+    ("!=", ConditionOperator::NotEquals),
+    ("<=", ConditionOperator::LessThanOrEquals),
+    (">=", ConditionOperator::GreaterThanOrEquals),
+    ("<", ConditionOperator::LessThan),
+    (">", ConditionOperator::GreaterThan),
+    (" is not ", ConditionOperator::NotEquals),
+    (" isnot ", ConditionOperator::NotEquals),
+    (" neq ", ConditionOperator::NotEquals),
+    (" is ", ConditionOperator::Equals),
+    (" eq ", ConditionOperator::Equals),
+    (" lte ", ConditionOperator::LessThanOrEquals),
+    (" gte ", ConditionOperator::GreaterThanOrEquals),
+    (" lt ", ConditionOperator::LessThan),
+    (" gt ", ConditionOperator::GreaterThan),
+];
+
+fn variable(token: &str, line: usize) -> Result<String, ImportError> {
+    match token.trim().strip_prefix('$') {
+        Some(name) if !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_') => {
+            Ok(name.to_string())
+        }
+        _ => Err(error(line, format!("expected a `$variable`, found `{}`", token.trim()))),
+    }
+}
+
+fn literal(text: &str, line: usize) -> Result<serde_json::Value, ImportError> {
+    let text = text.trim();
+    if let Some(s) = text.strip_prefix('\'').and_then(|t| t.strip_suffix('\'')) {
+        return Ok(serde_json::Value::String(s.to_string()));
+    }
+    match serde_json::from_str(text) {
+        Ok(value @ (serde_json::Value::Bool(_) | serde_json::Value::Number(_) | serde_json::Value::String(_))) => {
+            Ok(value)
+        }
+        _ => Err(error(line, format!("unsupported expression `{}`", text))),
+    }
+}
+
+/// Parse `a and b and ...` into conditions that must all hold.
+fn condition(text: &str, line: usize) -> Result<Vec<StoryCondition>, ImportError> {
+    let text = text.trim();
+    if text.contains(" or ") || text.contains("||") {
+        return Err(error(line, format!("`or` conditions can't be imported: `{}`", text)));
+    }
+    text.split(" and ")
+        .flat_map(|part| part.split("&&"))
+        .map(|part| comparison(part.trim(), line))
+        .collect()
+}
+
+fn comparison(text: &str, line: usize) -> Result<StoryCondition, ImportError> {
+    for (symbol, operator) in OPERATORS {
+        if let Some((name, value)) = text.split_once(symbol) {
+            return Ok(StoryCondition { variable: variable(name, line)?, operator, value: literal(value, line)? });
+        }
+    }
+    let (name, value) = match text.strip_prefix("not ").or_else(|| text.strip_prefix('!')) {
+        Some(name) => (name, false),
+        None => (text, true),
+    };
+    Ok(StoryCondition {
+        variable: variable(name, line)?,
+        operator: ConditionOperator::Equals,
+        value: serde_json::Value::Bool(value),
+    })
+}
+
+/// The opposite of a single condition.
+fn negate(conditions: &[StoryCondition]) -> Option<StoryCondition> {
+    let [condition] = conditions else { return None };
+    let operator = match condition.operator {
+        ConditionOperator::Equals => ConditionOperator::NotEquals,
+        ConditionOperator::NotEquals => ConditionOperator::Equals,
+        ConditionOperator::LessThan => ConditionOperator::GreaterThanOrEquals,
+        ConditionOperator::GreaterThanOrEquals => ConditionOperator::LessThan,
+        ConditionOperator::GreaterThan => ConditionOperator::LessThanOrEquals,
+        ConditionOperator::LessThanOrEquals => ConditionOperator::GreaterThan,
+        ConditionOperator::Contains => return None,
+    };
+    Some(StoryCondition { operator, ..condition.clone() })
+}
+
+/// Parse `$x to v`, `$x = v`, `$x += n` or `$x -= n`.
+fn assignment(text: &str, line: usize) -> Result<StoryEffect, ImportError> {
+    let amount = |value: &str, sign: i64| {
+        let value = literal(value, line)?;
+        match (value.as_i64(), value.as_f64()) {
+            (Some(i), _) => Ok(serde_json::json!(sign * i)),
+            (None, Some(f)) => Ok(serde_json::json!(sign as f64 * f)),
+            _ => Err(error(line, format!("expected a number, found `{}`", value))),
+        }
+    };
+    let (name, effect_type, value) = if let Some((name, value)) = text.split_once("+=") {
+        (name, EffectType::AddVar, amount(value, 1)?)
+    } else if let Some((name, value)) = text.split_once("-=") {
+        (name, EffectType::AddVar, amount(value, -1)?)
+    } else if let Some((name, value)) = text.split_once(" to ").or_else(|| text.split_once('=')) {
+        (name, EffectType::SetVar, literal(value, line)?)
+    } else {
+        return Err(error(line, format!("expected `$variable to value`, found `{}`", text.trim())));
+    };
+    Ok(StoryEffect {
+        effect_type,
+        params: HashMap::from([
+            ("variable".to_string(), serde_json::Value::String(variable(name, line)?)),
+            ("value".to_string(), value),
+        ]),
+    })
+}
+
+/// Turn source text into story markup: brackets and braces are escaped and
+/// `$var` or `{$var}` becomes `{var}`.
+fn markup(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '$' || (c == '{' && chars.get(i + 1) == Some(&'$')) {
+            let start = if c == '$' { i + 1 } else { i + 2 };
+            let end = start
+                + chars[start..].iter().take_while(|c| c.is_alphanumeric() || **c == '_').count();
+            let closed = c == '$' || chars.get(end) == Some(&'}');
+            if end > start && closed {
+                out.push('{');
+                out.extend(&chars[start..end]);
+                out.push('}');
+                i = if c == '$' { end } else { end + 1 };
+                continue;
+            }
+        }
+        if matches!(c, '{' | '}' | '[' | ']') {
+            out.push(c);
+        }
+        out.push(c);
+        i += 1;
+    }
+    out
+}
+
+/// Split a macro into its name and arguments.
+fn split_macro(inner: &str) -> (&str, &str) {
+    let inner = inner.trim();
+    if let Some(args) = inner.strip_prefix('=') {
+        return ("print", args.trim());
+    }
+    match inner.split_once(char::is_whitespace) {
+        Some((name, args)) => (name, args.trim()),
+        None => (inner, ""),
+    }
+}
+
+/// Parse the inside of a `[[...]]` link.
+fn link(inner: &str, line: usize) -> Result<Opt, ImportError> {
+    let (link, setter) = match inner.split_once("][") {
+        Some((link, setter)) => (link, Some(setter)),
+        None => (inner, None),
+    };
+    let (text, target) = if let Some((text, target)) = link.split_once('|') {
+        (text, target)
+    } else if let Some((text, target)) = link.rsplit_once("->") {
+        (text, target)
+    } else if let Some((target, text)) = link.split_once("<-") {
+        (text, target)
+    } else {
+        (link, link)
+    };
+    let effects = match setter {
+        Some(setter) => setter.split(';').map(|s| assignment(s, line)).collect::<Result<_, _>>()?,
+        None => Vec::new(),
+    };
+    Ok(Opt {
+        text: markup(text.trim()),
+        conditions: Vec::new(),
+        effects,
+        target: Some((target.trim().to_string(), line)),
+        body: Vec::new(),
+    })
+}
+
+// --- Twine -----------------------------------------------------------------
+
+#[derive(Debug)]
+enum Token {
+    Text(String),
+    Break,
+    Macro { name: String, args: String },
+    Link(Opt),
+}
+
+struct TweePassage<'a> {
+    title: String,
+    tags: Vec<String>,
+    line: usize,
+    body: Vec<(usize, &'a str)>,
+}
+
+/// Split `Title [tags] {metadata}` into the title and tags.
+fn twee_header(header: &str) -> (String, Vec<String>) {
+    let mut header = header.trim();
+    if let Some(i) = header.rfind('{').filter(|_| header.ends_with('}')) {
+        header = header[..i].trim_end();
+    }
+    match header.rfind('[').filter(|_| header.ends_with(']')) {
+        Some(i) => (
+            header[..i].trim().to_string(),
+            header[i + 1..header.len() - 1].split_whitespace().map(str::to_string).collect(),
+        ),
+        None => (header.to_string(), Vec::new()),
+    }
+}
+
+fn parse_twee(source: &str) -> Result<Story, ImportError> {
+    let mut passages: Vec<TweePassage> = Vec::new();
+    for (index, line) in source.lines().enumerate() {
+        if let Some(header) = line.strip_prefix("::") {
+            let (title, tags) = twee_header(header);
+            passages.push(TweePassage { title, tags, line: index + 1, body: Vec::new() });
+        } else if let Some(passage) = passages.last_mut() {
+            passage.body.push((index + 1, line));
+        }
+    }
+
+    let mut story = Story::default();
+    for passage in passages {
+        let contents = || passage.body.iter().map(|(_, l)| *l).collect::<Vec<_>>().join("\n");
+        match passage.title.as_str() {
+            "StoryTitle" => story.name = Some(contents().trim().to_string()),
+            "StoryData" => {
+                let data: serde_json::Value = serde_json::from_str(&contents())
+                    .map_err(|e| error(passage.line, format!("invalid StoryData: {}", e)))?;
+                story.start = data
+                    .get("start")
+                    .and_then(|s| s.as_str())
+                    .map(|s| (s.to_string(), passage.line));
+            }
+            _ if passage.tags.iter().any(|t| t == "script" || t == "stylesheet") => {}
+            title => {
+                let mut parser = TweeParser {
+                    tokens: twee_tokens(&passage.body)?,
+                    pos: 0,
+                    links: Vec::new(),
+                    warnings: &mut story.warnings,
+                };
+                let mut items = match parser.block(Some(&[]))? {
+                    (items, None) => items,
+                    (_, Some((_, line))) => return Err(error(line, "no `<<if>>` to continue or close")),
+                };
+                if !parser.links.is_empty() {
+                    items.push(Item::Options(parser.links));
+                }
+
+                if title == "StoryInit" {
+                    for item in items {
+                        match item {
+                            Item::Set(StoryEffect { effect_type: EffectType::SetVar, mut params }) => {
+                                if let (Some(serde_json::Value::String(name)), Some(value)) =
+                                    (params.remove("variable"), params.remove("value"))
+                                {
+                                    story.variables.push((name, value));
+                                }
+                            }
+                            _ => story.warnings.push(ImportWarning {
+                                line: passage.line,
+                                message: "only `<<set $variable to value>>` is imported from StoryInit".to_string(),
+                            }),
+                        }
+                    }
+                } else {
+                    story.sections.push(Section { title: passage.title, line: passage.line, items });
+                }
+            }
+        }
+    }
+    Ok(story)
+}
+
+fn twee_tokens(body: &[(usize, &str)]) -> Result<Vec<(usize, Token)>, ImportError> {
+    let mut tokens = Vec::new();
+    for &(line, text) in body {
+        let mut rest = text;
+        while !rest.is_empty() {
+            let Some(start) = [rest.find("<<"), rest.find("[[")].into_iter().flatten().min() else {
+                tokens.push((line, Token::Text(rest.to_string())));
+                break;
+            };
+            if start > 0 {
+                tokens.push((line, Token::Text(rest[..start].to_string())));
+            }
+            let inner = &rest[start + 2..];
+            if rest[start..].starts_with("<<") {
+                let end = inner.find(">>").ok_or_else(|| error(line, "unclosed `<<`"))?;
+                let (name, args) = split_macro(&inner[..end]);
+                tokens.push((line, Token::Macro { name: name.to_string(), args: args.to_string() }));
+                rest = &inner[end + 2..];
+            } else {
+                let end = inner.find("]]").ok_or_else(|| error(line, "unclosed `[[`"))?;
+                tokens.push((line, Token::Link(link(&inner[..end], line)?)));
+                rest = &inner[end + 2..];
+            }
+        }
+        tokens.push((line, Token::Break));
+    }
+    Ok(tokens)
+}
+
+struct TweeParser<'a> {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    /// Links in the passage, with the conditions they were found under
+    links: Vec<Opt>,
+    warnings: &'a mut Vec<ImportWarning>,
+}
+
+impl TweeParser<'_> {
+    fn block(&mut self, path: Option<&[StoryCondition]>) -> Result<Block, ImportError> {
+        let mut items = Vec::new();
+        let mut text = String::new();
+        // Whether `text` has more than link labels.
+        let mut prose = false;
+        let flush = |items: &mut Vec<Item>, text: &mut String, prose: &mut bool| {
+            if *prose && !text.trim().is_empty() {
+                items.push(Item::Line { speaker: NARRATOR.to_string(), text: text.trim().to_string() });
+            }
+            text.clear();
+            *prose = false;
+        };
+
+        while self.pos < self.tokens.len() {
+            let line = self.tokens[self.pos].0;
+            self.pos += 1;
+            match &self.tokens[self.pos - 1].1 {
+                Token::Text(t) => {
+                    prose |= !t.trim().is_empty();
+                    text.push_str(&markup(t));
+                }
+                Token::Break => flush(&mut items, &mut text, &mut prose),
+                Token::Link(option) => {
+                    let conditions = path.ok_or_else(|| {
+                        error(line, "links after an `<<else>>` of a compound condition can't be imported")
+                    })?;
+                    text.push_str(&option.text);
+                    self.links.push(Opt { conditions: conditions.to_vec(), ..option.clone() });
+                }
+                Token::Macro { name, args } if name == "print" => {
+                    prose = true;
+                    text.push_str(&format!("{{{}}}", variable(args, line)?));
+                }
+                Token::Macro { name, args } => {
+                    let (name, args) = (name.clone(), args.clone());
+                    flush(&mut items, &mut text, &mut prose);
+                    match name.as_str() {
+                        "set" => {
+                            for a in args.split(';') {
+                                items.push(Item::Set(assignment(a, line)?));
+                            }
+                        }
+                        "if" => {
+                            let first = condition(&args, line)?;
+                            items.push(if_chain(first, line, path, |p| self.block(p))?);
+                        }
+                        "elseif" => return Ok((items, Some((Terminator::ElseIf(condition(&args, line)?), line)))),
+                        "else" => {
+                            let end = match args.strip_prefix("if ") {
+                                Some(c) => Terminator::ElseIf(condition(c, line)?),
+                                None => Terminator::Else,
+                            };
+                            return Ok((items, Some((end, line))));
+                        }
+                        "/if" | "endif" => return Ok((items, Some((Terminator::EndIf, line)))),
+                        "goto" => items.push(Item::Jump {
+                            target: args.trim_matches(|c| matches!(c, '"' | '\'' | '[' | ']')).to_string(),
+                            line,
+                        }),
+                        name if name.starts_with('/') => {}
+                        name => self.warnings.push(ImportWarning {
+                            line,
+                            message: format!("unsupported macro `<<{}>>` was skipped", name),
+                        }),
+                    }
+                }
+            }
+        }
+        flush(&mut items, &mut text, &mut prose);
+        Ok((items, None))
+    }
+}
+
+// --- Yarn ------------------------------------------------------------------
+
+/// Strip trailing `#tags` (line ids, metadata) from a Yarn line.
+fn strip_tags(mut text: &str) -> &str {
+    while let Some((rest, tag)) = text.rsplit_once(" #") {
+        if tag.contains(char::is_whitespace) {
+            break;
+        }
+        text = rest.trim_end();
+    }
+    text
+}
+
+fn parse_yarn(source: &str) -> Result<Story, ImportError> {
+    let mut story = Story::default();
+    let mut title: Option<(String, usize)> = None;
+    let mut body: Vec<(usize, usize, &str)> = Vec::new();
+    let mut in_body = false;
+
+    for (index, raw) in source.lines().enumerate() {
+        let line = index + 1;
+        let trimmed = raw.trim();
+        if !in_body {
+            if trimmed == "---" {
+                if title.is_none() {
+                    return Err(error(line, "node has no `title:`"));
+                }
+                in_body = true;
+            } else if let Some(t) = trimmed.strip_prefix("title:") {
+                title = Some((t.trim().to_string(), line));
+            }
+        } else if trimmed == "===" {
+            let (title, title_line) = title.take().unwrap_or_default();
+            let mut parser = YarnParser {
+                lines: std::mem::take(&mut body),
+                pos: 0,
+                links: Vec::new(),
+                story: &mut story,
+            };
+            let mut items = match parser.block(None, Some(&[]))? {
+                (items, None) => items,
+                (_, Some((_, line))) => return Err(error(line, "no `<<if>>` to continue or close")),
+            };
+            if !parser.links.is_empty() {
+                items.push(Item::Options(parser.links));
+            }
+            story.sections.push(Section { title, line: title_line, items });
+            in_body = false;
+        } else if !trimmed.is_empty() && !trimmed.starts_with("//") {
+            let indent = raw.chars().take_while(|c| c.is_whitespace()).count();
+            body.push((line, indent, trimmed));
+        }
+    }
+
+    if let (true, Some((title, line))) = (in_body, title) {
+        return Err(error(line, format!("node `{}` is missing its closing `===`", title)));
+    }
+    Ok(story)
+}
+
+struct YarnParser<'a, 's> {
+    /// Line number, indent and trimmed text of each line in the node
+    lines: Vec<(usize, usize, &'a str)>,
+    pos: usize,
+    /// `[[...]]` links in the node, with the conditions they were found under
+    links: Vec<Opt>,
+    story: &'s mut Story,
+}
+
+impl YarnParser<'_, '_> {
+    /// Parse lines indented deeper than `parent` (an option line's indent).
+    fn block(&mut self, parent: Option<usize>, path: Option<&[StoryCondition]>) -> Result<Block, ImportError> {
+        let mut items = Vec::new();
+        while let Some(&(line, indent, text)) = self.lines.get(self.pos) {
+            if parent.is_some_and(|p| indent <= p) {
+                break;
+            }
+
+            if text.starts_with("->") {
+                let mut options = Vec::new();
+                while let Some(&(line, i, text)) = self.lines.get(self.pos) {
+                    let Some(option) = text.strip_prefix("->").filter(|_| i == indent) else { break };
+                    self.pos += 1;
+                    let option = strip_tags(option.trim());
+                    let (label, conditions) = match option.split_once("<<if ") {
+                        Some((label, c)) => {
+                            let c = c.strip_suffix(">>").ok_or_else(|| error(line, "unclosed `<<if`"))?;
+                            (label.trim(), condition(c, line)?)
+                        }
+                        None => (option, Vec::new()),
+                    };
+                    let body = match self.block(Some(indent), path)? {
+                        (body, None) => body,
+                        (_, Some((_, l))) => return Err(error(l, "`<<if>>` branches can't end inside an option")),
+                    };
+                    options.push(Opt { text: markup(label), conditions, effects: Vec::new(), target: None, body });
+                }
+                items.push(Item::Options(options));
+                continue;
+            }
+
+            self.pos += 1;
+            if let Some(command) = text.strip_prefix("<<").and_then(|t| strip_tags(t).strip_suffix(">>")) {
+                let (name, args) = split_macro(command);
+                match name {
+                    "set" => items.push(Item::Set(assignment(args, line)?)),
+                    "declare" => {
+                        let args = args.split(" as ").next().unwrap_or_default();
+                        let (name, value) = args
+                            .split_once('=')
+                            .ok_or_else(|| error(line, "expected `<<declare $variable = value>>`"))?;
+                        self.story.variables.push((variable(name, line)?, literal(value, line)?));
+                    }
+                    "if" => {
+                        let first = condition(args, line)?;
+                        items.push(if_chain(first, line, path, |p| self.block(parent, p))?);
+                    }
+                    "elseif" => return Ok((items, Some((Terminator::ElseIf(condition(args, line)?), line)))),
+                    "else" => return Ok((items, Some((Terminator::Else, line)))),
+                    "endif" => return Ok((items, Some((Terminator::EndIf, line)))),
+                    "jump" => items.push(Item::Jump { target: args.to_string(), line }),
+                    "stop" => items.push(Item::Stop),
+                    name => self.story.warnings.push(ImportWarning {
+                        line,
+                        message: format!("unsupported command `<<{}>>` was skipped", name),
+                    }),
+                }
+            } else if let Some(links) = text.strip_prefix("[[") {
+                let conditions =
+                    path.ok_or_else(|| error(line, "links after an `<<else>>` of a compound condition can't be imported"))?;
+                for inner in links.split("[[") {
+                    let inner = inner.trim().strip_suffix("]]").ok_or_else(|| error(line, "unclosed `[[`"))?;
+                    self.links.push(Opt { conditions: conditions.to_vec(), ..link(inner, line)? });
+                }
+            } else {
+                let text = strip_tags(text);
+                let (speaker, text) = match text.split_once(": ") {
+                    Some((speaker, text)) if !speaker.contains(['{', '[', '<']) => (speaker, text),
+                    _ => (NARRATOR, text),
+                };
+                items.push(Item::Line { speaker: speaker.to_string(), text: markup(text) });
+            }
+        }
+        Ok((items, None))
+    }
+}
+
+// --- Building --------------------------------------------------------------
+
+/// A node field waiting for the id of whatever comes next.
+#[derive(Debug, Clone, Copy)]
+enum Slot {
+    Next(usize),
+    True(usize),
+    False(usize),
+    Option(usize, usize),
+}
+
+struct Builder<'a> {
+    graph: StoryGraphData,
+    titles: &'a HashMap<&'a str, usize>,
+    /// Title and line of the section each node came from
+    origins: Vec<(String, usize)>,
+    /// Sections that start with a jump, and where they jump to
+    aliases: HashMap<String, String>,
+    section: String,
+    line: usize,
+    count: usize,
+    pending: Vec<Slot>,
+}
+
+impl Builder<'_> {
+    fn emit(&mut self, data: StoryNodeVariant) -> Result<usize, ImportError> {
+        let id = match self.count {
+            0 => self.section.clone(),
+            n => format!("{}.{}", self.section, n),
+        };
+        if self.graph.find_node(&id).is_some() {
+            return Err(error(self.line, format!("duplicate node id `{}`", id)));
+        }
+        self.count += 1;
+        self.fill(&id);
+        self.graph.add_node(node(id, data));
+        self.origins.push((self.section.clone(), self.line));
+        Ok(self.graph.nodes.len() - 1)
+    }
+
+    /// Point every pending slot at `target`.
+    fn fill(&mut self, target: &str) {
+        for slot in std::mem::take(&mut self.pending) {
+            let target = target.to_string();
+            let index = match slot {
+                Slot::Next(i) | Slot::True(i) | Slot::False(i) | Slot::Option(i, _) => i,
+            };
+            match (slot, &mut self.graph.nodes[index].data) {
+                (Slot::Next(_), StoryNodeVariant::Dialogue(d)) => d.next_node_id = Some(target),
+                (Slot::Next(_), StoryNodeVariant::Effect(e)) => e.next_node_id = Some(target),
+                (Slot::True(_), StoryNodeVariant::Conditional(c)) => c.true_target_node_id = target,
+                (Slot::False(_), StoryNodeVariant::Conditional(c)) => c.false_target_node_id = target,
+                (Slot::Option(_, o), StoryNodeVariant::Choice(c)) => c.options[o].target_node_id = target,
+                _ => unreachable!("slot doesn't match its node"),
+            }
+        }
+    }
+
+    fn jump(&mut self, target: &str, line: usize) -> Result<(), ImportError> {
+        if !self.titles.contains_key(target) {
+            return Err(error(line, format!("unknown passage `{}`", target)));
+        }
+        if self.count == 0 {
+            self.aliases.insert(self.section.clone(), target.to_string());
+        }
+        self.fill(target);
+        Ok(())
+    }
+
+    fn section(&mut self, section: &Section) -> Result<(), ImportError> {
+        self.section = section.title.clone();
+        self.line = section.line;
+        self.count = 0;
+        self.pending.clear();
+        self.lower(&section.items)?;
+        if (self.count == 0 && !self.aliases.contains_key(&section.title)) || !self.pending.is_empty() {
+            self.emit(StoryNodeVariant::End(EndNodeData::default()))?;
+        }
+        Ok(())
+    }
+
+    fn lower(&mut self, items: &[Item]) -> Result<(), ImportError> {
+        for item in items {
+            match item {
+                Item::Line { speaker, text } => {
+                    let i = self.emit(StoryNodeVariant::Dialogue(DialogueNodeData {
+                        speaker_id: speaker.clone(),
+                        text: localized(text),
+                        ..Default::default()
+                    }))?;
+                    self.pending = vec![Slot::Next(i)];
+                }
+                Item::Set(effect) => {
+                    // Consecutive assignments share one Effect node.
+                    let last = self.graph.nodes.len().wrapping_sub(1);
+                    let merge = matches!(self.pending.as_slice(), [Slot::Next(i)] if *i == last)
+                        && matches!(self.graph.nodes[last].data, StoryNodeVariant::Effect(_));
+                    if merge {
+                        if let StoryNodeVariant::Effect(e) = &mut self.graph.nodes[last].data {
+                            e.effects.push(effect.clone());
+                        }
+                    } else {
+                        let i = self.emit(StoryNodeVariant::Effect(EffectNodeData {
+                            effects: vec![effect.clone()],
+                            next_node_id: None,
+                        }))?;
+                        self.pending = vec![Slot::Next(i)];
+                    }
+                }
+                Item::If { branches, otherwise } => {
+                    let mut tails = Vec::new();
+                    for (conditions, body) in branches {
+                        let mut skipped = Vec::new();
+                        for condition in conditions {
+                            let i = self.emit(StoryNodeVariant::Conditional(ConditionalNodeData {
+                                condition: condition.clone(),
+                                true_target_node_id: String::new(),
+                                false_target_node_id: String::new(),
+                            }))?;
+                            skipped.push(Slot::False(i));
+                            self.pending = vec![Slot::True(i)];
+                        }
+                        self.lower(body)?;
+                        tails.append(&mut self.pending);
+                        self.pending = skipped;
+                    }
+                    self.lower(otherwise)?;
+                    self.pending.append(&mut tails);
+                }
+                Item::Options(options) => {
+                    let i = self.emit(StoryNodeVariant::Choice(ChoiceNodeData {
+                        prompt: LocalizedString::new(),
+                        options: options
+                            .iter()
+                            .enumerate()
+                            .map(|(n, option)| ChoiceOption {
+                                id: (n + 1).to_string(),
+                                text: localized(&option.text),
+                                target_node_id: String::new(),
+                                flag_required: None,
+                                conditions: option.conditions.clone(),
+                                effects: option.effects.clone(),
+                                show_when_unavailable: false,
+                            })
+                            .collect(),
+                        ..Default::default()
+                    }))?;
+                    let mut tails = Vec::new();
+                    for (n, option) in options.iter().enumerate() {
+                        self.pending = vec![Slot::Option(i, n)];
+                        match &option.target {
+                            Some((target, line)) => self.jump(target, *line)?,
+                            None => {
+                                self.lower(&option.body)?;
+                                tails.append(&mut self.pending);
+                            }
+                        }
+                    }
+                    self.pending = tails;
+                }
+                Item::Jump { target, line } => self.jump(target, *line)?,
+                Item::Stop => {
+                    self.emit(StoryNodeVariant::End(EndNodeData::default()))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Node ids a node points at, for rewriting jumps to aliased sections.
+fn targets_mut(data: &mut StoryNodeVariant) -> Vec<&mut String> {
+    match data {
+        StoryNodeVariant::Dialogue(d) => d.next_node_id.iter_mut().collect(),
+        StoryNodeVariant::Effect(e) => e.next_node_id.iter_mut().collect(),
+        StoryNodeVariant::Conditional(c) => vec![&mut c.true_target_node_id, &mut c.false_target_node_id],
+        StoryNodeVariant::Choice(c) => c.options.iter_mut().map(|o| &mut o.target_node_id).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn build(id: &str, story: Story) -> Result<ImportedGraph, ImportError> {
+    let Story { name, start, variables, sections, mut warnings } = story;
+    let first = sections.first().ok_or(ImportError::Empty)?;
+
+    let mut titles: HashMap<&str, usize> = HashMap::new();
+    for section in &sections {
+        if titles.insert(&section.title, section.line).is_some() {
+            return Err(error(section.line, format!("duplicate passage `{}`", section.title)));
+        }
+    }
+    let start = match start {
+        Some((start, _)) if titles.contains_key(start.as_str()) => start,
+        Some((start, line)) => return Err(error(line, format!("start passage `{}` doesn't exist", start))),
+        None if titles.contains_key("Start") => "Start".to_string(),
+        None => first.title.clone(),
+    };
+
+    let mut builder = Builder {
+        graph: StoryGraphData::new(id, name.unwrap_or_else(|| id.to_string())),
+        titles: &titles,
+        origins: Vec::new(),
+        aliases: HashMap::new(),
+        section: String::new(),
+        line: 0,
+        count: 0,
+        pending: Vec::new(),
+    };
+    builder.graph.variables.extend(variables);
+    for section in &sections {
+        builder.section(section)?;
+    }
+    let Builder { mut graph, origins, aliases, .. } = builder;
+
+    // Sections that start with a jump have no node of their own.
+    let mut resolved: HashMap<&str, &str> = HashMap::new();
+    for title in aliases.keys() {
+        let mut target = title.as_str();
+        for _ in 0..=aliases.len() {
+            match aliases.get(target) {
+                Some(next) => target = next,
+                None => break,
+            }
+        }
+        if aliases.contains_key(target) {
+            return Err(error(titles[title.as_str()], format!("`{}` only jumps in a loop", title)));
+        }
+        resolved.insert(title, target);
+    }
+    for node in &mut graph.nodes {
+        for target in targets_mut(&mut node.data) {
+            if let Some(resolved) = resolved.get(target.as_str()) {
+                *target = resolved.to_string();
+            }
+        }
+    }
+    graph.root_node_id = resolved.get(start.as_str()).map_or(start.clone(), |s| s.to_string());
+
+    // Drop what the start can't reach so the graph validates.
+    let reachable: HashSet<String> = reachable_nodes(&graph, &[graph.root_node_id.as_str()])
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+    let mut reported = HashSet::new();
+    let nodes = std::mem::take(&mut graph.nodes);
+    for (node, (title, line)) in nodes.into_iter().zip(origins) {
+        if reachable.contains(&node.id) {
+            graph.nodes.push(node);
+        } else if reported.insert(title.clone()) {
+            let message = if node.id == title {
+                format!("`{}` can't be reached from the start and was skipped", title)
+            } else {
+                format!("unreachable content in `{}` was skipped", title)
+            };
+            warnings.push(ImportWarning { line, message });
+        }
+    }
+
+    Ok(ImportedGraph { graph, warnings })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TWEE: &str = r#":: StoryTitle
+The Shop
+
+:: StoryData
+{"ifid": "D674C58C-DEFA-4F70-B7A2-27742230C0FC", "format": "SugarCube", "start": "Shop"}
+
+:: StoryInit
+<<set $gold to 10>>
+
+:: Shop [town] {"position":"100,100"}
+Welcome! You have $gold gold.
+<<if $gold gte 5>>
+[[Buy a herb|Thanks][$gold -= 5]]
+<<else>>
+You can't afford anything.
+<</if>>
+<<audio "bell" play>>
+[[Leave->Goodbye]]
+
+:: Thanks
+Come again.
+<<goto "Goodbye">>
+
+:: Goodbye
+<<set $visited to true>>
+Bye!
+
+:: Notes
+Never linked.
+"#;
+
+    const YARN: &str = r#"title: Start
+tags: intro
+---
+<<declare $met = false>>
+Guard: Halt! Who goes there? #line:a1
+-> A friend.
+    <<set $met to true>>
+    Guard: Pass, friend.
+-> Nobody. <<if not $met>>
+    Guard: Off with you.
+    <<jump Leave>>
+<<wait 1>>
+<<if $met>>
+    Guard: Welcome back.
+<<else>>
+    Guard: Move along.
+<<endif>>
+===
+title: Leave
+---
+<<stop>>
+===
+"#;
+
+    #[test]
+    fn test_import_twee() {
+        let imported = import_twee("shop", TWEE).unwrap();
+        let graph = &imported.graph;
+        assert_eq!(graph.name, "The Shop");
+        assert_eq!(graph.root_node_id, "Shop");
+        assert_eq!(graph.variables["gold"], serde_json::json!(10));
+        assert!(graph.validate().is_empty(), "{:?}", graph.validate());
+
+        let StoryNodeVariant::Dialogue(welcome) = &graph.find_node("Shop").unwrap().data else { panic!() };
+        assert_eq!(welcome.text["en"], "Welcome! You have {gold} gold.");
+        let choice = graph
+            .nodes
+            .iter()
+            .find_map(|n| match &n.data {
+                StoryNodeVariant::Choice(c) => Some(c),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(choice.options.len(), 2);
+        assert_eq!(choice.options[0].target_node_id, "Thanks");
+        assert_eq!(choice.options[0].conditions[0].operator, ConditionOperator::GreaterThanOrEquals);
+        assert_eq!(choice.options[0].effects[0].params["value"], serde_json::json!(-5));
+        assert!(choice.options[1].conditions.is_empty());
+        assert!(graph.nodes.iter().any(|n| matches!(n.data, StoryNodeVariant::Conditional(_))));
+
+        let StoryNodeVariant::Dialogue(thanks) = &graph.find_node("Thanks").unwrap().data else { panic!() };
+        assert_eq!(thanks.next_node_id.as_deref(), Some("Goodbye"));
+        assert!(matches!(graph.find_node("Goodbye").unwrap().data, StoryNodeVariant::Effect(_)));
+        assert!(graph.find_node("Notes").is_none());
+
+        let messages: Vec<_> = imported.warnings.iter().map(|w| w.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "line 17: unsupported macro `<<audio>>` was skipped",
+                "line 28: `Notes` can't be reached from the start and was skipped",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_import_yarn() {
+        let imported = import_yarn("guard", YARN).unwrap();
+        let graph = &imported.graph;
+        assert_eq!(graph.root_node_id, "Start");
+        assert_eq!(graph.variables["met"], serde_json::json!(false));
+        assert!(graph.validate().is_empty(), "{:?}", graph.validate());
+
+        let StoryNodeVariant::Dialogue(halt) = &graph.find_node("Start").unwrap().data else { panic!() };
+        assert_eq!(halt.speaker_id, "Guard");
+        assert_eq!(halt.text["en"], "Halt! Who goes there?");
+
+        let StoryNodeVariant::Choice(choice) = &graph.find_node("Start.1").unwrap().data else { panic!() };
+        assert_eq!(choice.options[1].conditions[0].value, serde_json::json!(false));
+        // "A friend." runs its body then rejoins at the `<<if>>`.
+        let StoryNodeVariant::Effect(set) = &graph.find_node(&choice.options[0].target_node_id).unwrap().data
+        else {
+            panic!()
+        };
+        assert_eq!(set.next_node_id.as_deref(), Some("Start.3"));
+        let StoryNodeVariant::Dialogue(pass) = &graph.find_node("Start.3").unwrap().data else { panic!() };
+        assert_eq!(pass.next_node_id.as_deref(), Some("Start.5"));
+        let StoryNodeVariant::Dialogue(off) = &graph.find_node("Start.4").unwrap().data else { panic!() };
+        assert_eq!(off.next_node_id.as_deref(), Some("Leave"));
+        assert!(matches!(graph.find_node("Start.5").unwrap().data, StoryNodeVariant::Conditional(_)));
+        assert!(matches!(graph.find_node("Leave").unwrap().data, StoryNodeVariant::End(_)));
+
+        assert_eq!(imported.warnings.len(), 1);
+        assert_eq!(imported.warnings[0].to_string(), "line 12: unsupported command `<<wait>>` was skipped");
+    }
+
+    #[test]
+    fn test_import_errors() {
+        for (source, line) in [
+            (":: Start\n[[Go->Nowhere]]", 2),
+            (":: Start\n<<if $a or $b>>x<</if>>", 2),
+            (":: Start\n<<if $a>>\nhi", 2),
+            (":: Start\n<<set $a to $b + 1>>", 2),
+            (":: Start\nhi\n:: Start\nagain", 3),
+        ] {
+            match import_twee("test", source) {
+                Err(ImportError::Syntax { line: l, .. }) => assert_eq!(l, line, "{}", source),
+                other => panic!("{}: expected a syntax error, got {:?}", source, other),
+            }
+        }
+        assert!(matches!(
+            import_yarn("test", "title: A\n---\n<<jump B>>\n==="),
+            Err(ImportError::Syntax { line: 3, .. })
+        ));
+        assert_eq!(import_twee("test", "no passages").unwrap_err(), ImportError::Empty);
+    }
+}
@@ -0,0 +1,946 @@
+//! Plain-text story scripts.
+//!
+//! A compact format for writing story graphs by hand:
+//!
+//! ```text
+//! VAR gold = 10
+//!
+//! === shop ===
+//! Shopkeeper: Welcome! You have {gold} gold.
+//! > What will it be?
+//! * Buy a herb <<if gold >= 5>> <<set gold -= 5>> -> thanks
+//! * Leave -> END
+//!
+//! === thanks ===
+//! Shopkeeper: Come again. #farewell
+//! -> shop
+//! ```
+//!
+//! - `=== name ===` starts a knot; the first knot is the graph's root.
+//! - `Speaker: text` is a line of dialogue (markup allowed).
+//! - `> prompt` and the `* option` lines after it form a choice. Options
+//!   may carry `<<if cond>>` conditions, a `<<flag name>>` story flag they
+//!   require and `<<set ...>>` effects, and must end in `-> target`.
+//! - `if cond -> a else -> b` branches; without `else` the false branch
+//!   continues with the next statement.
+//! - `set var = value`, `set var += n` and `set var -= n` change variables.
+//! - `call graph` or `call graph:node` runs another graph; `RETURN` and
+//!   `END` finish this one. `-> END` jumps to a shared end node.
+//! - `-> knot` jumps; otherwise statements run in order.
+//! - `VAR name = value` declares an initial variable; `//` starts a comment.
+//!
+//! Conditions are `var`, `!var` or `var op value` with `==`, `!=`, `<`,
+//! `<=`, `>`, `>=` or `contains`. Values are JSON literals, or bare words
+//! for strings.
+//!
+//! Node ids are stable: a knot's first node is named after the knot and
+//! later ones `knot.1`, `knot.2`, ... A trailing `#id` overrides the id (on
+//! an option line it names the option); write `\#` for a literal `#` in
+//! text. [`to_script`] writes a graph back out in this format, omitting ids
+//! that match the defaults.
+
+use std::collections::{HashMap, HashSet};
+use thiserror::Error;
+
+use super::components::Vec3Data;
+use super::story::{
+    CallNodeData, ChoiceNodeData, ChoiceOption, ConditionOperator, ConditionalNodeData,
+    DialogueNodeData, EffectNodeData, EffectType, EndNodeData, LocalizedString,
+    ReturnNodeData, StoryCondition, StoryEffect, StoryGraphData, StoryNodeData, StoryNodeVariant,
+};
+use crate::story_graph::localization::DEFAULT_LANGUAGE;
+
+/// Id of the shared node `-> END` jumps to.
+pub const END_NODE_ID: &str = "END";
+
+/// Errors from compiling or writing story scripts.
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ScriptError {
+    /// 1-based line and column in the script
+    #[error("line {line}, column {column}: {message}")]
+    Syntax { line: usize, column: usize, message: String },
+
+    #[error("node '{node_id}' can't be written as script: {reason}")]
+    Unsupported { node_id: String, reason: String },
+}
+
+/// Compile a script into a story graph with the given id.
+pub fn compile(id: &str, source: &str) -> Result<StoryGraphData, ScriptError> {
+    let parsed = parse(source)?;
+    build(id, parsed)
+}
+
+/// Write a graph back out as script.
+///
+/// Dialogue text is written in the default language; other translations
+/// are dropped, as are node positions and the graph's name, description
+/// and type. Fails for node types and fields the format can't express,
+/// such as voice lines or timed choices.
+pub fn to_script(graph: &StoryGraphData) -> Result<String, ScriptError> {
+    Printer::new(graph)?.print()
+}
+
+// --- Parsing ---------------------------------------------------------------
+
+/// A position in the source, for error messages.
+#[derive(Debug, Clone, Copy)]
+struct Pos {
+    line: usize,
+    column: usize,
+}
+
+impl Pos {
+    fn error(self, message: impl Into<String>) -> ScriptError {
+        ScriptError::Syntax { line: self.line, column: self.column, message: message.into() }
+    }
+
+    fn offset(self, chars: usize) -> Pos {
+        Pos { line: self.line, column: self.column + chars }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Target {
+    name: String,
+    pos: Pos,
+}
+
+#[derive(Debug)]
+struct ParsedOption {
+    id: Option<String>,
+    text: String,
+    flag_required: Option<String>,
+    conditions: Vec<StoryCondition>,
+    effects: Vec<StoryEffect>,
+    target: Target,
+}
+
+#[derive(Debug)]
+enum Kind {
+    Dialogue { speaker: String, text: String },
+    Choice { prompt: String, options: Vec<ParsedOption> },
+    If { condition: StoryCondition, then: Target, otherwise: Option<Target> },
+    Set(StoryEffect),
+    Call { graph_id: String, entry_node: Option<String> },
+    Return,
+    End,
+    Jump(Target),
+}
+
+#[derive(Debug)]
+struct Statement {
+    pos: Pos,
+    id: Option<String>,
+    kind: Kind,
+}
+
+#[derive(Debug)]
+struct Knot {
+    name: String,
+    pos: Pos,
+    statements: Vec<Statement>,
+}
+
+#[derive(Debug, Default)]
+struct Parsed {
+    variables: Vec<(String, serde_json::Value)>,
+    knots: Vec<Knot>,
+}
+
+fn parse(source: &str) -> Result<Parsed, ScriptError> {
+    let mut parsed = Parsed::default();
+
+    for (index, raw) in source.lines().enumerate() {
+        let indent = raw.chars().take_while(|c| c.is_whitespace()).count();
+        let pos = Pos { line: index + 1, column: indent + 1 };
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with("//") {
+            continue;
+        }
+
+        if let Some(rest) = line.strip_prefix("===") {
+            let name = rest.trim().trim_end_matches('=').trim();
+            check_name(name, pos.offset(3), "knot name")?;
+            parsed.knots.push(Knot { name: name.to_string(), pos, statements: Vec::new() });
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix("VAR ") {
+            let (name, value) = rest
+                .split_once('=')
+                .ok_or_else(|| pos.error("expected `VAR name = value`"))?;
+            let name = name.trim();
+            check_name(name, pos.offset(4), "variable name")?;
+            parsed.variables.push((name.to_string(), parse_value(value.trim())));
+            continue;
+        }
+
+        if parsed.knots.is_empty() {
+            // Statements before the first header belong to an implicit knot.
+            parsed.knots.push(Knot { name: "start".to_string(), pos, statements: Vec::new() });
+        }
+        let statements = &mut parsed.knots.last_mut().unwrap().statements;
+
+        if let Some(rest) = line.strip_prefix('*') {
+            let option = parse_option(rest, pos.offset(1))?;
+            match statements.last_mut() {
+                Some(Statement { kind: Kind::Choice { options, .. }, .. }) => options.push(option),
+                _ => statements.push(Statement {
+                    pos,
+                    id: None,
+                    kind: Kind::Choice { prompt: String::new(), options: vec![option] },
+                }),
+            }
+            continue;
+        }
+
+        let (body, id) = split_id(line, pos)?;
+        let kind = if let Some(prompt) = body.strip_prefix('>') {
+            Kind::Choice { prompt: unescape(prompt.trim()), options: Vec::new() }
+        } else if let Some(target) = body.strip_prefix("->") {
+            Kind::Jump(parse_target(target, pos.offset(2))?)
+        } else if let Some(rest) = body.strip_prefix("if ") {
+            parse_if(rest, pos.offset(3))?
+        } else if let Some(rest) = body.strip_prefix("set ") {
+            Kind::Set(parse_set(rest, pos.offset(4))?)
+        } else if let Some(rest) = body.strip_prefix("call ") {
+            let rest = rest.trim();
+            let (graph_id, entry_node) = match rest.split_once(':') {
+                Some((graph, node)) => (graph.trim(), Some(node.trim().to_string())),
+                None => (rest, None),
+            };
+            check_name(graph_id, pos.offset(5), "graph id")?;
+            Kind::Call { graph_id: graph_id.to_string(), entry_node }
+        } else if body == "RETURN" {
+            Kind::Return
+        } else if body == "END" {
+            Kind::End
+        } else if let Some((speaker, text)) = body.split_once(':') {
+            if speaker.trim().is_empty() {
+                return Err(pos.error("missing speaker before ':'"));
+            }
+            Kind::Dialogue { speaker: unescape(speaker.trim()), text: unescape(text.trim()) }
+        } else {
+            return Err(pos.error(format!("expected `Speaker: text` or a statement, found `{}`", body)));
+        };
+
+        if let (Kind::Jump(_), Some(_)) = (&kind, &id) {
+            return Err(pos.error("jumps can't have an #id"));
+        }
+        statements.push(Statement { pos, id, kind });
+    }
+
+    Ok(parsed)
+}
+
+fn is_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+fn check_name(name: &str, pos: Pos, what: &str) -> Result<(), ScriptError> {
+    if is_name(name) {
+        Ok(())
+    } else {
+        Err(pos.error(format!("invalid {} `{}`", what, name)))
+    }
+}
+
+/// Split a trailing `#id` off a line.
+fn split_id(line: &str, pos: Pos) -> Result<(&str, Option<String>), ScriptError> {
+    match line.rsplit_once(" #") {
+        Some((body, id)) if !id.contains(' ') => {
+            let column = body.chars().count() + 2;
+            if !is_name(id) {
+                return Err(pos
+                    .offset(column)
+                    .error(format!("invalid id `{}`; write `\\#` for a literal `#`", id)));
+            }
+            Ok((body.trim_end(), Some(id.to_string())))
+        }
+        _ => Ok((line, None)),
+    }
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("\\#", "#")
+}
+
+/// Escape each `#` that could be read as an `#id`. `None` if the text
+/// already contains `\#`, which has no escape of its own.
+fn escape(text: &str) -> Option<String> {
+    if text.contains("\\#") {
+        return None;
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut after_space = true;
+    for c in text.chars() {
+        if c == '#' && after_space {
+            out.push('\\');
+        }
+        out.push(c);
+        after_space = c.is_whitespace();
+    }
+    Some(out)
+}
+
+fn parse_target(text: &str, pos: Pos) -> Result<Target, ScriptError> {
+    let name = text.trim();
+    let pos = pos.offset(text.len() - text.trim_start().len());
+    check_name(name, pos, "jump target")?;
+    Ok(Target { name: name.to_string(), pos })
+}
+
+fn parse_value(text: &str) -> serde_json::Value {
+    serde_json::from_str(text).unwrap_or_else(|_| serde_json::Value::String(text.to_string()))
+}
+
+const OPERATORS: [(&str, ConditionOperator); 7] = [
+    ("==", ConditionOperator::Equals),
+    ("!=", ConditionOperator::NotEquals),
+    ("<=", ConditionOperator::LessThanOrEquals),
+    (">=", ConditionOperator::GreaterThanOrEquals),
+    ("<", ConditionOperator::LessThan),
+    (">", ConditionOperator::GreaterThan),
+    (" contains ", ConditionOperator::Contains),
+];
+
+fn parse_condition(text: &str, pos: Pos) -> Result<StoryCondition, ScriptError> {
+    let text = text.trim();
+    for (symbol, operator) in OPERATORS {
+        if let Some((variable, value)) = text.split_once(symbol) {
+            let variable = variable.trim();
+            check_name(variable, pos, "variable name")?;
+            return Ok(StoryCondition {
+                variable: variable.to_string(),
+                operator,
+                value: parse_value(value.trim()),
+            });
+        }
+    }
+    let (variable, value) = match text.strip_prefix('!') {
+        Some(variable) => (variable.trim(), false),
+        None => (text, true),
+    };
+    check_name(variable, pos, "condition")?;
+    Ok(StoryCondition {
+        variable: variable.to_string(),
+        operator: ConditionOperator::Equals,
+        value: serde_json::Value::Bool(value),
+    })
+}
+
+fn parse_set(text: &str, pos: Pos) -> Result<StoryEffect, ScriptError> {
+    let (variable, effect_type, value) = if let Some((variable, value)) = text.split_once("+=") {
+        (variable, EffectType::AddVar, parse_value(value.trim()))
+    } else if let Some((variable, value)) = text.split_once("-=") {
+        let amount = parse_value(value.trim());
+        let negated = match (amount.as_i64(), amount.as_f64()) {
+            (Some(i), _) => serde_json::json!(-i),
+            (None, Some(f)) => serde_json::json!(-f),
+            _ => return Err(pos.error(format!("`-=` needs a number, found `{}`", value.trim()))),
+        };
+        (variable, EffectType::AddVar, negated)
+    } else if let Some((variable, value)) = text.split_once('=') {
+        (variable, EffectType::SetVar, parse_value(value.trim()))
+    } else {
+        return Err(pos.error("expected `set name = value`, `+= n` or `-= n`"));
+    };
+
+    let variable = variable.trim();
+    check_name(variable, pos, "variable name")?;
+    Ok(StoryEffect {
+        effect_type,
+        params: HashMap::from([
+            ("variable".to_string(), serde_json::Value::String(variable.to_string())),
+            ("value".to_string(), value),
+        ]),
+    })
+}
+
+fn parse_if(text: &str, pos: Pos) -> Result<Kind, ScriptError> {
+    let (condition, targets) = text
+        .split_once("->")
+        .ok_or_else(|| pos.error("expected `if condition -> target`"))?;
+    let targets_pos = pos.offset(condition.chars().count() + 2);
+    let (then, otherwise) = match targets.split_once(" else ") {
+        Some((then, otherwise)) => {
+            let else_pos = targets_pos.offset(then.chars().count() + 6);
+            let otherwise = otherwise
+                .trim_start()
+                .strip_prefix("->")
+                .ok_or_else(|| else_pos.error("expected `else -> target`"))?;
+            (then, Some(parse_target(otherwise, else_pos.offset(2))?))
+        }
+        None => (targets, None),
+    };
+    Ok(Kind::If {
+        condition: parse_condition(condition, pos)?,
+        then: parse_target(then, targets_pos)?,
+        otherwise,
+    })
+}
+
+fn parse_option(text: &str, pos: Pos) -> Result<ParsedOption, ScriptError> {
+    let pos = pos.offset(text.chars().count() - text.trim_start().chars().count());
+    let (body, id) = split_id(text.trim(), pos)?;
+    let (body, target) = body
+        .rsplit_once("->")
+        .ok_or_else(|| pos.error("option needs a `-> target`"))?;
+    let target = parse_target(target, pos.offset(body.chars().count() + 2))?;
+
+    let (text, mut rest) = match body.find("<<") {
+        Some(start) => (&body[..start], &body[start..]),
+        None => (body, ""),
+    };
+    let mut option = ParsedOption {
+        id,
+        text: unescape(text.trim()),
+        flag_required: None,
+        conditions: Vec::new(),
+        effects: Vec::new(),
+        target,
+    };
+    if option.text.is_empty() {
+        return Err(pos.error("option has no text"));
+    }
+
+    while !rest.trim().is_empty() {
+        let trimmed = rest.trim_start();
+        let directive_pos = pos.offset(body.chars().count() - trimmed.chars().count());
+        let inner = trimmed
+            .strip_prefix("<<")
+            .and_then(|r| r.split_once(">>"))
+            .ok_or_else(|| directive_pos.error("expected `<<if ...>>`, `<<flag ...>>` or `<<set ...>>`"))?;
+        let (directive, after) = inner;
+        if let Some(condition) = directive.trim().strip_prefix("if ") {
+            option.conditions.push(parse_condition(condition, directive_pos)?);
+        } else if let Some(flag) = directive.trim().strip_prefix("flag ") {
+            if option.flag_required.is_some() {
+                return Err(directive_pos.error("option already requires a flag"));
+            }
+            check_name(flag.trim(), directive_pos.offset(7), "flag name")?;
+            option.flag_required = Some(flag.trim().to_string());
+        } else if let Some(effect) = directive.trim().strip_prefix("set ") {
+            option.effects.push(parse_set(effect, directive_pos)?);
+        } else {
+            return Err(directive_pos.error(format!("unknown directive `<<{}>>`", directive.trim())));
+        }
+        rest = after;
+    }
+    Ok(option)
+}
+
+// --- Building --------------------------------------------------------------
+
+pub(crate) fn node(id: String, data: StoryNodeVariant) -> StoryNodeData {
+    StoryNodeData {
+        id,
+        position: Vec3Data::default(),
+        data,
+        required_entities: Vec::new(),
+        required_items: Vec::new(),
+    }
+}
+
+pub(crate) fn text(text: &str) -> LocalizedString {
+    if text.is_empty() {
+        LocalizedString::new()
+    } else {
+        LocalizedString::from([(DEFAULT_LANGUAGE.to_string(), text.to_string())])
+    }
+}
+
+fn build(id: &str, parsed: Parsed) -> Result<StoryGraphData, ScriptError> {
+    let mut graph = StoryGraphData::new(id, id);
+    graph.variables.extend(parsed.variables);
+
+    // Assign ids first so jumps can point forwards.
+    let mut ids: Vec<Vec<Option<String>>> = Vec::new();
+    let mut known: HashSet<String> = HashSet::new();
+    // Knot name -> id of its first node, for knots whose first node has an #id.
+    let mut aliases: HashMap<String, String> = HashMap::new();
+    for knot in &parsed.knots {
+        let mut knot_ids = Vec::new();
+        let mut count = 0;
+        for statement in &knot.statements {
+            if let Kind::Jump(_) = statement.kind {
+                knot_ids.push(None);
+                continue;
+            }
+            let id = statement.id.clone().unwrap_or_else(|| match count {
+                0 => knot.name.clone(),
+                n => format!("{}.{}", knot.name, n),
+            });
+            count += 1;
+            if id == END_NODE_ID || !known.insert(id.clone()) {
+                return Err(statement.pos.error(format!("duplicate node id `{}`", id)));
+            }
+            knot_ids.push(Some(id));
+        }
+        match knot_ids.iter().flatten().next() {
+            Some(head) => {
+                aliases.insert(knot.name.clone(), head.clone());
+            }
+            None => return Err(knot.pos.error(format!("knot `{}` has no statements", knot.name))),
+        }
+        ids.push(knot_ids);
+    }
+
+    let mut uses_end = false;
+    let mut resolve = |target: &Target| -> Result<String, ScriptError> {
+        if target.name == END_NODE_ID {
+            uses_end = true;
+        } else if !known.contains(&target.name) {
+            return aliases
+                .get(&target.name)
+                .cloned()
+                .ok_or_else(|| target.pos.error(format!("unknown jump target `{}`", target.name)));
+        }
+        Ok(target.name.clone())
+    };
+
+    for (knot, knot_ids) in parsed.knots.iter().zip(&ids) {
+        for (i, statement) in knot.statements.iter().enumerate() {
+            let Some(id) = &knot_ids[i] else {
+                // A jump must follow a statement that continues.
+                let Kind::Jump(target) = &statement.kind else { unreachable!() };
+                match i.checked_sub(1).map(|p| &knot.statements[p].kind) {
+                    None => return Err(target.pos.error("a knot can't start with a jump")),
+                    Some(Kind::Choice { .. } | Kind::Return | Kind::End | Kind::Jump(_)) => {
+                        return Err(target.pos.error("unreachable jump"));
+                    }
+                    Some(_) => continue,
+                }
+            };
+            // Where flow goes after this statement.
+            let next = match knot.statements.get(i + 1) {
+                Some(Statement { kind: Kind::Jump(target), .. }) => Some(resolve(target)?),
+                Some(_) => knot_ids[i + 1].clone(),
+                None => None,
+            };
+
+            let data = match &statement.kind {
+                Kind::Dialogue { speaker, text: line } => StoryNodeVariant::Dialogue(DialogueNodeData {
+                    speaker_id: speaker.clone(),
+                    text: text(line),
+                    next_node_id: next,
+                    ..Default::default()
+                }),
+                Kind::Choice { prompt, options } => {
+                    if options.is_empty() {
+                        return Err(statement.pos.error("prompt has no `*` options"));
+                    }
+                    StoryNodeVariant::Choice(ChoiceNodeData {
+                        prompt: text(prompt),
+                        options: options
+                            .iter()
+                            .enumerate()
+                            .map(|(n, option)| {
+                                Ok(ChoiceOption {
+                                    id: option.id.clone().unwrap_or_else(|| (n + 1).to_string()),
+                                    text: text(&option.text),
+                                    target_node_id: resolve(&option.target)?,
+                                    flag_required: option.flag_required.clone(),
+                                    conditions: option.conditions.clone(),
+                                    effects: option.effects.clone(),
+                                    show_when_unavailable: false,
+                                })
+                            })
+                            .collect::<Result<_, ScriptError>>()?,
+                        ..Default::default()
+                    })
+                }
+                Kind::If { condition, then, otherwise } => {
+                    let false_target = match otherwise {
+                        Some(target) => resolve(target)?,
+                        None => next.ok_or_else(|| {
+                            statement.pos.error("`if` without `else` needs a statement after it")
+                        })?,
+                    };
+                    StoryNodeVariant::Conditional(ConditionalNodeData {
+                        condition: condition.clone(),
+                        true_target_node_id: resolve(then)?,
+                        false_target_node_id: false_target,
+                    })
+                }
+                Kind::Set(effect) => StoryNodeVariant::Effect(EffectNodeData {
+                    effects: vec![effect.clone()],
+                    next_node_id: next,
+                }),
+                Kind::Call { graph_id, entry_node } => StoryNodeVariant::Call(CallNodeData {
+                    graph_id: graph_id.clone(),
+                    entry_node: entry_node.clone(),
+                    next_node_id: next,
+                }),
+                Kind::Return => StoryNodeVariant::Return(ReturnNodeData {}),
+                Kind::End => StoryNodeVariant::End(EndNodeData::default()),
+                Kind::Jump(_) => unreachable!("jumps have no id"),
+            };
+            graph.add_node(node(id.clone(), data));
+        }
+    }
+
+    if uses_end {
+        graph.add_node(node(END_NODE_ID.to_string(), StoryNodeVariant::End(EndNodeData::default())));
+    }
+    graph.root_node_id = ids[0].iter().flatten().next().cloned().unwrap_or_default();
+    Ok(graph)
+}
+
+// --- Printing --------------------------------------------------------------
+
+struct Printer<'a> {
+    graph: &'a StoryGraphData,
+    /// Indices of nodes that start a knot, in print order
+    knots: Vec<Vec<usize>>,
+}
+
+/// The node flow continues to when nothing jumps elsewhere.
+fn continuation(node: &StoryNodeData) -> Option<&str> {
+    match &node.data {
+        StoryNodeVariant::Dialogue(d) => d.next_node_id.as_deref(),
+        StoryNodeVariant::Effect(e) => e.next_node_id.as_deref(),
+        StoryNodeVariant::Call(c) => c.next_node_id.as_deref(),
+        StoryNodeVariant::Conditional(c) => Some(&c.false_target_node_id),
+        _ => None,
+    }
+}
+
+fn is_shared_end(node: &StoryNodeData) -> bool {
+    node.id == END_NODE_ID && matches!(node.data, StoryNodeVariant::End(_))
+}
+
+impl<'a> Printer<'a> {
+    fn new(graph: &'a StoryGraphData) -> Result<Self, ScriptError> {
+        let nodes: Vec<(usize, &StoryNodeData)> =
+            graph.nodes.iter().enumerate().filter(|(_, n)| !is_shared_end(n)).collect();
+
+        // Nodes reached other than by falling through from the node before.
+        let mut jumped_to: HashSet<&str> = HashSet::new();
+        for (position, (_, node)) in nodes.iter().enumerate() {
+            let falls_into = nodes.get(position + 1).map(|(_, n)| n.id.as_str());
+            let continues = continuation(node);
+            for target in node.next_node_ids() {
+                let is_fallthrough = Some(target) == continues && Some(target) == falls_into;
+                if !is_fallthrough || matches!(node.data, StoryNodeVariant::Choice(_)) {
+                    jumped_to.insert(target);
+                }
+            }
+        }
+
+        let mut knots: Vec<Vec<usize>> = Vec::new();
+        for (position, (index, node)) in nodes.iter().enumerate() {
+            let previous_continues = position > 0
+                && continuation(nodes[position - 1].1) == Some(node.id.as_str());
+            let starts_knot = position == 0
+                || node.id == graph.root_node_id
+                || jumped_to.contains(node.id.as_str())
+                || !previous_continues;
+            if starts_knot {
+                if !is_name(&node.id) {
+                    return Err(ScriptError::Unsupported {
+                        node_id: node.id.clone(),
+                        reason: "id isn't a valid knot name".to_string(),
+                    });
+                }
+                knots.push(vec![*index]);
+            } else {
+                knots.last_mut().unwrap().push(*index);
+            }
+        }
+
+        // The root knot goes first.
+        if let Some(root) = knots.iter().position(|k| graph.nodes[k[0]].id == graph.root_node_id) {
+            let root_knot = knots.remove(root);
+            knots.insert(0, root_knot);
+        }
+        Ok(Self { graph, knots })
+    }
+
+    fn print(&self) -> Result<String, ScriptError> {
+        let mut out = String::new();
+        let mut variables: Vec<_> = self.graph.variables.iter().collect();
+        variables.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, value) in &variables {
+            out.push_str(&format!("VAR {} = {}\n", name, value));
+        }
+
+        for knot in &self.knots {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            let name = &self.graph.nodes[knot[0]].id;
+            out.push_str(&format!("=== {} ===\n", name));
+            for (n, &index) in knot.iter().enumerate() {
+                let node = &self.graph.nodes[index];
+                let default_id = match n {
+                    0 => name.clone(),
+                    n => format!("{}.{}", name, n),
+                };
+                let falls_into = knot.get(n + 1).map(|&i| self.graph.nodes[i].id.as_str());
+                self.print_node(&mut out, node, node.id != default_id, falls_into)?;
+            }
+        }
+        Ok(out)
+    }
+
+    fn print_node(
+        &self,
+        out: &mut String,
+        node: &StoryNodeData,
+        tag_id: bool,
+        falls_into: Option<&str>,
+    ) -> Result<(), ScriptError> {
+        let unsupported = |reason: &str| ScriptError::Unsupported {
+            node_id: node.id.clone(),
+            reason: reason.to_string(),
+        };
+        let with_id = |line: String| {
+            if tag_id {
+                format!("{} #{}\n", line, node.id)
+            } else {
+                format!("{}\n", line)
+            }
+        };
+        let mut jump = continuation(node).filter(|&next| Some(next) != falls_into);
+        if !node.required_entities.is_empty() || !node.required_items.is_empty() {
+            return Err(unsupported("required entities and items have no script syntax"));
+        }
+
+        match &node.data {
+            StoryNodeVariant::Dialogue(d) => {
+                if d.speaker_id.contains(':') || d.speaker_id.trim().is_empty() {
+                    return Err(unsupported("speaker must be non-empty and contain no ':'"));
+                }
+                if d.portrait_id.is_some() || d.voice_line_id.is_some() || d.duration.is_some() {
+                    return Err(unsupported("portraits, voice lines and durations have no script syntax"));
+                }
+                let line = escape(&format!("{}: {}", d.speaker_id, line_text(&d.text)))
+                    .ok_or_else(|| unsupported("text contains `\\#`"))?;
+                out.push_str(&with_id(line));
+            }
+            StoryNodeVariant::Choice(c) => {
+                if c.timeout.is_some() || c.default_option.is_some() {
+                    return Err(unsupported("timed choices have no script syntax"));
+                }
+                let prompt = escape(line_text(&c.prompt)).ok_or_else(|| unsupported("text contains `\\#`"))?;
+                if !prompt.is_empty() || tag_id {
+                    out.push_str(&with_id(format!("> {}", prompt).trim_end().to_string()));
+                }
+                for (n, option) in c.options.iter().enumerate() {
+                    if option.show_when_unavailable {
+                        return Err(unsupported("show_when_unavailable has no script syntax"));
+                    }
+                    let text = escape(line_text(&option.text)).ok_or_else(|| unsupported("text contains `\\#`"))?;
+                    let mut line = format!("* {}", text);
+                    if let Some(flag) = &option.flag_required {
+                        if !is_name(flag) {
+                            return Err(unsupported("flag names must be plain words"));
+                        }
+                        line.push_str(&format!(" <<flag {}>>", flag));
+                    }
+                    for condition in &option.conditions {
+                        line.push_str(&format!(" <<if {}>>", print_condition(condition)));
+                    }
+                    for effect in &option.effects {
+                        let effect = print_effect(effect).ok_or_else(|| unsupported("option effect isn't set/add"))?;
+                        line.push_str(&format!(" <<set {}>>", effect));
+                    }
+                    line.push_str(&format!(" -> {}", option.target_node_id));
+                    if option.id != (n + 1).to_string() {
+                        line.push_str(&format!(" #{}", option.id));
+                    }
+                    out.push_str(&line);
+                    out.push('\n');
+                }
+            }
+            StoryNodeVariant::Conditional(c) => {
+                let mut line = format!("if {} -> {}", print_condition(&c.condition), c.true_target_node_id);
+                if let Some(otherwise) = jump.take() {
+                    line.push_str(&format!(" else -> {}", otherwise));
+                }
+                out.push_str(&with_id(line));
+            }
+            StoryNodeVariant::Effect(e) => match e.effects.as_slice() {
+                [effect] => {
+                    let effect = print_effect(effect).ok_or_else(|| unsupported("effect isn't set/add"))?;
+                    out.push_str(&with_id(format!("set {}", effect)));
+                }
+                _ => return Err(unsupported("effect nodes must have exactly one effect")),
+            },
+            StoryNodeVariant::Call(c) => {
+                let line = match &c.entry_node {
+                    Some(entry) => format!("call {}:{}", c.graph_id, entry),
+                    None => format!("call {}", c.graph_id),
+                };
+                out.push_str(&with_id(line));
+            }
+            StoryNodeVariant::Return(_) => out.push_str(&with_id("RETURN".to_string())),
+            StoryNodeVariant::End(e) if *e == EndNodeData::default() => {
+                out.push_str(&with_id("END".to_string()))
+            }
+            _ => return Err(unsupported(&format!("{:?} nodes aren't supported", node.node_type()))),
+        }
+
+        if let Some(next) = jump {
+            out.push_str(&format!("-> {}\n", next));
+        }
+        Ok(())
+    }
+}
+
+/// Text in the default language (or any, if it's missing).
+pub(crate) fn line_text(text: &LocalizedString) -> &str {
+    text.get(DEFAULT_LANGUAGE)
+        .or_else(|| text.iter().min_by_key(|(code, _)| *code).map(|(_, t)| t))
+        .map_or("", String::as_str)
+}
+
+fn print_value(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) if is_name(s) && parse_value(s) == *value => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+pub(crate) fn print_condition(condition: &StoryCondition) -> String {
+    match (condition.operator, &condition.value) {
+        (ConditionOperator::Equals, serde_json::Value::Bool(true)) => condition.variable.clone(),
+        (ConditionOperator::Equals, serde_json::Value::Bool(false)) => format!("!{}", condition.variable),
+        (operator, value) => {
+            let symbol = OPERATORS.iter().find(|(_, op)| *op == operator).map(|(s, _)| s.trim()).unwrap();
+            format!("{} {} {}", condition.variable, symbol, print_value(value))
+        }
+    }
+}
+
+pub(crate) fn print_effect(effect: &StoryEffect) -> Option<String> {
+    let variable = effect.params.get("variable")?.as_str()?;
+    let value = effect.params.get("value")?;
+    match effect.effect_type {
+        EffectType::SetVar => Some(format!("{} = {}", variable, print_value(value))),
+        EffectType::AddVar => match (value.as_i64(), value.as_f64()) {
+            (Some(i), _) if i < 0 => Some(format!("{} -= {}", variable, -i)),
+            (None, Some(f)) if f < 0.0 => Some(format!("{} -= {}", variable, -f)),
+            _ => Some(format!("{} += {}", variable, print_value(value))),
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::story::RequiredItem;
+
+    const SHOP: &str = "\
+VAR gold = 10
+
+=== shop ===
+Shopkeeper: Welcome! You have {gold} gold.
+> What will it be?
+* Buy a herb <<if gold >= 5>> <<set gold -= 5>> -> thanks
+* Use the coupon <<flag has_coupon>> <<set gold += 1>> -> thanks #coupon
+* Leave -> END #leave
+
+=== broke ===
+set mood = sad
+Shopkeeper: Out of gold?
+-> END
+
+=== thanks ===
+Shopkeeper: Come again.
+Shopkeeper: Don't spend it all at once. #farewell
+if gold > 0 -> shop else -> broke
+";
+
+    #[test]
+    fn test_compile_script() {
+        let graph = compile("shop", SHOP).unwrap();
+        assert_eq!(graph.root_node_id, "shop");
+        assert_eq!(graph.variables["gold"], serde_json::json!(10));
+        let ids: Vec<&str> = graph.nodes.iter().map(|n| n.id.as_str()).collect();
+        assert_eq!(ids, vec!["shop", "shop.1", "broke", "broke.1", "thanks", "farewell", "thanks.2", "END"]);
+
+        let StoryNodeVariant::Choice(choice) = &graph.nodes[1].data else { panic!() };
+        assert_eq!(choice.options[0].target_node_id, "thanks");
+        assert_eq!(choice.options[0].conditions[0].operator, ConditionOperator::GreaterThanOrEquals);
+        assert_eq!(choice.options[0].effects[0].params["value"], serde_json::json!(-5));
+        assert_eq!(choice.options[1].flag_required.as_deref(), Some("has_coupon"));
+        assert_eq!(choice.options[2].id, "leave");
+        assert!(graph.validate().is_empty());
+    }
+
+    #[test]
+    fn test_script_round_trip() {
+        let graph = compile("shop", SHOP).unwrap();
+        let script = to_script(&graph).unwrap();
+        assert_eq!(script, SHOP);
+        assert_eq!(compile("shop", &script).unwrap(), graph);
+    }
+
+    #[test]
+    fn test_script_rejects_unwritable_fields() {
+        let graph = compile("shop", SHOP).unwrap();
+        let edits: [fn(&mut StoryGraphData); 4] = [
+            |graph| {
+                let StoryNodeVariant::Dialogue(d) = &mut graph.nodes[0].data else { panic!() };
+                d.voice_line_id = Some("shop_welcome".to_string());
+            },
+            |graph| {
+                let StoryNodeVariant::Choice(c) = &mut graph.nodes[1].data else { panic!() };
+                c.options[0].flag_required = Some("has coupon".to_string());
+            },
+            |graph| {
+                let StoryNodeVariant::Choice(c) = &mut graph.nodes[1].data else { panic!() };
+                c.options[0].show_when_unavailable = true;
+            },
+            |graph| graph.nodes[2].required_items.push(RequiredItem { item_id: "herb".to_string(), quantity: 1 }),
+        ];
+        for edit in edits {
+            let mut edited = graph.clone();
+            edit(&mut edited);
+            assert!(matches!(to_script(&edited), Err(ScriptError::Unsupported { .. })));
+        }
+    }
+
+    #[test]
+    fn test_script_hash_in_text() {
+        let graph = compile("rank", "Merchant: Ranked \\#1\nMerchant: You're \\#1! #cheer\n> Pick \\#2\n* Take \\#2 -> END").unwrap();
+        let ids: Vec<&str> = graph.nodes.iter().map(|n| n.id.as_str()).collect();
+        assert_eq!(ids, vec!["start", "cheer", "start.2", "END"]);
+        let StoryNodeVariant::Dialogue(d) = &graph.nodes[1].data else { panic!() };
+        assert_eq!(line_text(&d.text), "You're #1!");
+        let StoryNodeVariant::Choice(c) = &graph.nodes[2].data else { panic!() };
+        assert_eq!(line_text(&c.options[0].text), "Take #2");
+
+        let script = to_script(&graph).unwrap();
+        assert!(script.contains("Merchant: Ranked \\#1\n"));
+        assert_eq!(compile("rank", &script).unwrap(), graph);
+
+        match compile("rank", "Merchant: You're #1!") {
+            Err(ScriptError::Syntax { message, .. }) => assert!(message.contains("\\#"), "{}", message),
+            other => panic!("expected a syntax error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_script_errors() {
+        for (source, line, column) in [
+            ("=== a ===\nNarrator: hi\n-> nowhere", 3, 4),
+            ("=== a ===\n  * Go somewhere", 2, 5),
+            ("=== a ===\nNarrator hi", 2, 1),
+            ("=== a ===\nNarrator: hi\n=== a ===\nEND", 4, 1),
+            ("=== a ===\nEND\n-> a", 3, 4),
+            ("=== a ===\n* Go <<wait 1>> -> a", 2, 6),
+            ("=== a ===\n* Go <<flag a>> <<flag b>> -> a", 2, 17),
+        ] {
+            match compile("test", source) {
+                Err(ScriptError::Syntax { line: l, column: c, .. }) => {
+                    assert_eq!((l, c), (line, column), "{}", source)
+                }
+                other => panic!("{}: expected a syntax error, got {:?}", source, other),
+            }
+        }
+    }
+}
@@ -10,7 +10,7 @@ use bevy_inspector_egui::bevy_inspector;
 use crate::diagnostics::console::ConsoleLogStore;
 use crate::data::story::{StoryGraphData, StoryNodeData, StoryNodeVariant};
 use crate::story_graph::GraphExecutor;
-use crate::data::{loader, project::Project};
+use crate::data::{find_project_file, load_project_bundle, loader, project::Project, save_project_bundle, ProjectBundle};
 use crate::data::scene::{Scene, Entity as SceneEntity};
 use crate::data::components::{EntityComponents, TransformComponent, SpriteComponent, ColorData, Vec3Data};
 use std::path::PathBuf;
@@ -19,6 +19,13 @@ const COLOR_PRIMARY: Color32 = Color32::from_rgb(0, 255, 204); // Cyberpunk Mint
 const COLOR_SECONDARY: Color32 = Color32::from_rgb(255, 175, 200); // Pale Rose
 const COLOR_BG: Color32 = Color32::from_rgb(15, 15, 20);
 
+/// Ids the editor saves its scene and story graph under.
+const CURRENT_SCENE: &str = "current_scene";
+const MAIN_GRAPH: &str = "main";
+/// Where they go in projects that don't list them yet.
+const CURRENT_SCENE_PATH: &str = "scenes/current_scene.json";
+const MAIN_GRAPH_PATH: &str = "story_graphs/main.json";
+
 #[derive(States, Debug, Clone, PartialEq, Eq, Hash, Default)]
 pub enum EditorState {
     #[default]
@@ -290,27 +297,27 @@ fn draw_top_menu(ui: &mut egui::Ui, world: &mut World) {
                 let path = PathBuf::from("games/dev/doomexe");
                 project.path = Some(path.clone());
                 
-                // Try load scene
-                let scene_path = path.join("scenes/current_scene.json");
-                if scene_path.exists() {
-                     match loader::load_scene(&scene_path) {
-                         Ok(scene) => load_scene_into_editor(world, scene),
-                         Err(e) => error!("Failed to load scene: {}", e),
-                     }
-                } else {
-                     warn!("No scene found at {:?}", scene_path);
+                // Load the project with every scene and graph it lists
+                let (scene, graph) = match load_project_bundle(&path) {
+                    Ok(mut bundle) => (
+                        bundle.scenes.remove(CURRENT_SCENE).or_else(|| bundle.scenes.pop_first().map(|(_, s)| s)),
+                        bundle.story_graphs.remove(MAIN_GRAPH).or_else(|| bundle.story_graphs.pop_first().map(|(_, g)| g)),
+                    ),
+                    Err(e) => {
+                        error!("Failed to load project: {}", e);
+                        (None, None)
+                    }
+                };
+
+                // Projects that list none keep them at the editor's fixed paths
+                let scene = scene.or_else(|| load_fixed(&path.join(CURRENT_SCENE_PATH), loader::load_scene));
+                match scene {
+                    Some(scene) => load_scene_into_editor(world, scene),
+                    None => warn!("Project at {:?} has no scenes", path),
                 }
-                
-                // Try load story graph
-                let graph_path = path.join("story_graphs/main.json");
-                if graph_path.exists() {
-                     match loader::load_story_graph(&graph_path) {
-                         Ok(graph) => {
-                             world.insert_resource(ActiveStoryGraph(graph));
-                             info!("Loaded story graph");
-                         }
-                         Err(e) => error!("Failed to load story graph: {}", e),
-                     }
+                if let Some(graph) = graph.or_else(|| load_fixed(&path.join(MAIN_GRAPH_PATH), loader::load_story_graph)) {
+                    world.insert_resource(ActiveStoryGraph(graph));
+                    info!("Loaded story graph");
                 }
                 
                 info!("Editor: Loaded project path 'games/dev/doomexe'");
@@ -826,6 +833,8 @@ fn draw_story_graph(ui: &mut egui::Ui, world: &mut World) {
                     StoryNodeVariant::Start(d) => d.next_node_id = Some(to),
                     StoryNodeVariant::Dialogue(d) => d.next_node_id = Some(to),
                     StoryNodeVariant::Action(a) => a.next_node_id = Some(to),
+                    StoryNodeVariant::Effect(e) => e.next_node_id = Some(to),
+                    StoryNodeVariant::Call(c) => c.next_node_id = Some(to),
                     _ => {}
                 }
             }
@@ -843,33 +852,49 @@ fn save_project_impl(world: &mut World) {
     if let Some(path) = project_path {
         info!("Saving project to {:?}", path);
         
-        // 1. Save Project Structure
-        let project_data = Project::new(&project_name); 
-        match loader::save_project_structure(&project_data, &path) {
-             Ok(_) => info!("Successfully saved project structure"),
-             Err(e) => error!("Failed to save project structure: {}", e),
+        // Keep what the project already lists; the editor's scene and graph
+        // go under their fixed ids
+        let project_file = find_project_file(&path);
+        let mut project_data = match &project_file {
+            Some((file, _)) => match loader::load_project(file) {
+                Ok(project) => project,
+                Err(e) => {
+                    error!("Failed to save project: {}", e);
+                    return;
+                }
+            },
+            None => Project::new(&project_name),
+        };
+        if project_data.find_scene(CURRENT_SCENE).is_none() {
+            project_data.add_scene(CURRENT_SCENE, CURRENT_SCENE_PATH);
+        }
+        if project_data.find_story_graph(MAIN_GRAPH).is_none() {
+            project_data.add_story_graph(MAIN_GRAPH, MAIN_GRAPH_PATH);
         }
 
-        // 2. Save Current Scene
-        let scene = world_to_scene(world);
-        let scene_path = path.join("scenes/current_scene.json");
-        match loader::save_scene(&scene, &scene_path) {
-            Ok(_) => info!("Successfully saved scene to {:?}", scene_path),
-            Err(e) => error!("Failed to save scene: {}", e),
+        let mut bundle = ProjectBundle::new(&path, project_data);
+        if let Some((_, format)) = project_file {
+            bundle.project_format = format;
         }
-        
-        // 3. Save Story Graph
-        let graph = &world.resource::<ActiveStoryGraph>().0;
-        let graph_path = path.join("story_graphs/main.json");
-        match loader::save_story_graph(graph, &graph_path) {
-             Ok(_) => info!("Successfully saved story graph to {:?}", graph_path),
-             Err(e) => error!("Failed to save story graph: {}", e),
+        bundle.scenes.insert(CURRENT_SCENE.to_string(), world_to_scene(world));
+        bundle.story_graphs.insert(MAIN_GRAPH.to_string(), world.resource::<ActiveStoryGraph>().0.clone());
+        match save_project_bundle(&bundle) {
+            Ok(written) => info!("Successfully saved project ({} files changed)", written.len()),
+            Err(e) => error!("Failed to save project: {}", e),
         }
     } else {
         warn!("Cannot save: No project path set!");
     }
 }
 
+/// Load a file the project doesn't list, if it exists.
+fn load_fixed<T>(path: &std::path::Path, load: fn(&std::path::Path) -> Result<T, loader::DataError>) -> Option<T> {
+    if !path.exists() {
+        return None;
+    }
+    load(path).map_err(|e| error!("Failed to load {:?}: {}", path, e)).ok()
+}
+
 fn world_to_scene(world: &mut World) -> Scene {
     let mut scene = Scene::new("current_scene", "Current Scene");
     
@@ -1,5 +1,6 @@
 use bevy::prelude::*;
 use bevy_egui::egui;
+use crate::data::analysis::Severity;
 use crate::data::story::{StoryGraphData, SceneValidationError};
 use crate::data::scene::{Scene, Entity, EntityType};
 
@@ -20,6 +21,11 @@ pub fn draw_validation_panel(
     ui.heading("Validation & Missing Items");
     ui.separator();
 
+    if let Some(graph) = active_graph {
+        draw_story_diagnostics(ui, graph);
+        ui.separator();
+    }
+
     if active_graph.is_none() || active_scene.is_none() {
         ui.label("Open a Scene and a Story Graph to validate.");
         return;
@@ -109,3 +115,21 @@ pub fn draw_validation_panel(
         });
     }
 }
+
+/// Lists analyzer findings (unreachable nodes, loops, etc.) for the graph.
+fn draw_story_diagnostics(ui: &mut egui::Ui, graph: &StoryGraphData) {
+    let diagnostics = graph.analyze();
+    if diagnostics.is_empty() {
+        ui.label(egui::RichText::new("✓ Story graph analysis passed").color(egui::Color32::GREEN));
+        return;
+    }
+
+    ui.label(egui::RichText::new(format!("⚠ Story graph analysis: {} issues", diagnostics.len())).color(egui::Color32::YELLOW));
+    for diagnostic in &diagnostics {
+        let color = match diagnostic.severity {
+            Severity::Error => egui::Color32::RED,
+            Severity::Warning => egui::Color32::ORANGE,
+        };
+        ui.label(egui::RichText::new(diagnostic.to_string()).color(color));
+    }
+}
@@ -38,7 +38,7 @@ pub mod prelude {
     // Individual plugins (for fine-grained control)
     pub use crate::animation::DJAnimationPlugin;
     pub use crate::assets::DJAssetPlugin;
-    pub use crate::audio::{AudioCommand, AudioState, BgmSource, DJAudioPlugin, SfxSource};
+    pub use crate::audio::{AudioCommand, AudioState, BgmSource, DJAudioPlugin, SfxSource, VoiceSource};
     pub use crate::diagnostics::DiagnosticsPlugin;
     pub use crate::input::{ActionState, DJInputPlugin, InputAction, InputConfig};
     pub use crate::rendering::RenderingPlugin;
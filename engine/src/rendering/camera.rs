@@ -12,6 +12,9 @@ pub struct MainCamera;
 pub const GAME_WIDTH: f32 = 320.0;
 pub const GAME_HEIGHT: f32 = 240.0;
 
+/// Orthographic scale of the main camera at zoom 1.0.
+pub const DEFAULT_CAMERA_SCALE: f32 = 0.25;
+
 /// Sets up the main camera for 2D rendering.
 pub fn setup_camera(mut commands: Commands) {
     commands.spawn((
@@ -21,7 +24,7 @@ pub fn setup_camera(mut commands: Commands) {
             ..default()
         },
         Projection::from(OrthographicProjection {
-            scale: 0.25, // Zoom in (4x)
+            scale: DEFAULT_CAMERA_SCALE, // Zoom in (4x)
             ..OrthographicProjection::default_2d()
         }),
         MainCamera,
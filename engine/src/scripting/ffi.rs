@@ -6,6 +6,8 @@ use bevy::prelude::*;
 use std::sync::{Arc, RwLock};
 use mlua::prelude::*;
 
+use crate::story_graph::{StoryValue, StoryVariableChanged};
+
 /// Registers core FFI functions into the Lua global table.
 /// Games should call this first, then register their own APIs.
 pub fn register_core_api(lua: &Lua) -> LuaResult<()> {
@@ -109,3 +111,49 @@ pub fn register_generic_state_api(lua: &Lua, state: SharedGenericState) -> LuaRe
 
     Ok(())
 }
+
+/// Convert a story variable into a Lua value (lists become sequences).
+pub fn story_value_to_lua(lua: &Lua, value: &StoryValue) -> LuaResult<LuaValue> {
+    Ok(match value {
+        StoryValue::Bool(b) => LuaValue::Boolean(*b),
+        StoryValue::Int(i) => LuaValue::Integer(*i),
+        StoryValue::Float(f) => LuaValue::Number(*f),
+        StoryValue::String(s) => LuaValue::String(lua.create_string(s)?),
+        StoryValue::List(items) => {
+            let table = lua.create_table()?;
+            for (i, item) in items.iter().enumerate() {
+                table.set(i + 1, story_value_to_lua(lua, item)?)?;
+            }
+            LuaValue::Table(table)
+        }
+    })
+}
+
+/// Register the `story_vars` table that mirrors story variables.
+pub fn register_story_variable_api(lua: &Lua) -> LuaResult<()> {
+    lua.globals().set("story_vars", lua.create_table()?)
+}
+
+/// Mirror a story variable change into `story_vars` and call the script's
+/// `on_story_var_changed(name, value, old)` hook if one is defined. A
+/// removed variable becomes `nil`.
+pub fn publish_story_variable_change(lua: &Lua, change: &StoryVariableChanged) -> LuaResult<()> {
+    let globals = lua.globals();
+    let value = match &change.value {
+        Some(value) => story_value_to_lua(lua, value)?,
+        None => LuaValue::Nil,
+    };
+
+    let vars: LuaTable = globals.get("story_vars")?;
+    vars.set(change.name.as_str(), value.clone())?;
+
+    if let Ok(callback) = globals.get::<LuaFunction>("on_story_var_changed") {
+        let old = match &change.old {
+            Some(old) => story_value_to_lua(lua, old)?,
+            None => LuaValue::Nil,
+        };
+        callback.call::<()>((change.name.as_str(), value, old))?;
+    }
+
+    Ok(())
+}
@@ -4,13 +4,15 @@
 
 use bevy::prelude::*;
 
+use crate::story_graph::{variables::emit_variable_changes, StoryVariableChanged};
+
 pub mod context;
 pub mod ffi;
 
 pub use context::LuaContext;
 pub use ffi::{
-    create_shared_state, register_core_api, register_generic_state_api, GenericStateBuffer,
-    SharedGenericState,
+    create_shared_state, register_core_api, register_generic_state_api,
+    register_story_variable_api, story_value_to_lua, GenericStateBuffer, SharedGenericState,
 };
 
 /// Events for script control.
@@ -33,12 +35,22 @@ impl Plugin for DJScriptingPlugin {
             if let Err(e) = ffi::register_core_api(&lua) {
                 error!("Failed to register core Lua API: {}", e);
             }
+            if let Err(e) = ffi::register_story_variable_api(&lua) {
+                error!("Failed to register story variable Lua API: {}", e);
+            }
         }
 
         app.insert_resource(lua_ctx)
             .register_type::<ScriptCommand>()
             .add_event::<ScriptCommand>()
-            .add_systems(Update, handle_script_commands);
+            .add_event::<StoryVariableChanged>()
+            .add_systems(
+                Update,
+                (
+                    handle_script_commands,
+                    sync_story_variables.after(emit_variable_changes),
+                ),
+            );
 
         info!("DJ Scripting Plugin initialized");
     }
@@ -67,3 +79,20 @@ fn handle_script_commands(
         }
     }
 }
+
+/// System that forwards story variable changes to Lua.
+fn sync_story_variables(
+    lua_ctx: Res<LuaContext>,
+    mut events: EventReader<StoryVariableChanged>,
+) {
+    if events.is_empty() {
+        return;
+    }
+
+    let lua = lua_ctx.lua.lock().unwrap();
+    for change in events.read() {
+        if let Err(e) = ffi::publish_story_variable_change(&lua, change) {
+            error!("Lua story variable hook failed for '{}': {}", change.name, e);
+        }
+    }
+}
@@ -0,0 +1,183 @@
+//! Camera and time control driven by story graphs.
+//!
+//! Camera and TimeControl nodes send [`CinematicCommand`]s; the systems here
+//! tween the [`MainCamera`] and scale or pause virtual time in response.
+//! Both run on real time so a paused game can still play a cutscene.
+
+use bevy::prelude::*;
+
+use crate::rendering::camera::{MainCamera, DEFAULT_CAMERA_SCALE};
+
+/// Easing curve used by camera tweens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+pub enum Easing {
+    #[default]
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    /// Parse an easing name as authored in `CameraNodeData::easing`.
+    ///
+    /// Unknown or empty names fall back to [`Easing::Linear`].
+    pub fn from_name(name: &str) -> Self {
+        match name.to_lowercase().replace('-', "_").as_str() {
+            "ease_in" | "in" => Easing::EaseIn,
+            "ease_out" | "out" => Easing::EaseOut,
+            "ease_in_out" | "in_out" | "smooth" => Easing::EaseInOut,
+            "" | "linear" => Easing::Linear,
+            other => {
+                warn!("Unknown camera easing '{}', using linear", other);
+                Easing::Linear
+            }
+        }
+    }
+
+    /// Map linear progress `t` in `[0, 1]` onto the curve.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+/// Commands sent from the story executor to the camera/time systems.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub enum CinematicCommand {
+    /// Tween the main camera to a position, zoom and angle (degrees).
+    MoveCamera {
+        position: Vec3,
+        zoom: f32,
+        angle: f32,
+        duration: f32,
+        easing: Easing,
+    },
+    /// Pause and/or scale gameplay (virtual) time.
+    SetTimeControl { pause_gameplay: bool, time_scale: f32 },
+}
+
+/// An in-flight camera tween attached to the main camera.
+#[derive(Component, Debug, Clone)]
+pub struct CameraTween {
+    pub from_translation: Vec3,
+    pub to_translation: Vec3,
+    pub from_scale: f32,
+    pub to_scale: f32,
+    pub from_angle: f32,
+    pub to_angle: f32,
+    pub easing: Easing,
+    pub timer: Timer,
+}
+
+/// Starts camera tweens and applies time control for incoming commands.
+pub fn handle_cinematic_commands(
+    mut commands: Commands,
+    mut events: EventReader<CinematicCommand>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    camera_query: Query<(Entity, &Transform, Option<&OrthographicProjection>), With<MainCamera>>,
+) {
+    for event in events.read() {
+        match event {
+            CinematicCommand::MoveCamera { position, zoom, angle, duration, easing } => {
+                let Ok((entity, transform, projection)) = camera_query.get_single() else {
+                    warn!("Story camera node ignored: no MainCamera found");
+                    continue;
+                };
+
+                // Keep the camera's own depth; story positions are 2D.
+                let to_translation = Vec3::new(position.x, position.y, transform.translation.z);
+                commands.entity(entity).insert(CameraTween {
+                    from_translation: transform.translation,
+                    to_translation,
+                    from_scale: projection.map_or(DEFAULT_CAMERA_SCALE, |p| p.scale),
+                    to_scale: DEFAULT_CAMERA_SCALE / zoom.max(0.01),
+                    from_angle: transform.rotation.to_euler(EulerRot::XYZ).2,
+                    to_angle: angle.to_radians(),
+                    easing: *easing,
+                    timer: Timer::from_seconds(duration.max(0.0), TimerMode::Once),
+                });
+            }
+            CinematicCommand::SetTimeControl { pause_gameplay, time_scale } => {
+                virtual_time.set_relative_speed(time_scale.max(0.0));
+                if *pause_gameplay {
+                    virtual_time.pause();
+                } else {
+                    virtual_time.unpause();
+                }
+                info!("Story time control: paused={}, scale={}", pause_gameplay, time_scale);
+            }
+        }
+    }
+}
+
+/// Query data for cameras with an active tween.
+type TweenedCamera<'a> = (
+    Entity,
+    &'a mut CameraTween,
+    &'a mut Transform,
+    Option<&'a mut OrthographicProjection>,
+    Option<&'a mut Projection>,
+);
+
+/// Advances active camera tweens and removes them when finished.
+pub fn update_camera_tweens(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut query: Query<TweenedCamera>,
+) {
+    for (entity, mut tween, mut transform, ortho, projection) in query.iter_mut() {
+        tween.timer.tick(time.delta());
+
+        let t = if tween.timer.duration().is_zero() {
+            1.0
+        } else {
+            tween.easing.apply(tween.timer.fraction())
+        };
+
+        transform.translation = tween.from_translation.lerp(tween.to_translation, t);
+        transform.rotation =
+            Quat::from_rotation_z(tween.from_angle + (tween.to_angle - tween.from_angle) * t);
+
+        let scale = tween.from_scale + (tween.to_scale - tween.from_scale) * t;
+        if let Some(mut ortho) = ortho {
+            ortho.scale = scale;
+        }
+        if let Some(mut projection) = projection {
+            if let Projection::Orthographic(ortho) = projection.as_mut() {
+                ortho.scale = scale;
+            }
+        }
+
+        if tween.timer.finished() {
+            commands.entity(entity).remove::<CameraTween>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_easing_endpoints() {
+        for easing in [Easing::Linear, Easing::EaseIn, Easing::EaseOut, Easing::EaseInOut] {
+            assert_eq!(easing.apply(0.0), 0.0);
+            assert_eq!(easing.apply(1.0), 1.0);
+        }
+        assert!(Easing::EaseIn.apply(0.5) < 0.5);
+        assert!(Easing::EaseOut.apply(0.5) > 0.5);
+    }
+
+    #[test]
+    fn test_easing_from_name() {
+        assert_eq!(Easing::from_name("ease-in-out"), Easing::EaseInOut);
+        assert_eq!(Easing::from_name(""), Easing::Linear);
+        assert_eq!(Easing::from_name("bogus"), Easing::Linear);
+    }
+}
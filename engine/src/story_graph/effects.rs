@@ -0,0 +1,105 @@
+//! Choice effects applied when the player selects an option.
+//!
+//! Variable effects write straight to [`StoryVariables`]. Inventory and quest
+//! effects belong to the game, so they are forwarded as [`StoryEvent`]s whose
+//! id is the effect type (`give_item`, `remove_item`, `set_quest_state`) and
+//! whose payload is the effect's params serialized as JSON, keys sorted.
+//! The [quest runtime](super::quests) applies them to the [`QuestLog`] and
+//! [`QuestInventory`]; games may handle them as well.
+//!
+//! [`QuestLog`]: super::QuestLog
+//! [`QuestInventory`]: super::QuestInventory
+
+use bevy::prelude::*;
+use std::collections::BTreeMap;
+
+use super::{StoryEvent, StoryValue, StoryVariables};
+use crate::data::story::{EffectType, StoryEffect};
+
+/// Apply a single effect.
+///
+/// Returns the event to forward to the game for effects the story runtime
+/// does not own.
+pub fn apply_effect(effect: &StoryEffect, variables: &mut StoryVariables) -> Option<StoryEvent> {
+    match effect.effect_type {
+        EffectType::SetVar => {
+            let (name, value) = (param_str(effect, "variable")?, param_value(effect, "value")?);
+            variables.set(name, value);
+            None
+        }
+        EffectType::AddVar => {
+            let name = param_str(effect, "variable")?;
+            let amount = param_value(effect, "value")
+                .or_else(|| param_value(effect, "amount"))
+                .unwrap_or(StoryValue::Int(1));
+            if !variables.add(name, amount) {
+                warn!("AddVar effect: cannot add to variable '{}'", name);
+            }
+            None
+        }
+        EffectType::GiveItem => Some(forward(effect, "give_item")),
+        EffectType::RemoveItem => Some(forward(effect, "remove_item")),
+        EffectType::SetQuestState => Some(forward(effect, "set_quest_state")),
+    }
+}
+
+fn forward(effect: &StoryEffect, id: &str) -> StoryEvent {
+    StoryEvent {
+        id: id.to_string(),
+        // Sorted so the payload is stable between runs.
+        payload: serde_json::to_string(&effect.params.iter().collect::<BTreeMap<_, _>>()).unwrap_or_default(),
+    }
+}
+
+fn param_str<'a>(effect: &'a StoryEffect, key: &str) -> Option<&'a str> {
+    let value = effect.params.get(key).and_then(|v| v.as_str());
+    if value.is_none() {
+        warn!("{:?} effect is missing string param '{}'", effect.effect_type, key);
+    }
+    value
+}
+
+fn param_value(effect: &StoryEffect, key: &str) -> Option<StoryValue> {
+    effect.params.get(key).and_then(StoryValue::from_json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn effect(effect_type: EffectType, params: serde_json::Value) -> StoryEffect {
+        let params: HashMap<String, serde_json::Value> = serde_json::from_value(params).unwrap();
+        StoryEffect { effect_type, params }
+    }
+
+    #[test]
+    fn test_variable_effects() {
+        let mut vars = StoryVariables::default();
+        let set = effect(EffectType::SetVar, json!({ "variable": "accepted_quest", "value": true }));
+        let add = effect(EffectType::AddVar, json!({ "variable": "gold", "value": 15 }));
+
+        assert!(apply_effect(&set, &mut vars).is_none());
+        assert!(apply_effect(&add, &mut vars).is_none());
+        assert!(apply_effect(&add, &mut vars).is_none());
+
+        assert_eq!(vars.get("accepted_quest"), Some(&StoryValue::Bool(true)));
+        assert_eq!(vars.get("gold"), Some(&StoryValue::Int(30)));
+    }
+
+    #[test]
+    fn test_game_effects_forwarded() {
+        let mut vars = StoryVariables::default();
+        let quest = effect(
+            EffectType::SetQuestState,
+            json!({ "quest_id": "quest_fetch_herbs", "state": "active" }),
+        );
+
+        let event = apply_effect(&quest, &mut vars).unwrap();
+        assert_eq!(event.id, "set_quest_state");
+        let payload: serde_json::Value = serde_json::from_str(&event.payload).unwrap();
+        assert_eq!(payload["quest_id"], "quest_fetch_herbs");
+        assert_eq!(vars.iter().count(), 0);
+    }
+}
@@ -0,0 +1,289 @@
+//! Headless story playthroughs for tests.
+//!
+//! [`StoryHarness`] runs the story executor in a minimal app without UI,
+//! feeds it scripted [`StoryInputEvent`]s and records what it emits into a
+//! plain-text transcript:
+//!
+//! ```text
+//! > start shop
+//! dialogue Merchant: Take a look!
+//! > advance
+//! choices What'll it be?
+//!   [0] Buy a herb
+//!   [1] Leave
+//! > choose 0
+//! event give_item: {"item_id":"herb"}
+//! dialogue Merchant: Enjoy!
+//! > advance
+//! complete
+//! ```
+//!
+//! After each input the harness steps fixed frames of
+//! [`StoryHarness::FRAME`] until the executor waits for the next input or
+//! finishes, so waits, camera moves and timed lines play out the same way
+//! on every run. Within a frame, story events are listed first, then audio
+//! commands, scene changes and flow events, which roughly follows the order
+//! nodes send them in (a choice's effects come before the next line).
+//!
+//! Compare transcripts against checked-in files with
+//! [`StoryHarness::assert_golden`]; run with `UPDATE_GOLDEN=1` to write
+//! them instead.
+
+use bevy::ecs::event::EventCursor;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use std::path::Path;
+use std::time::Duration;
+
+use super::{
+    ExecutionStatus, ExecutorId, GraphExecutor, StoryEvent, StoryFlowEvent, StoryGraph,
+    StoryGraphPlugin, StoryInputEvent,
+};
+use crate::audio::AudioCommand;
+use crate::data::story::StoryGraphData;
+use crate::scene::ChangeSceneEvent;
+
+/// Environment variable that makes [`StoryHarness::assert_golden`] write
+/// golden files instead of comparing against them.
+pub const UPDATE_GOLDEN_VAR: &str = "UPDATE_GOLDEN";
+
+/// Drives the main [`GraphExecutor`] headlessly and records a transcript.
+pub struct StoryHarness {
+    app: App,
+    flow: EventCursor<StoryFlowEvent>,
+    story: EventCursor<StoryEvent>,
+    audio: EventCursor<AudioCommand>,
+    scene: EventCursor<ChangeSceneEvent>,
+    transcript: Vec<String>,
+}
+
+impl Default for StoryHarness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StoryHarness {
+    /// Simulated time per frame.
+    pub const FRAME: Duration = Duration::from_nanos(16_666_667);
+    /// Frames to wait for the executor to settle before giving up.
+    pub const MAX_FRAMES: usize = 100_000;
+
+    pub fn new() -> Self {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugins(StoryGraphPlugin)
+            .add_event::<AudioCommand>()
+            .add_event::<ChangeSceneEvent>()
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Self::FRAME));
+
+        let world = app.world();
+        Self {
+            flow: world.resource::<Events<StoryFlowEvent>>().get_cursor(),
+            story: world.resource::<Events<StoryEvent>>().get_cursor(),
+            audio: world.resource::<Events<AudioCommand>>().get_cursor(),
+            scene: world.resource::<Events<ChangeSceneEvent>>().get_cursor(),
+            app,
+            transcript: Vec::new(),
+        }
+    }
+
+    /// The harness's world, for seeding variables, flags, the
+    /// [`StoryGraphLibrary`](super::StoryGraphLibrary) or a database.
+    pub fn world_mut(&mut self) -> &mut World {
+        self.app.world_mut()
+    }
+
+    pub fn executor(&self) -> &GraphExecutor {
+        self.app.world().resource::<GraphExecutor>()
+    }
+
+    pub fn status(&self) -> ExecutionStatus {
+        self.executor().status
+    }
+
+    /// Start a graph and run it until it waits or finishes.
+    pub fn start(&mut self, graph: StoryGraph) {
+        self.transcript.push(format!("> start {}", graph.id));
+        self.world_mut().resource_mut::<GraphExecutor>().start(graph);
+        self.settle();
+    }
+
+    /// Start an authored graph and run it until it waits or finishes.
+    pub fn load(&mut self, data: &StoryGraphData) {
+        self.start(StoryGraph::from_data(data));
+    }
+
+    /// Send one input and run until the executor waits or finishes again.
+    pub fn input(&mut self, input: StoryInputEvent) {
+        let line = match &input {
+            StoryInputEvent::Advance { .. } => "> advance".to_string(),
+            StoryInputEvent::SelectChoice { index, .. } => format!("> choose {}", index),
+            StoryInputEvent::Rewind { line, .. } => format!("> rewind {}", line),
+        };
+        self.transcript.push(line);
+        self.world_mut().send_event(input);
+        self.settle();
+    }
+
+    pub fn advance(&mut self) {
+        self.input(StoryInputEvent::Advance { executor: ExecutorId::Main });
+    }
+
+    /// Pick an option by its index in the last `choices` entry.
+    pub fn choose(&mut self, index: usize) {
+        self.input(StoryInputEvent::SelectChoice { executor: ExecutorId::Main, index });
+    }
+
+    /// Go back to a line of the executor's history.
+    pub fn rewind(&mut self, line: usize) {
+        self.input(StoryInputEvent::Rewind { executor: ExecutorId::Main, line });
+    }
+
+    /// Let `seconds` of simulated time pass without input, e.g. for a
+    /// timed choice to run out, then run until the executor waits again.
+    pub fn wait(&mut self, seconds: f32) {
+        self.transcript.push(format!("> wait {}s", seconds));
+        let frames = (seconds / Self::FRAME.as_secs_f32()).ceil() as usize;
+        for _ in 0..frames {
+            self.app.update();
+            self.record();
+        }
+        self.settle();
+    }
+
+    /// Send each input in turn.
+    pub fn run(&mut self, inputs: impl IntoIterator<Item = StoryInputEvent>) {
+        for input in inputs {
+            self.input(input);
+        }
+    }
+
+    /// The transcript so far, one entry per line.
+    pub fn transcript(&self) -> String {
+        let mut transcript = self.transcript.join("\n");
+        transcript.push('\n');
+        transcript
+    }
+
+    /// Panic unless the transcript matches the file at `path`.
+    ///
+    /// With [`UPDATE_GOLDEN_VAR`] set the file is (re)written instead.
+    pub fn assert_golden(&self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        let actual = self.transcript();
+        if std::env::var_os(UPDATE_GOLDEN_VAR).is_some() {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir).unwrap();
+            }
+            std::fs::write(path, &actual).unwrap();
+            return;
+        }
+
+        let expected = std::fs::read_to_string(path)
+            .unwrap_or_else(|e| {
+                panic!("can't read golden file {}: {} (run with {}=1 to create it)", path.display(), e, UPDATE_GOLDEN_VAR)
+            })
+            .replace("\r\n", "\n");
+        if expected == actual {
+            return;
+        }
+        let (line, expected_line, actual_line) = expected
+            .lines()
+            .map(Some)
+            .chain(std::iter::repeat(None))
+            .zip(actual.lines().map(Some).chain(std::iter::repeat(None)))
+            .enumerate()
+            .find(|(_, (e, a))| e != a)
+            .map(|(i, (e, a))| (i + 1, e.unwrap_or("<end>"), a.unwrap_or("<end>")))
+            .unwrap_or((0, "", ""));
+        panic!(
+            "transcript differs from {} at line {}\n  expected: {}\n    actual: {}\n\nfull transcript:\n{}\n(run with {}=1 to accept it)",
+            path.display(),
+            line,
+            expected_line,
+            actual_line,
+            actual,
+            UPDATE_GOLDEN_VAR
+        );
+    }
+
+    /// Step frames until the executor waits for input or stops.
+    fn settle(&mut self) {
+        for _ in 0..Self::MAX_FRAMES {
+            self.app.update();
+            self.record();
+            let executor = self.executor();
+            let busy = matches!(executor.status, ExecutionStatus::Running | ExecutionStatus::WaitingForTimer)
+                || executor.line_timer.is_some();
+            if !busy {
+                return;
+            }
+        }
+        panic!("story still running after {} frames", Self::MAX_FRAMES);
+    }
+
+    fn record(&mut self) {
+        let world = self.app.world();
+        for event in self.story.read(world.resource::<Events<StoryEvent>>()) {
+            match event.payload.as_str() {
+                "" => self.transcript.push(format!("event {}", event.id)),
+                payload => self.transcript.push(format!("event {}: {}", event.id, payload)),
+            }
+        }
+        for command in self.audio.read(world.resource::<Events<AudioCommand>>()) {
+            self.transcript.push(format!("audio {:?}", command));
+        }
+        for event in self.scene.read(world.resource::<Events<ChangeSceneEvent>>()) {
+            self.transcript.push(format!("scene {} ({}s)", event.background_path, event.duration));
+        }
+        for event in self.flow.read(world.resource::<Events<StoryFlowEvent>>()) {
+            match event {
+                StoryFlowEvent::ShowDialogue { speaker, text, portrait, .. } => {
+                    let mut line = format!("dialogue {}: {}", speaker, text);
+                    if let Some(portrait) = portrait {
+                        line.push_str(&format!(" [portrait {}]", portrait));
+                    }
+                    self.transcript.push(line);
+                }
+                StoryFlowEvent::ShowChoices { prompt, options, timeout, .. } => {
+                    let mut line = format!("choices {}", prompt).trim_end().to_string();
+                    if let Some(timeout) = timeout {
+                        line.push_str(&format!(" ({}s)", timeout));
+                    }
+                    self.transcript.push(line);
+                    for (index, option) in options.iter().enumerate() {
+                        let unavailable = if option.available { "" } else { " (unavailable)" };
+                        self.transcript.push(format!("  [{}] {}{}", index, option.text, unavailable));
+                    }
+                }
+                // A line per frame of countdown would drown out the rest.
+                StoryFlowEvent::ChoiceTimer { .. } => {}
+                StoryFlowEvent::GraphComplete { .. } => self.transcript.push("complete".to_string()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::story_graph::StoryNode;
+
+    #[test]
+    #[should_panic(expected = "transcript differs")]
+    fn test_golden_mismatch_panics() {
+        let mut graph = StoryGraph::new();
+        let end = graph.add(StoryNode::End);
+        let wait = graph.add(StoryNode::Wait { duration: 2.0, next: Some(end) });
+        graph.set_start(wait);
+
+        let mut harness = StoryHarness::new();
+        harness.start(graph);
+        assert_eq!(harness.transcript(), "> start \ncomplete\n");
+
+        let golden = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(golden.path(), "> start \n").unwrap();
+        harness.assert_golden(golden.path());
+    }
+}
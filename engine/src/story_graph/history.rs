@@ -0,0 +1,234 @@
+//! Backlog of what a blocking [`GraphExecutor`] has shown, and rewinding.
+//!
+//! Each shown line and choice is recorded in [`StoryHistory`] together with
+//! the variable and flag writes that followed it, as deltas. Rewinding to
+//! an earlier line undoes those writes newest first and re-enters the
+//! line's node, so the UI receives its `ShowDialogue` again. Lines refer to
+//! graphs and nodes by authored ids, as in a
+//! [`StorySnapshot`](super::StorySnapshot).
+//!
+//! Background executors don't record history. Starting a graph or
+//! restoring a snapshot clears it.
+
+use super::snapshot::resolve;
+use super::{
+    CallFrame, CallFrameSnapshot, ChoiceView, ExecutionStatus, GraphExecutor, Reentry, SnapshotError,
+    StoryFlags, StoryGraph, StoryVariableChanged, StoryVariables,
+};
+
+/// Where a line was shown.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryPosition {
+    pub graph_id: String,
+    /// Authored id of the dialogue node
+    pub node: String,
+    /// Suspended callers, innermost last
+    pub call_stack: Vec<CallFrameSnapshot>,
+}
+
+/// One entry of a [`StoryHistory`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum HistoryEntry {
+    /// A dialogue line, rendered as it was shown
+    Line { speaker: String, text: String, position: HistoryPosition },
+    /// A choice as it was shown; `chosen` indexes `options` once picked
+    Choice { prompt: String, options: Vec<ChoiceView>, chosen: Option<usize> },
+    /// A variable write
+    Variable(StoryVariableChanged),
+    /// A `SetFlag` write; `old` is `None` if the flag was unset
+    Flag { flag: String, old: Option<bool>, value: bool },
+}
+
+/// Everything an executor has shown since its graph started, oldest first.
+#[derive(Debug, Clone, Default)]
+pub struct StoryHistory {
+    entries: Vec<HistoryEntry>,
+}
+
+impl StoryHistory {
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    /// The backlog: every shown line, in order. Indices are the ones
+    /// [`GraphExecutor::rewind`] takes.
+    pub fn lines(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().filter_map(|entry| match entry {
+            HistoryEntry::Line { speaker, text, .. } => Some((speaker.as_str(), text.as_str())),
+            _ => None,
+        })
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Record a shown line. A line shown again without anything in between
+    /// (e.g. after a language change) replaces the previous entry.
+    pub(super) fn push_line(&mut self, speaker: String, text: String, position: HistoryPosition) {
+        if let Some(HistoryEntry::Line { position: last, .. }) = self.entries.last() {
+            if *last == position {
+                self.entries.pop();
+            }
+        }
+        self.entries.push(HistoryEntry::Line { speaker, text, position });
+    }
+
+    /// Record shown choices, replacing an identical unanswered entry.
+    pub(super) fn push_choice(&mut self, prompt: String, options: Vec<ChoiceView>) {
+        if let Some(HistoryEntry::Choice { chosen: None, .. }) = self.entries.last() {
+            self.entries.pop();
+        }
+        self.entries.push(HistoryEntry::Choice { prompt, options, chosen: None });
+    }
+
+    /// Mark the option picked for the last shown choice.
+    pub(super) fn choose(&mut self, index: usize) {
+        if let Some(HistoryEntry::Choice { chosen, .. }) = self.entries.last_mut() {
+            *chosen = Some(index);
+        }
+    }
+
+    pub(super) fn push(&mut self, entry: HistoryEntry) {
+        self.entries.push(entry);
+    }
+}
+
+impl GraphExecutor {
+    /// Where the current node is, for a history line.
+    pub(super) fn history_position(&self) -> Option<HistoryPosition> {
+        let graph = self.active_graph.as_ref()?;
+        Some(HistoryPosition {
+            graph_id: graph.id.clone(),
+            node: graph.node_key(self.current_node?),
+            call_stack: self
+                .call_stack
+                .iter()
+                .map(|frame| CallFrameSnapshot {
+                    graph_id: frame.graph.id.clone(),
+                    return_node: frame.return_node.map(|id| frame.graph.node_key(id)),
+                })
+                .collect(),
+        })
+    }
+
+    /// Go back to the `line`th line of [`GraphExecutor::history`], undoing
+    /// every variable and flag write since it was shown. The line plays as
+    /// unread, even in [`AdvanceMode::SkipRead`](super::AdvanceMode).
+    ///
+    /// `find_graph` returns the graph for an id, as for
+    /// [`GraphExecutor::restore`]. Nothing is modified if `line` is out of
+    /// range or a graph or node can't be resolved.
+    pub fn rewind(
+        &mut self,
+        line: usize,
+        mut find_graph: impl FnMut(&str) -> Option<StoryGraph>,
+        flags: &mut StoryFlags,
+        variables: &mut StoryVariables,
+    ) -> Result<(), SnapshotError> {
+        let Some((index, position)) = self
+            .history
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(i, entry)| match entry {
+                HistoryEntry::Line { position, .. } => Some((i, position)),
+                _ => None,
+            })
+            .nth(line)
+        else {
+            return Err(SnapshotError::UnknownLine(line));
+        };
+
+        let (graph, current_node) = resolve(&mut find_graph, &position.graph_id, Some(&position.node))?;
+        let call_stack = position
+            .call_stack
+            .iter()
+            .map(|frame| {
+                let (graph, return_node) = resolve(&mut find_graph, &frame.graph_id, frame.return_node.as_deref())?;
+                Ok(CallFrame { graph, return_node })
+            })
+            .collect::<Result<Vec<_>, SnapshotError>>()?;
+
+        for entry in self.history.entries.drain(index..).rev() {
+            match entry {
+                HistoryEntry::Variable(change) => match change.old {
+                    Some(old) => variables.set(&change.name, old),
+                    None => {
+                        variables.remove(&change.name);
+                    }
+                },
+                HistoryEntry::Flag { flag, old, .. } => match old {
+                    Some(old) => flags.set(&flag, old),
+                    None => {
+                        flags.0.remove(&flag);
+                    }
+                },
+                HistoryEntry::Line { .. } | HistoryEntry::Choice { .. } => {}
+            }
+        }
+
+        self.active_graph = Some(graph);
+        self.current_node = current_node;
+        self.call_stack = call_stack;
+        self.presented_choices.clear();
+        self.clear_line();
+        self.status = ExecutionStatus::Running;
+        self.reentry = Reentry::Rewind;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::story_graph::StoryNode;
+
+    #[test]
+    fn test_rewind_undoes_writes() {
+        let mut graph = StoryGraph::new();
+        graph.id = "intro".to_string();
+        let end = graph.add(StoryNode::End);
+        let line = graph.add(StoryNode::Dialogue {
+            speaker: "Guide".to_string(),
+            text: "Hello".into(),
+            portrait: None,
+            voice: None,
+            duration: None,
+            next: Some(end),
+        });
+        graph.set_start(line);
+
+        let mut executor = GraphExecutor::default();
+        executor.start(graph.clone());
+        let mut flags = StoryFlags::default();
+        let mut variables = StoryVariables::default();
+        variables.set("gold", 5);
+
+        let position = executor.history_position().unwrap();
+        executor.history.push_line("Guide".to_string(), "Hello".to_string(), position);
+        variables.set("gold", 2);
+        variables.set("met_guide", true);
+        flags.set("met_guide", true);
+        for change in variables.drain_changes().into_iter().skip(1) {
+            executor.history.push(HistoryEntry::Variable(change));
+        }
+        executor.history.push(HistoryEntry::Flag { flag: "met_guide".to_string(), old: None, value: true });
+        executor.current_node = Some(end);
+        executor.status = ExecutionStatus::Idle;
+
+        assert_eq!(
+            executor.rewind(1, |_| Some(graph.clone()), &mut flags, &mut variables),
+            Err(SnapshotError::UnknownLine(1))
+        );
+        assert_eq!(variables.get("gold"), Some(&2.into()));
+
+        executor.rewind(0, |_| Some(graph.clone()), &mut flags, &mut variables).unwrap();
+        assert_eq!(executor.current_node, Some(line));
+        assert_eq!(executor.status, ExecutionStatus::Running);
+        assert_eq!(variables.get("gold"), Some(&5.into()));
+        assert_eq!(variables.get("met_guide"), None);
+        assert!(flags.0.is_empty());
+        assert!(executor.history.entries().is_empty());
+    }
+}
@@ -0,0 +1,50 @@
+//! Story graphs available to `Call` nodes, keyed by graph id.
+//!
+//! Games fill the library from `Project::story_graphs` (see
+//! [`load_story_graphs`](crate::data::load_story_graphs)) or insert graphs
+//! built in code. Graphs are cloned into the executor when called.
+
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+use super::StoryGraph;
+use crate::data::story::StoryGraphData;
+
+/// Runtime graphs that Call nodes can enter.
+#[derive(Resource, Default, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct StoryGraphLibrary {
+    graphs: HashMap<String, StoryGraph>,
+}
+
+impl StoryGraphLibrary {
+    /// Build a library from authored graphs.
+    pub fn from_data(graphs: &[StoryGraphData]) -> Self {
+        let mut library = Self::default();
+        for data in graphs {
+            library.insert(StoryGraph::from_data(data));
+        }
+        library
+    }
+
+    /// Add or replace a graph under its `id`.
+    pub fn insert(&mut self, graph: StoryGraph) {
+        self.graphs.insert(graph.id.clone(), graph);
+    }
+
+    pub fn get(&self, id: &str) -> Option<&StoryGraph> {
+        self.graphs.get(id)
+    }
+
+    pub fn remove(&mut self, id: &str) -> Option<StoryGraph> {
+        self.graphs.remove(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.graphs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.graphs.is_empty()
+    }
+}
@@ -0,0 +1,131 @@
+//! Language selection for story text.
+//!
+//! Runtime nodes keep every translation of their text as [`LocalizedText`]
+//! and resolve it against [`CurrentLanguage`] when a line is shown, so
+//! changing language mid-conversation re-renders the waiting line or choices.
+
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+use crate::data::project::LocalizationSettings;
+use crate::data::story::LocalizedString;
+
+/// Language used for text built in code (`"...".into()`).
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// The language story text is displayed in.
+#[derive(Resource, Debug, Clone, PartialEq, Reflect)]
+#[reflect(Resource)]
+pub struct CurrentLanguage {
+    /// Language code, e.g. `fr` or `pt-BR`
+    pub language: String,
+    /// Tried in order when `language` has no translation
+    pub fallbacks: Vec<String>,
+}
+
+impl Default for CurrentLanguage {
+    fn default() -> Self {
+        Self {
+            language: DEFAULT_LANGUAGE.to_string(),
+            fallbacks: Vec::new(),
+        }
+    }
+}
+
+impl CurrentLanguage {
+    /// Start in the project's default language, falling back to it.
+    pub fn from_settings(settings: &LocalizationSettings) -> Self {
+        Self {
+            language: settings.default_language.clone(),
+            fallbacks: vec![settings.default_language.clone()],
+        }
+    }
+
+    /// Switch language, keeping the fallback chain.
+    pub fn set(&mut self, language: impl Into<String>) {
+        self.language = language.into();
+    }
+
+    /// Languages to try, in order.
+    ///
+    /// Each code is followed by its base language (`pt-BR` then `pt`), and
+    /// duplicates are dropped.
+    pub fn chain(&self) -> Vec<&str> {
+        let mut chain: Vec<&str> = Vec::new();
+        for code in std::iter::once(&self.language).chain(&self.fallbacks) {
+            let base = code.split(['-', '_']).next().unwrap_or(code);
+            for candidate in [code.as_str(), base] {
+                if !candidate.is_empty() && !chain.contains(&candidate) {
+                    chain.push(candidate);
+                }
+            }
+        }
+        chain
+    }
+}
+
+/// Text with a translation per language code.
+#[derive(Debug, Clone, Default, PartialEq, Reflect)]
+pub struct LocalizedText(pub HashMap<String, String>);
+
+impl LocalizedText {
+    /// Pick the translation for `language`.
+    ///
+    /// If nothing in the fallback chain matches, any translation is used
+    /// (the alphabetically first language, so the choice is stable).
+    pub fn resolve(&self, language: &CurrentLanguage) -> &str {
+        language
+            .chain()
+            .into_iter()
+            .find_map(|code| self.0.get(code))
+            .or_else(|| self.0.iter().min_by_key(|(code, _)| *code).map(|(_, text)| text))
+            .map_or("", String::as_str)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.values().all(String::is_empty)
+    }
+}
+
+impl From<&str> for LocalizedText {
+    fn from(text: &str) -> Self {
+        Self(HashMap::from([(DEFAULT_LANGUAGE.to_string(), text.to_string())]))
+    }
+}
+
+impl From<String> for LocalizedText {
+    fn from(text: String) -> Self {
+        Self(HashMap::from([(DEFAULT_LANGUAGE.to_string(), text)]))
+    }
+}
+
+impl From<&LocalizedString> for LocalizedText {
+    fn from(text: &LocalizedString) -> Self {
+        Self(text.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fallback_chain() {
+        let language = CurrentLanguage {
+            language: "pt-BR".to_string(),
+            fallbacks: vec!["es".to_string(), "en".to_string()],
+        };
+        assert_eq!(language.chain(), vec!["pt-BR", "pt", "es", "en"]);
+
+        let text = LocalizedText(HashMap::from([
+            ("en".to_string(), "Hello".to_string()),
+            ("pt".to_string(), "Olá".to_string()),
+        ]));
+        assert_eq!(text.resolve(&language), "Olá");
+
+        let mut language = CurrentLanguage::default();
+        language.set("jp");
+        assert_eq!(text.resolve(&language), "Hello");
+        assert_eq!(LocalizedText::default().resolve(&language), "");
+    }
+}
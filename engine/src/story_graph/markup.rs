@@ -0,0 +1,381 @@
+//! Inline markup for story text.
+//!
+//! Dialogue text may contain:
+//!
+//! - `{name}`: the value of a story variable
+//! - `{item:id}`, `{npc:id}`, `{enemy:id}`, `{tower:id}`, `{quest:id}`: a
+//!   database row's display name
+//! - `[color=red]...[/color]` (named or `#rrggbb`), `[speed=2]...[/speed]`,
+//!   `[shake]...[/shake]`: styled runs
+//! - `[pause=0.5]`: a pause in seconds
+//!
+//! `{{`, `}}`, `[[` and `]]` produce literal braces and brackets. Text is
+//! parsed into [`MarkupToken`]s (validated by
+//! [`StoryGraphData::validate`](crate::data::story::StoryGraphData::validate))
+//! and rendered into [`MarkupSpan`]s for the dialogue typewriter.
+
+use bevy::prelude::*;
+use thiserror::Error;
+
+use super::{CurrentLanguage, LocalizedText, StoryVariables};
+use crate::data::Database;
+
+/// Database tables `{table:id}` can look up.
+pub const LOOKUP_TABLES: [&str; 5] = ["item", "npc", "enemy", "tower", "quest"];
+
+/// A markup syntax error.
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error("{message} (at character {offset})")]
+pub struct MarkupError {
+    /// Character offset into the source text
+    pub offset: usize,
+    pub message: String,
+}
+
+/// Styling tags that wrap a run of text.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MarkupTag {
+    Color(Color),
+    /// Typing speed multiplier (2.0 types twice as fast)
+    Speed(f32),
+    Shake,
+}
+
+impl MarkupTag {
+    fn name(&self) -> &'static str {
+        match self {
+            MarkupTag::Color(_) => "color",
+            MarkupTag::Speed(_) => "speed",
+            MarkupTag::Shake => "shake",
+        }
+    }
+}
+
+/// Parsed markup, before variables are filled in.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MarkupToken {
+    Text(String),
+    /// `{name}`
+    Variable(String),
+    /// `{table:id}`
+    Lookup { table: String, id: String },
+    Open(MarkupTag),
+    /// Closing tag name (`color`, `speed` or `shake`)
+    Close(String),
+    Pause(f32),
+}
+
+/// Style applied to a run of rendered text.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpanStyle {
+    pub color: Option<Color>,
+    pub speed: f32,
+    pub shake: bool,
+}
+
+impl Default for SpanStyle {
+    fn default() -> Self {
+        Self { color: None, speed: 1.0, shake: false }
+    }
+}
+
+/// Rendered output consumed by dialogue UIs.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MarkupSpan {
+    Text { text: String, style: SpanStyle },
+    /// Stop typing for this many seconds
+    Pause(f32),
+}
+
+/// Where `{...}` placeholders get their values.
+pub struct MarkupContext<'a> {
+    pub variables: &'a StoryVariables,
+    pub database: Option<&'a Database>,
+    pub language: &'a CurrentLanguage,
+}
+
+/// Parse markup into tokens, checking that tags are known and balanced.
+pub fn parse(text: &str) -> Result<Vec<MarkupToken>, MarkupError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut literal = String::new();
+    let mut open: Vec<(String, usize)> = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let doubled = chars.get(i + 1) == Some(&c);
+        match c {
+            '{' | '[' | '}' | ']' if doubled => {
+                literal.push(c);
+                i += 2;
+            }
+            '}' | ']' => return Err(error(i, format!("unmatched '{}'", c))),
+            '{' | '[' => {
+                let close = if c == '{' { '}' } else { ']' };
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|&ch| ch == close)
+                    .map(|p| i + 1 + p)
+                    .ok_or_else(|| error(i, format!("unclosed '{}'", c)))?;
+                let body: String = chars[i + 1..end].iter().collect();
+                let body = body.trim();
+
+                if !literal.is_empty() {
+                    tokens.push(MarkupToken::Text(std::mem::take(&mut literal)));
+                }
+                let token = if c == '{' {
+                    parse_placeholder(body, i)?
+                } else {
+                    parse_tag(body, i)?
+                };
+                match &token {
+                    MarkupToken::Open(tag) => open.push((tag.name().to_string(), i)),
+                    MarkupToken::Close(name) => match open.pop() {
+                        Some((opened, _)) if opened == *name => {}
+                        Some((opened, _)) => {
+                            return Err(error(i, format!("[/{}] closes [{}]", name, opened)))
+                        }
+                        None => return Err(error(i, format!("[/{}] has no opening tag", name))),
+                    },
+                    _ => {}
+                }
+                tokens.push(token);
+                i = end + 1;
+            }
+            _ => {
+                literal.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    if let Some((name, offset)) = open.pop() {
+        return Err(error(offset, format!("[{}] is never closed", name)));
+    }
+    if !literal.is_empty() {
+        tokens.push(MarkupToken::Text(literal));
+    }
+    Ok(tokens)
+}
+
+/// Render markup into styled spans, filling in placeholders.
+pub fn render(text: &str, ctx: &MarkupContext) -> Result<Vec<MarkupSpan>, MarkupError> {
+    let mut spans: Vec<MarkupSpan> = Vec::new();
+    let mut styles = vec![SpanStyle::default()];
+
+    for token in parse(text)? {
+        let style = *styles.last().unwrap();
+        let text = match token {
+            MarkupToken::Text(text) => text,
+            MarkupToken::Variable(name) => match ctx.variables.get(&name) {
+                Some(value) => value.to_string(),
+                None => {
+                    warn!("Markup: story variable '{}' is not set", name);
+                    String::new()
+                }
+            },
+            MarkupToken::Lookup { table, id } => lookup(ctx, &table, &id),
+            MarkupToken::Open(tag) => {
+                let mut next = style;
+                match tag {
+                    MarkupTag::Color(color) => next.color = Some(color),
+                    MarkupTag::Speed(speed) => next.speed = speed,
+                    MarkupTag::Shake => next.shake = true,
+                }
+                styles.push(next);
+                continue;
+            }
+            MarkupToken::Close(_) => {
+                styles.pop();
+                continue;
+            }
+            MarkupToken::Pause(seconds) => {
+                spans.push(MarkupSpan::Pause(seconds));
+                continue;
+            }
+        };
+
+        // Merge with the previous run when the style is unchanged.
+        match spans.last_mut() {
+            Some(MarkupSpan::Text { text: previous, style: previous_style }) if *previous_style == style => {
+                previous.push_str(&text);
+            }
+            _ if text.is_empty() => {}
+            _ => spans.push(MarkupSpan::Text { text, style }),
+        }
+    }
+
+    Ok(spans)
+}
+
+/// The text of spans without styling or pauses.
+pub fn plain_text(spans: &[MarkupSpan]) -> String {
+    spans
+        .iter()
+        .filter_map(|span| match span {
+            MarkupSpan::Text { text, .. } => Some(text.as_str()),
+            MarkupSpan::Pause(_) => None,
+        })
+        .collect()
+}
+
+/// Render `text`, falling back to the raw text if the markup is malformed.
+pub fn render_or_raw(text: &str, ctx: &MarkupContext) -> Vec<MarkupSpan> {
+    render(text, ctx).unwrap_or_else(|e| {
+        warn!("Malformed story markup in {:?}: {}", text, e);
+        vec![MarkupSpan::Text { text: text.to_string(), style: SpanStyle::default() }]
+    })
+}
+
+fn error(offset: usize, message: String) -> MarkupError {
+    MarkupError { offset, message }
+}
+
+fn parse_placeholder(body: &str, offset: usize) -> Result<MarkupToken, MarkupError> {
+    if body.is_empty() {
+        return Err(error(offset, "empty placeholder '{}'".to_string()));
+    }
+    match body.split_once(':') {
+        Some((table, id)) => {
+            let (table, id) = (table.trim(), id.trim());
+            if !LOOKUP_TABLES.contains(&table) {
+                return Err(error(offset, format!("unknown lookup table '{}'", table)));
+            }
+            if id.is_empty() {
+                return Err(error(offset, format!("missing id in '{{{}:}}'", table)));
+            }
+            Ok(MarkupToken::Lookup { table: table.to_string(), id: id.to_string() })
+        }
+        None => Ok(MarkupToken::Variable(body.to_string())),
+    }
+}
+
+fn parse_tag(body: &str, offset: usize) -> Result<MarkupToken, MarkupError> {
+    if let Some(name) = body.strip_prefix('/') {
+        return match name.trim() {
+            name @ ("color" | "speed" | "shake") => Ok(MarkupToken::Close(name.to_string())),
+            name => Err(error(offset, format!("unknown tag '[/{}]'", name))),
+        };
+    }
+
+    let (name, value) = match body.split_once('=') {
+        Some((name, value)) => (name.trim(), Some(value.trim())),
+        None => (body, None),
+    };
+    let number = |value: Option<&str>| -> Result<f32, MarkupError> {
+        let value = value.ok_or_else(|| error(offset, format!("[{}] needs a value", name)))?;
+        match value.parse::<f32>() {
+            Ok(n) if n >= 0.0 && n.is_finite() => Ok(n),
+            _ => Err(error(offset, format!("invalid number '{}' in [{}]", value, name))),
+        }
+    };
+
+    match name {
+        "color" => {
+            let value = value.ok_or_else(|| error(offset, "[color] needs a value".to_string()))?;
+            parse_color(value)
+                .map(|c| MarkupToken::Open(MarkupTag::Color(c)))
+                .ok_or_else(|| error(offset, format!("invalid color '{}'", value)))
+        }
+        "speed" => match number(value)? {
+            0.0 => Err(error(offset, "[speed] must be greater than 0".to_string())),
+            speed => Ok(MarkupToken::Open(MarkupTag::Speed(speed))),
+        },
+        "pause" => number(value).map(MarkupToken::Pause),
+        "shake" if value.is_none() => Ok(MarkupToken::Open(MarkupTag::Shake)),
+        "shake" => Err(error(offset, "[shake] takes no value".to_string())),
+        _ => Err(error(offset, format!("unknown tag '[{}]'", name))),
+    }
+}
+
+fn parse_color(value: &str) -> Option<Color> {
+    let color = match value.to_lowercase().as_str() {
+        "white" => Color::WHITE,
+        "black" => Color::BLACK,
+        "red" => Color::srgb(1.0, 0.2, 0.2),
+        "green" => Color::srgb(0.2, 1.0, 0.2),
+        "blue" => Color::srgb(0.3, 0.5, 1.0),
+        "yellow" => Color::srgb(1.0, 1.0, 0.0),
+        "orange" => Color::srgb(1.0, 0.6, 0.0),
+        "purple" => Color::srgb(0.7, 0.3, 1.0),
+        "cyan" => Color::srgb(0.0, 1.0, 1.0),
+        "gray" | "grey" => Color::srgb(0.5, 0.5, 0.5),
+        hex if hex.starts_with('#') => Srgba::hex(hex).ok()?.into(),
+        _ => return None,
+    };
+    Some(color)
+}
+
+fn lookup(ctx: &MarkupContext, table: &str, id: &str) -> String {
+    let name = ctx.database.and_then(|db| match table {
+        "item" => db.find_item(id).map(|r| &r.name),
+        "npc" => db.find_npc(id).map(|r| &r.name),
+        "enemy" => db.find_enemy(id).map(|r| &r.name),
+        "tower" => db.find_tower(id).map(|r| &r.name),
+        "quest" => db.find_quest(id).map(|r| &r.name),
+        _ => None,
+    });
+    match name {
+        Some(name) => LocalizedText::from(name).resolve(ctx.language).to_string(),
+        None => {
+            warn!("Markup: no {} '{}' in the database", table, id);
+            id.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::database::ItemRow;
+
+    fn render_plain(text: &str, variables: &StoryVariables, database: Option<&Database>) -> Vec<MarkupSpan> {
+        let language = CurrentLanguage::default();
+        render(text, &MarkupContext { variables, database, language: &language }).unwrap()
+    }
+
+    #[test]
+    fn test_interpolation_and_lookup() {
+        let mut variables = StoryVariables::default();
+        variables.set("gold", 12);
+        let mut database = Database::new();
+        database.items.push(ItemRow::new("herb", "Healing Herb"));
+
+        let spans = render_plain("{{{gold}}} gold for a {item:herb}", &variables, Some(&database));
+        assert_eq!(plain_text(&spans), "{12} gold for a Healing Herb");
+        assert_eq!(spans.len(), 1);
+    }
+
+    #[test]
+    fn test_tags_produce_spans() {
+        let spans = render_plain(
+            "Wait[pause=0.5] [color=#ff0000][shake]now[/shake][/color]!",
+            &StoryVariables::default(),
+            None,
+        );
+        assert_eq!(spans.len(), 5);
+        assert_eq!(spans[1], MarkupSpan::Pause(0.5));
+        let MarkupSpan::Text { text, style } = &spans[3] else { panic!() };
+        assert_eq!(text, "now");
+        assert!(style.shake);
+        assert_eq!(style.color, Some(Color::srgb(1.0, 0.0, 0.0)));
+        assert_eq!(plain_text(&spans), "Wait now!");
+    }
+
+    #[test]
+    fn test_malformed_markup() {
+        for (text, offset) in [
+            ("Hello {name", 6),
+            ("[color=red]never closed", 0),
+            ("[shake]crossed[/color]", 14),
+            ("[wobble]x[/wobble]", 0),
+            ("[speed=fast]x[/speed]", 0),
+            ("{weapon:sword}", 0),
+            ("stray ]", 6),
+        ] {
+            let err = parse(text).unwrap_err();
+            assert_eq!(err.offset, offset, "{}: {}", text, err);
+        }
+    }
+}
@@ -3,11 +3,41 @@
 //! Replaces linear dialogue queues with a directed graph of nodes.
 //! Supports branching logic, events, and complex narrative flow.
 
+use bevy::audio::{Decodable, Source};
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use crate::audio::AudioCommand;
+use crate::data::{AssetIndex, Database};
+use crate::data::story::{StoryCondition, StoryEffect};
 use crate::scene::ChangeSceneEvent;
 
+pub mod cinematic;
+pub mod effects;
+pub mod harness;
+pub mod history;
+pub mod library;
+pub mod localization;
+pub mod markup;
+pub mod playback;
+pub mod quests;
+pub mod reload;
+pub mod snapshot;
+pub mod variables;
+
+pub use cinematic::{CameraTween, CinematicCommand, Easing};
+pub use harness::StoryHarness;
+pub use history::{HistoryEntry, HistoryPosition, StoryHistory};
+pub use library::StoryGraphLibrary;
+pub use localization::{CurrentLanguage, LocalizedText};
+pub use markup::{MarkupError, MarkupSpan, SpanStyle};
+pub use playback::{AdvanceMode, StoryReadSet};
+pub use quests::{ObjectiveProgress, QuestEvent, QuestInventory, QuestLog, QuestProgress};
+pub use reload::{StoryGraphWatcher, StoryReloadEvent};
+pub use snapshot::{CallFrameSnapshot, SnapshotError, StorySnapshot};
+pub use variables::{StoryValue, StoryVariableChanged, StoryVariables};
+
 /// Unique identifier for a node in the graph.
 pub type NodeId = usize;
 
@@ -17,15 +47,23 @@ pub enum StoryNode {
     /// Show dialogue and wait for user confirmation.
     Dialogue {
         speaker: String,
-        text: String,
+        text: LocalizedText,
         portrait: Option<String>,
+        /// Voice clip, as an [`AssetIndex`] audio id or a path
+        voice: Option<String>,
+        /// Auto-advance after this many seconds (else after the voice clip)
+        duration: Option<f32>,
         next: Option<NodeId>,
     },
     /// Present a set of choices to the player.
     Choice {
         speaker: String,
-        prompt: String,
+        prompt: LocalizedText,
         options: Vec<GraphChoice>,
+        /// Seconds to choose before `default_option` is picked
+        timeout: Option<f32>,
+        /// Index of the option picked on timeout (else the first available)
+        default_option: Option<usize>,
     },
     /// Play a sound effect or music track.
     Audio {
@@ -44,6 +82,12 @@ pub enum StoryNode {
         if_true: Option<NodeId>,
         if_false: Option<NodeId>,
     },
+    /// Conditional branch based on a typed story variable.
+    Conditional {
+        condition: StoryCondition,
+        if_true: Option<NodeId>,
+        if_false: Option<NodeId>,
+    },
     /// Set or unset a story flag.
     SetFlag {
         flag: String,
@@ -55,6 +99,26 @@ pub enum StoryNode {
         duration: f32,
         next: Option<NodeId>,
     },
+    /// Tween the main camera, waiting for the move to finish.
+    Camera {
+        position: Vec3,
+        zoom: f32,
+        angle: f32,
+        duration: f32,
+        easing: Easing,
+        next: Option<NodeId>,
+    },
+    /// Pause or scale gameplay time.
+    TimeControl {
+        pause_gameplay: bool,
+        time_scale: f32,
+        next: Option<NodeId>,
+    },
+    /// Apply story effects (variables, items, quests).
+    Effect {
+        effects: Vec<StoryEffect>,
+        next: Option<NodeId>,
+    },
     /// A generic event trigger for game-specific logic.
     Event {
         event_id: String,
@@ -65,32 +129,69 @@ pub enum StoryNode {
     Start {
         next: Option<NodeId>,
     },
-    /// End execution of the current graph.
+    /// Run another graph from the [`StoryGraphLibrary`], then continue at `next`.
+    Call {
+        graph_id: String,
+        /// Authored id of the node to enter at (defaults to the graph's start)
+        entry_node: Option<String>,
+        next: Option<NodeId>,
+    },
+    /// Resume the calling graph (same as `End` when there is no caller).
+    Return,
+    /// End execution of the current graph, returning to the caller if any.
     End,
 }
 
+/// Maximum nesting of Call nodes before further calls are skipped.
+pub const MAX_CALL_DEPTH: usize = 32;
+
 /// A choice option within a Choice node.
-#[derive(Debug, Clone, Reflect)]
+#[derive(Debug, Clone, Default, Reflect)]
 pub struct GraphChoice {
-    pub text: String,
+    /// Stable option id, as authored.
+    pub id: String,
+    pub text: LocalizedText,
     pub next: Option<NodeId>,
     pub flag_required: Option<String>,
+    /// All must pass for the option to be selectable.
+    pub conditions: Vec<StoryCondition>,
+    /// Applied in order when the option is selected.
+    pub effects: Vec<StoryEffect>,
+    /// Show greyed-out instead of hiding when conditions fail.
+    pub show_when_unavailable: bool,
+}
+
+impl GraphChoice {
+    /// Whether `flag_required` is set and every condition passes.
+    pub fn is_available(&self, flags: &StoryFlags, variables: &StoryVariables) -> bool {
+        self.flag_required.as_ref().is_none_or(|flag| flags.get(flag))
+            && self.conditions.iter().all(|c| variables.evaluate(c))
+    }
 }
 
 /// The graph container holding all nodes.
 #[derive(Resource, Default, Clone, Reflect)]
 #[reflect(Resource)]
 pub struct StoryGraph {
+    /// Authored graph id (empty for graphs built in code).
+    pub id: String,
     pub nodes: HashMap<NodeId, StoryNode>,
     pub start_node: Option<NodeId>,
+    /// Initial variable values, seeded into [`StoryVariables`] on start.
+    pub variables: HashMap<String, StoryValue>,
+    /// Authored string id of each node, when loaded from data.
+    pub node_keys: HashMap<NodeId, String>,
     next_id: usize,
 }
 
 impl StoryGraph {
     pub fn new() -> Self {
         Self {
+            id: String::new(),
             nodes: HashMap::new(),
             start_node: None,
+            variables: HashMap::new(),
+            node_keys: HashMap::new(),
             next_id: 0,
         }
     }
@@ -105,10 +206,27 @@ impl StoryGraph {
     pub fn set_start(&mut self, id: NodeId) {
         self.start_node = Some(id);
     }
+
+    /// Stable key for a node: its authored id, or the numeric id for
+    /// graphs built in code.
+    pub fn node_key(&self, id: NodeId) -> String {
+        self.node_keys.get(&id).cloned().unwrap_or_else(|| id.to_string())
+    }
+
+    /// Inverse of [`StoryGraph::node_key`].
+    pub fn find_node(&self, key: &str) -> Option<NodeId> {
+        self.node_keys
+            .iter()
+            .find_map(|(id, k)| (k == key).then_some(*id))
+            .or_else(|| key.parse().ok().filter(|id| self.nodes.contains_key(id)))
+    }
 }
 
 /// Generic container for story flags (booleans).
-#[derive(Resource, Default, Debug, Clone, Reflect)]
+///
+/// Kept for hand-built graphs; `SetFlag` nodes also mirror into
+/// [`StoryVariables`], which authored conditions read.
+#[derive(Resource, Default, Debug, Clone, PartialEq, Reflect, Serialize, Deserialize)]
 #[reflect(Resource)]
 pub struct StoryFlags(pub HashMap<String, bool>);
 
@@ -122,7 +240,8 @@ impl StoryFlags {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Reflect, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ExecutionStatus {
     #[default]
     Idle,
@@ -132,29 +251,148 @@ pub enum ExecutionStatus {
     Paused,
 }
 
-#[derive(Resource, Default, Reflect)]
-#[reflect(Resource)]
+/// Identifies the executor a story event belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Reflect)]
+pub enum ExecutorId {
+    /// The [`GraphExecutor`] resource that runs the main conversation.
+    #[default]
+    Main,
+    /// A [`GraphExecutor`] component on this entity.
+    Entity(Entity),
+}
+
+/// How an executor interacts with the player.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Reflect)]
+pub enum ExecutorMode {
+    /// Waits for [`StoryInputEvent`]s at dialogue and choice nodes.
+    #[default]
+    Blocking,
+    /// Never waits for input: dialogue lines advance after `line_duration`
+    /// seconds and choices take the first available option. Voice lines
+    /// aren't played, so barks can't cut off the conversation's voice. Used
+    /// for ambient barks and graphs driven alongside a conversation.
+    Background { line_duration: f32 },
+}
+
+/// How an executor treats its current node the next time it processes it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+pub enum Reentry {
+    /// Entered anew: a dialogue line checks the [`StoryReadSet`], plays its
+    /// voice and starts its timers.
+    #[default]
+    New,
+    /// Shown again after a language switch, hot reload or restore: the line
+    /// keeps its read state, voice and timers.
+    Reshow,
+    /// Gone back to by a rewind: the line plays as if it were unread.
+    Rewind,
+    /// Restored before the line's voice clip had loaded: shown again like
+    /// `Reshow`, loading the clip again so its length still advances the line.
+    ReloadVoice,
+}
+
+/// Runs one story graph at a time.
+///
+/// The resource is the main (usually blocking) executor. Spawn the component
+/// on an entity to run more graphs in parallel; their events carry
+/// [`ExecutorId::Entity`].
+#[derive(Resource, Component, Default, Reflect)]
+#[reflect(Resource, Component)]
 pub struct GraphExecutor {
     // For prototype simplicity, we store the struct directly.
     pub active_graph: Option<StoryGraph>,     
     pub current_node: Option<NodeId>,
     pub status: ExecutionStatus,
     pub wait_timer: Timer,
+    /// Set by [`GraphExecutor::start`]; the graph's initial variables are
+    /// seeded on the next update.
+    pub seed_variables: bool,
+    /// Option indices shown by the last `ShowChoices`, in display order.
+    pub presented_choices: Vec<usize>,
+    /// Suspended caller graphs, innermost last.
+    pub call_stack: Vec<CallFrame>,
+    pub mode: ExecutorMode,
+    /// Auto-advance or skip lines (blocking mode only).
+    pub advance_mode: AdvanceMode,
+    /// Auto-advances the waiting dialogue line when it finishes.
+    pub line_timer: Option<Timer>,
+    /// Voice clip whose length becomes `line_timer` once it has loaded.
+    #[reflect(ignore)]
+    pub pending_voice: Option<Handle<AudioSource>>,
+    /// Whether the waiting line started a voice clip.
+    pub voice_playing: bool,
+    /// Whether the waiting line was in the [`StoryReadSet`] when it was
+    /// entered. Lines are added to the set once the player advances past them.
+    pub line_read: bool,
+    /// How the current node is processed next; reset once it has been.
+    pub reentry: Reentry,
+    /// Counts down a timed choice.
+    pub choice_timer: Option<Timer>,
+    /// Lines, choices and writes since the graph started (blocking mode only).
+    #[reflect(ignore)]
+    pub history: StoryHistory,
+}
+
+/// A suspended graph, resumed at `return_node` when its callee returns.
+#[derive(Clone, Reflect)]
+pub struct CallFrame {
+    pub graph: StoryGraph,
+    pub return_node: Option<NodeId>,
 }
 
 use crate::data::story::{StoryGraphData, StoryNodeVariant};
 
 impl GraphExecutor {
+    /// An idle executor that never waits for player input.
+    pub fn background(line_duration: f32) -> Self {
+        Self {
+            mode: ExecutorMode::Background { line_duration },
+            ..default()
+        }
+    }
+
     pub fn start(&mut self, graph: StoryGraph) {
         let start = graph.start_node;
         self.active_graph = Some(graph);
         self.current_node = start;
         self.status = ExecutionStatus::Running;
+        self.seed_variables = true;
+        self.reentry = Reentry::New;
+        self.call_stack.clear();
+        self.presented_choices.clear();
+        self.history.clear();
+        self.clear_line();
+    }
+
+    /// Arm auto-advance for a dialogue line that just started.
+    fn start_line(&mut self, duration: Option<f32>, voice: Option<String>, server: Option<&AssetServer>) {
+        self.voice_playing = voice.is_some();
+        self.line_timer = duration.map(|seconds| Timer::from_seconds(seconds, TimerMode::Once));
+        self.pending_voice = match (duration, voice, server) {
+            (None, Some(clip), Some(server)) => Some(server.load(clip)),
+            _ => None,
+        };
+    }
+
+    fn clear_line(&mut self) {
+        self.line_timer = None;
+        self.choice_timer = None;
+        self.pending_voice = None;
+        self.voice_playing = false;
+        self.line_read = false;
     }
 
     /// Helper to bridge Editor Data -> Runtime Graph
     pub fn load_from_data(&mut self, data: &StoryGraphData) {
+        self.start(StoryGraph::from_data(data));
+    }
+}
+
+impl StoryGraph {
+    /// Build a runtime graph from authored data, keeping the authored ids.
+    pub fn from_data(data: &StoryGraphData) -> Self {
         let mut graph = StoryGraph::new();
+        graph.id = data.id.clone();
         let mut id_map: HashMap<String, NodeId> = HashMap::new();
 
         // Pass 1: Allocate IDs
@@ -163,6 +401,7 @@ impl GraphExecutor {
             // We insert a placeholder to reserve the ID
             graph.add(StoryNode::End); 
             id_map.insert(node_data.id.clone(), next_id);
+            graph.node_keys.insert(next_id, node_data.id.clone());
         }
 
         // Pass 2: Overwrite with actual data
@@ -179,18 +418,29 @@ impl GraphExecutor {
                 },
                 StoryNodeVariant::Dialogue(d) => StoryNode::Dialogue {
                     speaker: d.speaker_id.clone(),
-                    text: d.text.get("en").cloned().unwrap_or_default(),
+                    text: (&d.text).into(),
                     portrait: d.portrait_id.clone(),
+                    voice: d.voice_line_id.clone(),
+                    duration: d.duration,
                     next: resolve(&d.next_node_id),
                 },
                 StoryNodeVariant::Choice(c) => StoryNode::Choice {
                     speaker: "Player".into(), // Default?
-                    prompt: c.prompt.get("en").cloned().unwrap_or_default(),
+                    prompt: (&c.prompt).into(),
                     options: c.options.iter().map(|o| GraphChoice {
-                        text: o.text.get("en").cloned().unwrap_or_default(),
-                        next: Some(id_map[&o.target_node_id]), // Choices must have targets?
-                        flag_required: None,
+                        id: o.id.clone(),
+                        text: (&o.text).into(),
+                        next: id_map.get(&o.target_node_id).copied(),
+                        flag_required: o.flag_required.clone(),
+                        conditions: o.conditions.clone(),
+                        effects: o.effects.clone(),
+                        show_when_unavailable: o.show_when_unavailable,
                     }).collect(),
+                    timeout: c.timeout,
+                    default_option: c
+                        .default_option
+                        .as_ref()
+                        .and_then(|id| c.options.iter().position(|o| &o.id == id)),
                 },
                 StoryNodeVariant::Action(a) => {
                      // For now, assume action is Event? or Lua script?
@@ -201,6 +451,16 @@ impl GraphExecutor {
                          next: resolve(&a.next_node_id),
                      }
                 },
+                StoryNodeVariant::Effect(e) => StoryNode::Effect {
+                    effects: e.effects.clone(),
+                    next: resolve(&e.next_node_id),
+                },
+                StoryNodeVariant::Call(c) => StoryNode::Call {
+                    graph_id: c.graph_id.clone(),
+                    entry_node: c.entry_node.clone(),
+                    next: resolve(&c.next_node_id),
+                },
+                StoryNodeVariant::Return(_) => StoryNode::Return,
                 StoryNodeVariant::End(e) => {
                     if let Some(scene) = &e.target_scene_id {
                         StoryNode::Scene {
@@ -212,38 +472,107 @@ impl GraphExecutor {
                         StoryNode::End
                     }
                 },
-                _ => StoryNode::End, // Unimplemented variants
+                StoryNodeVariant::Conditional(c) => StoryNode::Conditional {
+                    condition: c.condition.clone(),
+                    if_true: id_map.get(&c.true_target_node_id).copied(),
+                    if_false: id_map.get(&c.false_target_node_id).copied(),
+                },
+                StoryNodeVariant::Camera(c) => StoryNode::Camera {
+                    position: c.position.into(),
+                    zoom: c.zoom,
+                    angle: c.angle,
+                    duration: c.duration,
+                    easing: Easing::from_name(&c.easing),
+                    next: resolve(&c.next_node_id),
+                },
+                StoryNodeVariant::TimeControl(t) => StoryNode::TimeControl {
+                    pause_gameplay: t.pause_gameplay,
+                    time_scale: t.time_scale,
+                    next: resolve(&t.next_node_id),
+                },
             };
 
             graph.nodes.insert(runtime_id, node);
         }
 
-        if let Ok(start_id) = data.root_node_id.parse::<usize>() {
-             // If root_node_id happens to be numerical? unlikely. 
-             // We need to look up root node from map.
-        }
-        
         if let Some(start_id) = id_map.get(&data.root_node_id) {
             graph.set_start(*start_id);
         }
 
-        self.start(graph);
+        for (name, value) in &data.variables {
+            match StoryValue::from_json(value) {
+                Some(value) => {
+                    graph.variables.insert(name.clone(), value);
+                }
+                None => warn!("Story graph '{}': variable '{}' has unsupported value {}", data.id, name, value),
+            }
+        }
+
+        graph
     }
 }
 
+/// A choice option as presented to the UI.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChoiceView {
+    /// Stable option id, as authored.
+    pub id: String,
+    pub text: String,
+    /// `false` when conditions fail; the UI should grey the option out.
+    pub available: bool,
+}
+
 /// Events sent FROM the Executor TO the UI/Game
 #[derive(Event, Debug, Clone)]
 pub enum StoryFlowEvent {
-    ShowDialogue { speaker: String, text: String, portrait: Option<String> },
-    ShowChoices { prompt: String, options: Vec<String> }, // Only send text to UI
-    GraphComplete,
+    /// `text` is the line with markup rendered and tags stripped; `spans`
+    /// keeps the styling and pauses for typewriter-style UIs.
+    ShowDialogue {
+        executor: ExecutorId,
+        speaker: String,
+        text: String,
+        spans: Vec<MarkupSpan>,
+        portrait: Option<String>,
+    },
+    /// Options whose conditions fail are omitted unless marked
+    /// `show_when_unavailable`. `timeout` is set for timed choices.
+    ShowChoices { executor: ExecutorId, prompt: String, options: Vec<ChoiceView>, timeout: Option<f32> },
+    /// Sent every frame while a timed choice waits, for drawing a timer bar.
+    ChoiceTimer { executor: ExecutorId, remaining: f32, duration: f32 },
+    GraphComplete { executor: ExecutorId },
+}
+
+impl StoryFlowEvent {
+    /// The executor that sent this event.
+    pub fn executor(&self) -> ExecutorId {
+        match self {
+            StoryFlowEvent::ShowDialogue { executor, .. }
+            | StoryFlowEvent::ShowChoices { executor, .. }
+            | StoryFlowEvent::ChoiceTimer { executor, .. }
+            | StoryFlowEvent::GraphComplete { executor } => *executor,
+        }
+    }
 }
 
 /// Events sent FROM the UI/Game TO the Executor
 #[derive(Event, Debug, Clone)]
 pub enum StoryInputEvent {
-    Advance,
-    SelectChoice(usize),
+    Advance { executor: ExecutorId },
+    /// `index` is into the options of the last `ShowChoices`.
+    SelectChoice { executor: ExecutorId, index: usize },
+    /// Go back to a line of [`GraphExecutor::history`] (see [`GraphExecutor::rewind`]).
+    Rewind { executor: ExecutorId, line: usize },
+}
+
+impl StoryInputEvent {
+    /// The executor this input is for.
+    pub fn executor(&self) -> ExecutorId {
+        match self {
+            StoryInputEvent::Advance { executor }
+            | StoryInputEvent::SelectChoice { executor, .. }
+            | StoryInputEvent::Rewind { executor, .. } => *executor,
+        }
+    }
 }
 
 #[derive(Event)]
@@ -260,45 +589,172 @@ impl Plugin for StoryGraphPlugin {
            .register_type::<StoryNode>()
            .register_type::<GraphChoice>()
            .register_type::<StoryFlags>()
+           .register_type::<StoryVariables>()
            .register_type::<ExecutionStatus>()
+           .register_type::<ExecutorId>()
+           .register_type::<ExecutorMode>()
+           .register_type::<AdvanceMode>()
            .register_type::<GraphExecutor>()
+           .register_type::<StoryGraphLibrary>()
            .init_resource::<GraphExecutor>()
+           .init_resource::<StoryGraphLibrary>()
+           .register_type::<CurrentLanguage>()
+           .init_resource::<CurrentLanguage>()
            .init_resource::<StoryFlags>()
+           .init_resource::<StoryVariables>()
+           .init_resource::<StoryReadSet>()
+           .init_resource::<QuestLog>()
+           .init_resource::<QuestInventory>()
+           .add_event::<StoryVariableChanged>()
            .add_event::<StoryEvent>()
            .add_event::<StoryFlowEvent>()
            .add_event::<StoryInputEvent>()
-           .add_systems(Update, execute_graph);
+           .add_event::<CinematicCommand>()
+           .add_event::<StoryReloadEvent>()
+           .add_event::<QuestEvent>()
+           .add_systems(Update, (
+               reload::reload_changed_graphs,
+               execute_graph,
+               quests::update_quests,
+               variables::emit_variable_changes,
+               cinematic::handle_cinematic_commands,
+               cinematic::update_camera_tweens,
+           ).chain());
     }
 }
 
+/// Event writers the executor emits into while processing nodes.
+#[derive(SystemParam)]
+pub struct StoryOutputs<'w> {
+    pub flow: EventWriter<'w, StoryFlowEvent>,
+    pub audio: EventWriter<'w, AudioCommand>,
+    pub scene: EventWriter<'w, ChangeSceneEvent>,
+    pub story: EventWriter<'w, StoryEvent>,
+    pub cinematic: EventWriter<'w, CinematicCommand>,
+}
+
 enum NodeAction {
-    WaitInput,
+    /// Wait on a dialogue line, auto-advancing after `duration` or the voice clip.
+    WaitInput { duration: Option<f32>, voice: Option<String>, speaker: String, text: String },
+    /// Enter another graph, resuming at the node's `next` afterwards.
+    Call { graph_id: String, entry_node: Option<String> },
+    /// Wait for a choice among the `presented` option indices.
+    WaitChoice { presented: Vec<usize>, prompt: String, options: Vec<ChoiceView>, timeout: Option<f32> },
     WaitTimer(f32),
     Advance,
     Jump(NodeId),
     End,
 }
 
+/// Shared story state that every executor reads and writes.
+#[derive(SystemParam)]
+struct StoryContext<'w> {
+    flags: ResMut<'w, StoryFlags>,
+    variables: ResMut<'w, StoryVariables>,
+    read: ResMut<'w, StoryReadSet>,
+    library: Res<'w, StoryGraphLibrary>,
+    language: Res<'w, CurrentLanguage>,
+    database: Option<Res<'w, Database>>,
+    asset_index: Option<Res<'w, AssetIndex>>,
+    asset_server: Option<Res<'w, AssetServer>>,
+    audio_clips: Option<Res<'w, Assets<AudioSource>>>,
+}
+
+/// Runs the main executor resource, then every executor component.
 fn execute_graph(
-    mut executor: ResMut<GraphExecutor>,
-    mut flags: ResMut<StoryFlags>,
-    mut audio_events: EventWriter<AudioCommand>,
-    mut scene_events: EventWriter<ChangeSceneEvent>,
-    mut flow_events: EventWriter<StoryFlowEvent>,
-    mut story_events: EventWriter<StoryEvent>,
+    mut main: ResMut<GraphExecutor>,
+    mut instances: Query<(Entity, &mut GraphExecutor)>,
+    mut ctx: StoryContext,
+    mut outputs: StoryOutputs,
     mut input_events: EventReader<StoryInputEvent>,
     time: Res<Time>,
+    real_time: Res<Time<Real>>,
+) {
+    let inputs: Vec<StoryInputEvent> = input_events.read().cloned().collect();
+    let deltas = (time.delta(), real_time.delta());
+
+    run_executor(ExecutorId::Main, &mut main, &inputs, &mut ctx, &mut outputs, deltas);
+    for (entity, mut executor) in instances.iter_mut() {
+        run_executor(ExecutorId::Entity(entity), &mut executor, &inputs, &mut ctx, &mut outputs, deltas);
+    }
+}
+
+fn run_executor(
+    id: ExecutorId,
+    executor: &mut GraphExecutor,
+    inputs: &[StoryInputEvent],
+    ctx: &mut StoryContext,
+    outputs: &mut StoryOutputs,
+    // Gameplay (virtual) and real time since the last update.
+    (delta, real_delta): (std::time::Duration, std::time::Duration),
 ) {
+    if executor.seed_variables {
+        executor.seed_variables = false;
+        if let Some(graph) = &executor.active_graph {
+            ctx.variables.seed(&graph.variables);
+        }
+    }
+    // Variable writes from here on are this executor's, for its history.
+    let mut recorded = ctx.variables.pending_changes().len();
+
     // 1. Handle Input (if waiting)
+    for event in inputs.iter().filter(|e| e.executor() == id) {
+        if let StoryInputEvent::Rewind { line, .. } = event {
+            rewind(executor, *line, ctx, outputs);
+            recorded = ctx.variables.pending_changes().len();
+            continue;
+        }
+        if executor.status != ExecutionStatus::WaitingForInput {
+            continue;
+        }
+        match event {
+            StoryInputEvent::Advance { .. } => leave_line(executor, &mut ctx.read, outputs),
+            StoryInputEvent::SelectChoice { index, .. } => {
+                handle_choice_selection(executor, *index, &ctx.flags, &mut ctx.variables, outputs);
+                // handle_choice_selection sets status to Running
+            }
+            StoryInputEvent::Rewind { .. } => {}
+        }
+    }
+
+    // Re-show the waiting line or choices in the new language.
+    if executor.status == ExecutionStatus::WaitingForInput
+        && ctx.language.is_changed()
+        && !ctx.language.is_added()
+    {
+        executor.status = ExecutionStatus::Running;
+        executor.reentry = Reentry::Reshow;
+    }
+
+    // Auto-advance timed or voiced lines. Like voice clips, line and choice
+    // countdowns run in real time, so they go on while gameplay is paused.
     if executor.status == ExecutionStatus::WaitingForInput {
-        for event in input_events.read() {
-            match event {
-                StoryInputEvent::Advance => {
-                    executor.status = ExecutionStatus::Running;
-                }
-                StoryInputEvent::SelectChoice(index) => {
-                    handle_choice_selection(&mut executor, *index);
-                    // handle_choice_selection sets status to Running
+        if let (Some(handle), Some(clips)) = (&executor.pending_voice, &ctx.audio_clips) {
+            if let Some(clip) = clips.get(handle) {
+                // Some formats don't report a length; those lines wait for input.
+                executor.line_timer = clip.decoder().total_duration().map(|d| Timer::new(d, TimerMode::Once));
+                executor.pending_voice = None;
+            }
+        }
+        if executor.line_timer.as_mut().is_some_and(|timer| timer.tick(real_delta).finished()) {
+            leave_line(executor, &mut ctx.read, outputs);
+        }
+    }
+
+    // Count down timed choices, taking the default when time runs out.
+    if executor.status == ExecutionStatus::WaitingForInput {
+        if let Some(timer) = &mut executor.choice_timer {
+            timer.tick(real_delta);
+            outputs.flow.send(StoryFlowEvent::ChoiceTimer {
+                executor: id,
+                remaining: timer.remaining_secs(),
+                duration: timer.duration().as_secs_f32(),
+            });
+            if timer.finished() {
+                executor.choice_timer = None;
+                match default_choice(executor, &ctx.flags, &ctx.variables) {
+                    Some(index) => handle_choice_selection(executor, index, &ctx.flags, &mut ctx.variables, outputs),
+                    None => warn!("Story: timed choice ran out with no available option"),
                 }
             }
         }
@@ -306,10 +762,17 @@ fn execute_graph(
 
     // 2. Handle Timer (if waiting)
     if executor.status == ExecutionStatus::WaitingForTimer {
-        executor.wait_timer.tick(time.delta());
+        // Camera moves tween in real time, so a cutscene that pauses gameplay
+        // still finishes them; other waits follow gameplay time.
+        let camera = executor
+            .active_graph
+            .as_ref()
+            .zip(executor.current_node)
+            .is_some_and(|(graph, id)| matches!(graph.nodes.get(&id), Some(StoryNode::Camera { .. })));
+        executor.wait_timer.tick(if camera { real_delta } else { delta });
         if executor.wait_timer.finished() {
             executor.status = ExecutionStatus::Running;
-            advance_node(&mut executor);
+            advance_node(executor);
         }
     }
 
@@ -318,51 +781,235 @@ fn execute_graph(
     let mut loops = 0;
     while executor.status == ExecutionStatus::Running && loops < 100 {
         loops += 1;
+        let reentry = std::mem::take(&mut executor.reentry);
 
         // In a real asset system we'd use Handle and Assets<StoryGraph>
         
         if let Some(graph) = &executor.active_graph {
             if let Some(node_id) = executor.current_node {
                 if let Some(node) = graph.nodes.get(&node_id) {
-                    let action = process_node(
-                        node,
-                        &mut flags,
-                        &mut flow_events,
-                        &mut audio_events,
-                        &mut scene_events,
-                        &mut story_events
-                    );
+                    let flag_before = match node {
+                        StoryNode::SetFlag { flag, .. } => Some((flag.clone(), ctx.flags.0.get(flag).copied())),
+                        _ => None,
+                    };
+                    let line_key = matches!(node, StoryNode::Dialogue { .. })
+                        .then(|| (graph.id.clone(), graph.node_key(node_id)));
+                    let action = process_node(node, id, ctx, outputs);
+
+                    if executor.mode == ExecutorMode::Blocking {
+                        record_variables(executor, &ctx.variables, &mut recorded);
+                        if let Some((flag, old)) = flag_before {
+                            let value = ctx.flags.get(&flag);
+                            if old != Some(value) {
+                                executor.history.push(HistoryEntry::Flag { flag, old, value });
+                            }
+                        }
+                    }
 
                     match action {
-                        NodeAction::WaitInput => {
+                        NodeAction::WaitInput { duration, voice, speaker, text } => match executor.mode {
+                            ExecutorMode::Blocking => {
+                                executor.status = ExecutionStatus::WaitingForInput;
+                                match reentry {
+                                    Reentry::Reshow => {}
+                                    Reentry::ReloadVoice => {
+                                        executor.pending_voice = voice
+                                            .zip(ctx.asset_server.as_deref())
+                                            .map(|(clip, server)| server.load(clip));
+                                    }
+                                    Reentry::New | Reentry::Rewind => {
+                                        let read = reentry == Reentry::New
+                                            && line_key.is_some_and(|(graph_id, node)| ctx.read.contains(&graph_id, &node));
+                                        let mode = executor.advance_mode;
+                                        // Skipped lines don't start their voice at all.
+                                        let voice = voice.filter(|_| !mode.skips(read));
+                                        if let Some(clip) = &voice {
+                                            outputs.audio.send(AudioCommand::PlayVoice { clip: clip.clone() });
+                                        }
+                                        let duration = mode.line_duration(duration, voice.is_some(), &text, read);
+                                        executor.start_line(duration, voice, ctx.asset_server.as_deref());
+                                        executor.line_read = read;
+                                    }
+                                }
+                                if let Some(position) = executor.history_position() {
+                                    executor.history.push_line(speaker, text, position);
+                                }
+                            }
+                            ExecutorMode::Background { line_duration } => {
+                                executor.status = ExecutionStatus::WaitingForTimer;
+                                executor.wait_timer =
+                                    Timer::from_seconds(duration.unwrap_or(line_duration), TimerMode::Once);
+                            }
+                        },
+                        NodeAction::WaitChoice { presented, prompt, options, timeout } => {
                             executor.status = ExecutionStatus::WaitingForInput;
+                            executor.presented_choices = presented;
+                            if executor.mode == ExecutorMode::Blocking {
+                                executor.history.push_choice(prompt, options);
+                                // Re-showing the choice (e.g. in a new language) keeps the countdown.
+                                if executor.choice_timer.is_none() {
+                                    executor.choice_timer =
+                                        timeout.map(|seconds| Timer::from_seconds(seconds, TimerMode::Once));
+                                }
+                            } else {
+                                // Nobody is there to choose; take the first available option.
+                                match first_available_choice(executor, &ctx.flags, &ctx.variables) {
+                                    Some(index) => {
+                                        handle_choice_selection(executor, index, &ctx.flags, &mut ctx.variables, outputs);
+                                    }
+                                    None => {
+                                        warn!("Story: background graph has no available choice; ending");
+                                        executor.status = ExecutionStatus::Idle;
+                                        outputs.flow.send(StoryFlowEvent::GraphComplete { executor: id });
+                                    }
+                                }
+                            }
                         }
                         NodeAction::WaitTimer(duration) => {
                             executor.status = ExecutionStatus::WaitingForTimer;
                             executor.wait_timer = Timer::from_seconds(duration, TimerMode::Once);
                         }
                         NodeAction::Advance => {
-                            advance_node(&mut executor);
+                            advance_node(executor);
                         }
                         NodeAction::Jump(target_id) => {
                             executor.current_node = Some(target_id);
                         }
+                        NodeAction::Call { graph_id, entry_node } => {
+                            call_graph(
+                                executor,
+                                &ctx.library,
+                                &graph_id,
+                                entry_node.as_deref(),
+                                &mut ctx.variables,
+                            );
+                        }
                         NodeAction::End => {
-                            executor.status = ExecutionStatus::Idle;
-                            flow_events.send(StoryFlowEvent::GraphComplete);
+                            if !return_from_call(executor) {
+                                executor.status = ExecutionStatus::Idle;
+                                outputs.flow.send(StoryFlowEvent::GraphComplete { executor: id });
+                            }
                         }
                     }
                 } else {
                     executor.status = ExecutionStatus::Idle;
                 }
-            } else {
+            } else if !return_from_call(executor) {
                 executor.status = ExecutionStatus::Idle;
-                flow_events.send(StoryFlowEvent::GraphComplete);
+                outputs.flow.send(StoryFlowEvent::GraphComplete { executor: id });
             }
         } else {
             executor.status = ExecutionStatus::Idle;
         }
     }
+
+    if executor.status == ExecutionStatus::Running {
+        warn!(
+            "Story: {:?} ran {} nodes without waiting; continuing next frame (a non-waiting loop? see StoryGraphData::analyze)",
+            id, loops
+        );
+    }
+    if executor.mode == ExecutorMode::Blocking {
+        record_variables(executor, &ctx.variables, &mut recorded);
+    }
+}
+
+/// Advance past the waiting line or choice, stopping the line's voice and
+/// marking it read.
+fn leave_line(executor: &mut GraphExecutor, read: &mut StoryReadSet, outputs: &mut StoryOutputs) {
+    if let (ExecutorMode::Blocking, Some(graph), Some(node)) =
+        (executor.mode, &executor.active_graph, executor.current_node)
+    {
+        if matches!(graph.nodes.get(&node), Some(StoryNode::Dialogue { .. })) {
+            read.insert(&graph.id, &graph.node_key(node));
+        }
+    }
+    if executor.voice_playing {
+        outputs.audio.send(AudioCommand::StopVoice);
+    }
+    executor.clear_line();
+    executor.status = ExecutionStatus::Running;
+    advance_node(executor);
+}
+
+/// Append the variable writes since `recorded` to the executor's history.
+fn record_variables(executor: &mut GraphExecutor, variables: &StoryVariables, recorded: &mut usize) {
+    let changes = variables.pending_changes();
+    for change in changes.get(*recorded..).unwrap_or_default() {
+        executor.history.push(HistoryEntry::Variable(change.clone()));
+    }
+    *recorded = changes.len();
+}
+
+/// Handle a [`StoryInputEvent::Rewind`], finding graphs among the running
+/// ones and then the library.
+fn rewind(executor: &mut GraphExecutor, line: usize, ctx: &mut StoryContext, outputs: &mut StoryOutputs) {
+    let running: Vec<StoryGraph> = executor
+        .active_graph
+        .iter()
+        .chain(executor.call_stack.iter().map(|frame| &frame.graph))
+        .cloned()
+        .collect();
+    let find_graph = |graph_id: &str| {
+        running
+            .iter()
+            .find(|graph| graph.id == graph_id)
+            .or_else(|| ctx.library.get(graph_id))
+            .cloned()
+    };
+    if executor.voice_playing {
+        outputs.audio.send(AudioCommand::StopVoice);
+    }
+    if let Err(err) = executor.rewind(line, find_graph, &mut ctx.flags, &mut ctx.variables) {
+        warn!("Story: can't rewind: {}", err);
+    }
+}
+
+/// Resolve a voice id through the asset index; without one, ids are paths.
+fn voice_clip_path(index: Option<&AssetIndex>, id: &str) -> String {
+    match index.map(|index| index.find_audio(id)) {
+        Some(Some(asset)) => asset.path.clone(),
+        Some(None) => {
+            warn!("Story: voice line '{}' is not in the asset index; using it as a path", id);
+            id.to_string()
+        }
+        None => id.to_string(),
+    }
+}
+
+/// Index (into the presented options) of the first selectable choice.
+fn first_available_choice(executor: &GraphExecutor, flags: &StoryFlags, variables: &StoryVariables) -> Option<usize> {
+    let graph = executor.active_graph.as_ref()?;
+    let Some(StoryNode::Choice { options, .. }) = graph.nodes.get(&executor.current_node?) else {
+        return None;
+    };
+    executor
+        .presented_choices
+        .iter()
+        .position(|&i| options[i].is_available(flags, variables))
+}
+
+/// Index (into the presented options) of a timed choice's default option,
+/// or the first selectable one if it isn't selectable.
+fn default_choice(executor: &GraphExecutor, flags: &StoryFlags, variables: &StoryVariables) -> Option<usize> {
+    let graph = executor.active_graph.as_ref()?;
+    let Some(StoryNode::Choice { options, default_option, .. }) = graph.nodes.get(&executor.current_node?) else {
+        return None;
+    };
+    default_option
+        .and_then(|default| executor.presented_choices.iter().position(|&i| i == default))
+        .filter(|&index| options[executor.presented_choices[index]].is_available(flags, variables))
+        .or_else(|| first_available_choice(executor, flags, variables))
+}
+
+/// Run condition: true while a blocking executor is running a graph.
+///
+/// Games use this to freeze player control during conversations while
+/// background graphs keep playing.
+pub fn blocking_story_active(main: Res<GraphExecutor>, instances: Query<&GraphExecutor>) -> bool {
+    std::iter::once(&*main)
+        .chain(instances.iter())
+        .any(|e| e.mode == ExecutorMode::Blocking && e.status != ExecutionStatus::Idle)
 }
 
 fn advance_node(executor: &mut GraphExecutor) {
@@ -381,9 +1028,12 @@ fn advance_node(executor: &mut GraphExecutor) {
                     StoryNode::Scene { next, .. } => *next,
                     StoryNode::Wait { next, .. } => *next,
                     StoryNode::SetFlag { next, .. } => *next,
-                    StoryNode::SetFlag { next, .. } => *next,
+                    StoryNode::Camera { next, .. } => *next,
+                    StoryNode::TimeControl { next, .. } => *next,
                     StoryNode::Event { next, .. } => *next,
+                    StoryNode::Effect { next, .. } => *next,
                     StoryNode::Start { next, .. } => *next,
+                    StoryNode::Call { next, .. } => *next,
                     _ => None,
                 }
             } else { None }
@@ -393,50 +1043,152 @@ fn advance_node(executor: &mut GraphExecutor) {
     executor.current_node = next_id;
 }
 
-fn handle_choice_selection(executor: &mut GraphExecutor, index: usize) {
-    let next_id = if let Some(graph) = &executor.active_graph {
-        if let Some(node_id) = executor.current_node {
-             if let StoryNode::Choice { options, .. } = &graph.nodes[&node_id] {
-                 options.get(index).and_then(|opt| opt.next)
-             } else { None }
-        } else { None }
-    } else { None };
+/// Suspend the current graph and enter `graph_id` from the library.
+///
+/// Missing graphs or entry nodes, and calls nested deeper than
+/// [`MAX_CALL_DEPTH`], are skipped with a warning.
+fn call_graph(
+    executor: &mut GraphExecutor,
+    library: &StoryGraphLibrary,
+    graph_id: &str,
+    entry_node: Option<&str>,
+    variables: &mut StoryVariables,
+) {
+    // The caller resumes at the Call node's `next`.
+    advance_node(executor);
 
-    executor.current_node = next_id;
+    let Some(callee) = library.get(graph_id) else {
+        warn!("Story: Call to unknown graph '{}'", graph_id);
+        return;
+    };
+    if executor.call_stack.len() >= MAX_CALL_DEPTH {
+        warn!("Story: Call to '{}' exceeds max depth {}; skipped", graph_id, MAX_CALL_DEPTH);
+        return;
+    }
+    let entry = match entry_node {
+        Some(key) => callee.find_node(key),
+        None => callee.start_node,
+    };
+    let Some(entry) = entry else {
+        warn!("Story: graph '{}' has no entry node {:?}", graph_id, entry_node);
+        return;
+    };
+
+    variables.seed(&callee.variables);
+    if let Some(caller) = executor.active_graph.replace(callee.clone()) {
+        executor.call_stack.push(CallFrame {
+            graph: caller,
+            return_node: executor.current_node,
+        });
+    }
+    executor.current_node = Some(entry);
+}
+
+/// Resume the innermost caller. Returns `false` if there is none.
+fn return_from_call(executor: &mut GraphExecutor) -> bool {
+    let Some(frame) = executor.call_stack.pop() else {
+        return false;
+    };
+    executor.active_graph = Some(frame.graph);
+    executor.current_node = frame.return_node;
+    true
+}
+
+fn handle_choice_selection(
+    executor: &mut GraphExecutor,
+    index: usize,
+    flags: &StoryFlags,
+    variables: &mut StoryVariables,
+    outputs: &mut StoryOutputs,
+) {
+    let option = executor
+        .active_graph
+        .as_ref()
+        .zip(executor.current_node)
+        .and_then(|(graph, node_id)| match graph.nodes.get(&node_id) {
+            Some(StoryNode::Choice { options, .. }) => executor
+                .presented_choices
+                .get(index)
+                .and_then(|&i| options.get(i))
+                .cloned(),
+            _ => None,
+        });
+
+    let Some(option) = option else {
+        warn!("Story: choice {} is out of range", index);
+        return;
+    };
+    if !option.is_available(flags, variables) {
+        warn!("Story: choice '{}' is unavailable", option.id);
+        return;
+    }
+    executor.history.choose(index);
+
+    for effect in &option.effects {
+        if let Some(event) = effects::apply_effect(effect, variables) {
+            outputs.story.send(event);
+        }
+    }
+
+    executor.current_node = option.next;
+    executor.presented_choices.clear();
+    executor.choice_timer = None;
     executor.status = ExecutionStatus::Running;
 }
 
 fn process_node(
     node: &StoryNode,
-    flags: &mut StoryFlags,
-    flow: &mut EventWriter<StoryFlowEvent>,
-    audio: &mut EventWriter<AudioCommand>,
-    scene: &mut EventWriter<ChangeSceneEvent>,
-    story: &mut EventWriter<StoryEvent>,
+    id: ExecutorId,
+    ctx: &mut StoryContext,
+    outputs: &mut StoryOutputs,
 ) -> NodeAction {
+    let StoryContext { flags, variables, language, database, asset_index, .. } = ctx;
+    let language: &CurrentLanguage = language;
+    let markup = markup::MarkupContext { variables, database: database.as_deref(), language };
     match node {
-        StoryNode::Dialogue { speaker, text, portrait, .. } => {
-            flow.send(StoryFlowEvent::ShowDialogue { 
+        StoryNode::Dialogue { speaker, text, portrait, voice, duration, .. } => {
+            let spans = markup::render_or_raw(text.resolve(language), &markup);
+            let plain = markup::plain_text(&spans);
+            outputs.flow.send(StoryFlowEvent::ShowDialogue { 
+                executor: id,
                 speaker: speaker.clone(), 
-                text: text.clone(), 
+                text: plain.clone(),
+                spans,
                 portrait: portrait.clone() 
             });
-            NodeAction::WaitInput
+            // Played by blocking executors only; see `ExecutorMode::Background`.
+            let voice = voice.as_deref().map(|id| voice_clip_path(asset_index.as_deref(), id));
+            NodeAction::WaitInput { duration: *duration, voice, speaker: speaker.clone(), text: plain }
         }
-        StoryNode::Choice { prompt, options, .. } => {
-            let option_texts = options.iter().map(|o| o.text.clone()).collect();
-            flow.send(StoryFlowEvent::ShowChoices { 
-                prompt: prompt.clone(), 
-                options: option_texts 
+        StoryNode::Choice { prompt, options, timeout, .. } => {
+            let mut presented = Vec::new();
+            let mut views = Vec::new();
+            for (i, option) in options.iter().enumerate() {
+                let available = option.is_available(flags, markup.variables);
+                if available || option.show_when_unavailable {
+                    presented.push(i);
+                    views.push(ChoiceView {
+                        id: option.id.clone(),
+                        text: markup::plain_text(&markup::render_or_raw(option.text.resolve(language), &markup)),
+                        available,
+                    });
+                }
+            }
+            let prompt = markup::plain_text(&markup::render_or_raw(prompt.resolve(language), &markup));
+            outputs.flow.send(StoryFlowEvent::ShowChoices { 
+                executor: id,
+                prompt: prompt.clone(),
+                options: views.clone(),
+                timeout: *timeout,
             });
-            NodeAction::WaitInput
+            NodeAction::WaitChoice { presented, prompt, options: views, timeout: *timeout }
         }
         StoryNode::Audio { command, .. } => {
-            audio.send(command.clone());
+            outputs.audio.send(command.clone());
             NodeAction::Advance
         }
         StoryNode::Scene { path, duration, .. } => {
-            scene.send(ChangeSceneEvent { 
+            outputs.scene.send(ChangeSceneEvent { 
                 background_path: path.clone(), 
                 duration: *duration 
             });
@@ -452,15 +1204,49 @@ fn process_node(
                 if let Some(id) = if_false { NodeAction::Jump(*id) } else { NodeAction::Advance }
             }
         }
+        StoryNode::Conditional { condition, if_true, if_false } => {
+            let target = if variables.evaluate(condition) { if_true } else { if_false };
+            if let Some(id) = target { NodeAction::Jump(*id) } else { NodeAction::Advance }
+        }
+        StoryNode::Camera { position, zoom, angle, duration, easing, .. } => {
+            outputs.cinematic.send(CinematicCommand::MoveCamera {
+                position: *position,
+                zoom: *zoom,
+                angle: *angle,
+                duration: *duration,
+                easing: *easing,
+            });
+            if *duration > 0.0 { NodeAction::WaitTimer(*duration) } else { NodeAction::Advance }
+        }
+        StoryNode::TimeControl { pause_gameplay, time_scale, .. } => {
+            outputs.cinematic.send(CinematicCommand::SetTimeControl {
+                pause_gameplay: *pause_gameplay,
+                time_scale: *time_scale,
+            });
+            NodeAction::Advance
+        }
         StoryNode::SetFlag { flag, value, .. } => {
             flags.set(flag, *value);
+            variables.set(flag, *value);
+            NodeAction::Advance
+        }
+        StoryNode::Effect { effects, .. } => {
+            for effect in effects {
+                if let Some(event) = effects::apply_effect(effect, variables) {
+                    outputs.story.send(event);
+                }
+            }
             NodeAction::Advance
         }
         StoryNode::Event { event_id, payload, .. } => {
-            story.send(StoryEvent { id: event_id.clone(), payload: payload.clone() });
+            outputs.story.send(StoryEvent { id: event_id.clone(), payload: payload.clone() });
             NodeAction::Advance
         }
-        StoryNode::End => {
+        StoryNode::Call { graph_id, entry_node, .. } => NodeAction::Call {
+            graph_id: graph_id.clone(),
+            entry_node: entry_node.clone(),
+        },
+        StoryNode::Return | StoryNode::End => {
             NodeAction::End
         }
         StoryNode::Start { .. } => {
@@ -0,0 +1,117 @@
+//! Auto-advance and skip modes for blocking executors.
+//!
+//! [`AdvanceMode`] decides how long a dialogue line waits before the
+//! executor advances on its own. The line still waits in
+//! [`ExecutionStatus::WaitingForInput`](super::ExecutionStatus), so players
+//! can advance early and UIs need no changes. Choices always wait for input.
+//!
+//! Skipping previously seen lines needs [`StoryReadSet`], which records
+//! each line once a blocking executor leaves it, so a line that is shown
+//! but never advanced past stays unread. Save it with the player's settings
+//! to keep it between sessions.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// How a blocking executor advances past dialogue lines.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Reflect)]
+pub enum AdvanceMode {
+    /// Wait for [`StoryInputEvent::Advance`](super::StoryInputEvent), or the
+    /// line's own duration or voice clip.
+    #[default]
+    Manual,
+    /// Also advance unvoiced lines after `base_delay` seconds plus
+    /// `per_char` seconds for each character of text.
+    Auto { base_delay: f32, per_char: f32 },
+    /// Advance every line on the next frame.
+    FastForward,
+    /// Advance lines in the [`StoryReadSet`] on the next frame; unread lines
+    /// wait as in `Manual`.
+    SkipRead,
+}
+
+impl AdvanceMode {
+    /// `Auto` with a one second delay plus 50ms per character.
+    pub fn auto() -> Self {
+        AdvanceMode::Auto { base_delay: 1.0, per_char: 0.05 }
+    }
+
+    /// Seconds until a line advances on its own, if it does.
+    ///
+    /// `duration` is the line's own, `voiced` whether it plays a voice clip
+    /// (whose length applies otherwise) and `read` whether it was left
+    /// before (see [`StoryReadSet`]).
+    pub fn line_duration(&self, duration: Option<f32>, voiced: bool, text: &str, read: bool) -> Option<f32> {
+        match *self {
+            AdvanceMode::Manual => duration,
+            AdvanceMode::Auto { base_delay, per_char } => duration.or_else(|| {
+                (!voiced).then(|| base_delay + per_char * text.chars().count() as f32)
+            }),
+            AdvanceMode::FastForward => Some(0.0),
+            AdvanceMode::SkipRead if read => Some(0.0),
+            AdvanceMode::SkipRead => duration,
+        }
+    }
+
+    /// Whether a line is skipped rather than played.
+    pub fn skips(&self, read: bool) -> bool {
+        match self {
+            AdvanceMode::FastForward => true,
+            AdvanceMode::SkipRead => read,
+            AdvanceMode::Manual | AdvanceMode::Auto { .. } => false,
+        }
+    }
+}
+
+/// Dialogue lines the player has advanced past, by graph id and authored node id.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StoryReadSet {
+    lines: BTreeSet<(String, String)>,
+}
+
+impl StoryReadSet {
+    pub fn contains(&self, graph_id: &str, node: &str) -> bool {
+        self.lines.contains(&(graph_id.to_string(), node.to_string()))
+    }
+
+    /// Mark a line as read, returning whether it was read before.
+    pub fn insert(&mut self, graph_id: &str, node: &str) -> bool {
+        !self.lines.insert((graph_id.to_string(), node.to_string()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_duration() {
+        let auto = AdvanceMode::Auto { base_delay: 1.0, per_char: 0.5 };
+        assert_eq!(auto.line_duration(None, false, "Hey", false), Some(2.5));
+        assert_eq!(auto.line_duration(Some(4.0), false, "Hey", false), Some(4.0));
+        assert_eq!(auto.line_duration(None, true, "Hey", false), None);
+        assert_eq!(AdvanceMode::Manual.line_duration(None, false, "Hey", true), None);
+        assert_eq!(AdvanceMode::FastForward.line_duration(Some(4.0), true, "Hey", false), Some(0.0));
+        assert_eq!(AdvanceMode::SkipRead.line_duration(None, false, "Hey", true), Some(0.0));
+        assert_eq!(AdvanceMode::SkipRead.line_duration(None, false, "Hey", false), None);
+
+        let mut read = StoryReadSet::default();
+        assert!(!read.insert("intro", "hello"));
+        assert!(read.insert("intro", "hello"));
+        let json = serde_json::to_string(&read).unwrap();
+        assert_eq!(serde_json::from_str::<StoryReadSet>(&json).unwrap(), read);
+    }
+}
@@ -0,0 +1,430 @@
+//! Quest runtime for the quests in the [`Database`].
+//!
+//! [`QuestLog`] holds each quest's [`QuestState`] and the progress of its
+//! objectives (its completion conditions). Every update, inactive quests
+//! whose start conditions hold become active, and active quests whose
+//! objectives all hold complete and grant their [`QuestRewards`]. Stories
+//! change quest states with the `set_quest_state` effect (params `quest_id`
+//! and `state`); games call [`QuestLog::set_state`]. Every change is
+//! announced as a [`QuestEvent`].
+//!
+//! Rewards go to the story state: gold and experience are added to the
+//! [`GOLD_VARIABLE`] and [`EXPERIENCE_VARIABLE`] variables, reward flags are
+//! set as variables (booleans also in [`StoryFlags`]) and items are added to
+//! the [`QuestInventory`], which `has_item` conditions read. The
+//! `give_item`/`remove_item` effects (params `item_id` and `quantity`)
+//! update it as well.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::{StoryEvent, StoryFlags, StoryValue, StoryVariables};
+use crate::data::database::{QuestCondition, QuestRewards, QuestState};
+use crate::data::story::StoryCondition;
+use crate::data::Database;
+
+/// Variable that gold rewards are added to.
+pub const GOLD_VARIABLE: &str = "gold";
+/// Variable that experience rewards are added to.
+pub const EXPERIENCE_VARIABLE: &str = "experience";
+
+/// Item counts that `has_item` quest conditions check.
+///
+/// Games with an inventory of their own keep this in sync with it.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QuestInventory {
+    items: BTreeMap<String, u32>,
+}
+
+impl QuestInventory {
+    pub fn count(&self, item_id: &str) -> u32 {
+        self.items.get(item_id).copied().unwrap_or(0)
+    }
+
+    /// Add items, stopping at `u32::MAX`.
+    pub fn add(&mut self, item_id: &str, quantity: u32) {
+        let count = self.items.entry(item_id.to_string()).or_default();
+        *count = count.saturating_add(quantity);
+    }
+
+    /// Remove up to `quantity` items, returning how many were removed.
+    pub fn remove(&mut self, item_id: &str, quantity: u32) -> u32 {
+        let Some(count) = self.items.get_mut(item_id) else {
+            return 0;
+        };
+        let removed = quantity.min(*count);
+        *count -= removed;
+        if *count == 0 {
+            self.items.remove(item_id);
+        }
+        removed
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.items.iter().map(|(id, count)| (id.as_str(), *count))
+    }
+}
+
+/// How far along one objective is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ObjectiveProgress {
+    pub current: u32,
+    pub required: u32,
+}
+
+impl ObjectiveProgress {
+    pub fn is_complete(&self) -> bool {
+        self.current >= self.required
+    }
+}
+
+/// A quest's entry in the [`QuestLog`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QuestProgress {
+    pub state: QuestState,
+    /// One entry per completion condition, updated while the quest is active
+    pub objectives: Vec<ObjectiveProgress>,
+}
+
+/// State of every quest that has left [`QuestState::Inactive`].
+///
+/// Serializable so it can be saved with the game.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QuestLog {
+    quests: BTreeMap<String, QuestProgress>,
+    #[serde(skip)]
+    requested: Vec<(String, QuestState)>,
+    /// Quests already warned about for having custom conditions
+    #[serde(skip)]
+    warned: BTreeSet<String>,
+}
+
+/// Sent when quests change.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub enum QuestEvent {
+    StateChanged { quest_id: String, old: QuestState, new: QuestState },
+    /// An active quest's objective changed, or the quest just started.
+    Progress { quest_id: String, objective: usize, progress: ObjectiveProgress },
+    /// The quest completed and its rewards were granted.
+    Rewarded { quest_id: String, rewards: QuestRewards },
+}
+
+impl QuestLog {
+    pub fn state(&self, quest_id: &str) -> QuestState {
+        self.quests.get(quest_id).map(|quest| quest.state).unwrap_or_default()
+    }
+
+    pub fn get(&self, quest_id: &str) -> Option<&QuestProgress> {
+        self.quests.get(quest_id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &QuestProgress)> {
+        self.quests.iter().map(|(id, quest)| (id.as_str(), quest))
+    }
+
+    /// Active quests, by id.
+    pub fn active(&self) -> impl Iterator<Item = (&str, &QuestProgress)> {
+        self.iter().filter(|(_, quest)| quest.state == QuestState::Active)
+    }
+
+    /// Move a quest to `state` on the next [`QuestLog::update`], which
+    /// sends the events and grants rewards for it.
+    pub fn set_state(&mut self, quest_id: impl Into<String>, state: QuestState) {
+        self.requested.push((quest_id.into(), state));
+    }
+
+    /// Apply requested states, then start and complete quests whose
+    /// conditions hold.
+    pub fn update(
+        &mut self,
+        database: &Database,
+        inventory: &mut QuestInventory,
+        flags: &mut StoryFlags,
+        variables: &mut StoryVariables,
+    ) -> Vec<QuestEvent> {
+        let mut events = Vec::new();
+        for (quest_id, state) in std::mem::take(&mut self.requested) {
+            if database.find_quest(&quest_id).is_none() {
+                warn!("Quest: unknown quest '{}'", quest_id);
+                continue;
+            }
+            self.transition(&quest_id, state, database, inventory, flags, variables, &mut events);
+        }
+
+        for quest in &database.quests {
+            let custom = |c: &QuestCondition| matches!(c, QuestCondition::Custom { .. });
+            if quest.start_conditions.iter().chain(&quest.completion_conditions).any(custom)
+                && self.warned.insert(quest.id.clone())
+            {
+                warn!("Quest: '{}' has custom conditions, which never hold", quest.id);
+            }
+            if self.state(&quest.id) == QuestState::Inactive
+                && self.all_hold(&quest.start_conditions, inventory, flags, variables)
+            {
+                self.transition(&quest.id, QuestState::Active, database, inventory, flags, variables, &mut events);
+            }
+            if self.state(&quest.id) != QuestState::Active {
+                continue;
+            }
+
+            let objectives: Vec<_> = quest
+                .completion_conditions
+                .iter()
+                .map(|condition| self.progress(condition, inventory, flags, variables))
+                .collect();
+            let entry = self.quests.entry(quest.id.clone()).or_default();
+            for (objective, progress) in objectives.iter().enumerate() {
+                if entry.objectives.get(objective) != Some(progress) {
+                    events.push(QuestEvent::Progress { quest_id: quest.id.clone(), objective, progress: *progress });
+                }
+            }
+            entry.objectives = objectives;
+            if self.all_hold(&quest.completion_conditions, inventory, flags, variables) {
+                self.transition(&quest.id, QuestState::Completed, database, inventory, flags, variables, &mut events);
+            }
+        }
+        events
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn transition(
+        &mut self,
+        quest_id: &str,
+        state: QuestState,
+        database: &Database,
+        inventory: &mut QuestInventory,
+        flags: &mut StoryFlags,
+        variables: &mut StoryVariables,
+        events: &mut Vec<QuestEvent>,
+    ) {
+        let entry = self.quests.entry(quest_id.to_string()).or_default();
+        let old = entry.state;
+        if old == state {
+            return;
+        }
+        entry.state = state;
+        info!("Quest: '{}' is now {:?}", quest_id, state);
+        events.push(QuestEvent::StateChanged { quest_id: quest_id.to_string(), old, new: state });
+
+        if state == QuestState::Completed {
+            if let Some(quest) = database.find_quest(quest_id) {
+                grant_rewards(&quest.rewards, inventory, flags, variables);
+                events.push(QuestEvent::Rewarded { quest_id: quest_id.to_string(), rewards: quest.rewards.clone() });
+            }
+        }
+    }
+
+    /// Whether there are conditions and all of them hold.
+    fn all_hold(
+        &self,
+        conditions: &[QuestCondition],
+        inventory: &QuestInventory,
+        flags: &StoryFlags,
+        variables: &StoryVariables,
+    ) -> bool {
+        !conditions.is_empty()
+            && conditions.iter().all(|c| self.progress(c, inventory, flags, variables).is_complete())
+    }
+
+    /// How far along a condition is; conditions other than `has_item` are
+    /// 0 or 1 of 1, and custom ones are never met.
+    fn progress(
+        &self,
+        condition: &QuestCondition,
+        inventory: &QuestInventory,
+        flags: &StoryFlags,
+        variables: &StoryVariables,
+    ) -> ObjectiveProgress {
+        let holds = match condition {
+            QuestCondition::HasItem { item_id, quantity } => {
+                return ObjectiveProgress { current: inventory.count(item_id).min(*quantity), required: *quantity };
+            }
+            QuestCondition::Variable { variable, operator, value } => variables.evaluate(&StoryCondition {
+                variable: variable.clone(),
+                operator: *operator,
+                value: value.clone(),
+            }),
+            QuestCondition::Flag { flag, value } => {
+                let set = flags.0.get(flag).copied().or_else(|| match variables.get(flag) {
+                    Some(StoryValue::Bool(set)) => Some(*set),
+                    _ => None,
+                });
+                set.unwrap_or(false) == *value
+            }
+            QuestCondition::Quest { quest_id, state } => self.state(quest_id) == *state,
+            QuestCondition::Custom { .. } => false,
+        };
+        ObjectiveProgress { current: holds as u32, required: 1 }
+    }
+}
+
+/// Add a completed quest's rewards to the story state.
+pub fn grant_rewards(
+    rewards: &QuestRewards,
+    inventory: &mut QuestInventory,
+    flags: &mut StoryFlags,
+    variables: &mut StoryVariables,
+) {
+    if rewards.gold != 0 {
+        variables.add(GOLD_VARIABLE, rewards.gold);
+    }
+    if rewards.experience != 0 {
+        variables.add(EXPERIENCE_VARIABLE, rewards.experience);
+    }
+    for item in &rewards.item_rewards {
+        inventory.add(&item.item_id, item.quantity);
+    }
+    // Sorted so variable change events come in a stable order.
+    for (flag, value) in rewards.flags.iter().collect::<BTreeMap<_, _>>() {
+        match StoryValue::from_json(value) {
+            Some(value) => {
+                if let StoryValue::Bool(set) = value {
+                    flags.set(flag, set);
+                }
+                variables.set(flag, value);
+            }
+            None => warn!("Quest: reward flag '{}' has unsupported value {}", flag, value),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SetQuestState {
+    quest_id: String,
+    state: QuestState,
+}
+
+#[derive(Deserialize)]
+struct ItemParams {
+    item_id: String,
+    #[serde(default)]
+    quantity: Option<u32>,
+}
+
+/// Apply quest and item effects, then update the [`QuestLog`].
+pub(super) fn update_quests(
+    database: Option<Res<Database>>,
+    mut story_events: EventReader<StoryEvent>,
+    mut log: ResMut<QuestLog>,
+    mut inventory: ResMut<QuestInventory>,
+    mut flags: ResMut<StoryFlags>,
+    mut variables: ResMut<StoryVariables>,
+    mut events: EventWriter<QuestEvent>,
+) {
+    for event in story_events.read() {
+        match event.id.as_str() {
+            "set_quest_state" => match serde_json::from_str::<SetQuestState>(&event.payload) {
+                Ok(params) => log.set_state(params.quest_id, params.state),
+                Err(e) => warn!("Quest: bad set_quest_state params {}: {}", event.payload, e),
+            },
+            "give_item" | "remove_item" => match serde_json::from_str::<ItemParams>(&event.payload) {
+                Ok(params) if event.id == "give_item" => inventory.add(&params.item_id, params.quantity.unwrap_or(1)),
+                Ok(params) => {
+                    inventory.remove(&params.item_id, params.quantity.unwrap_or(1));
+                }
+                Err(e) => warn!("Quest: bad {} params {}: {}", event.id, event.payload, e),
+            },
+            _ => {}
+        }
+    }
+
+    let Some(database) = database else {
+        return;
+    };
+    // Avoid tripping change detection when nothing happens.
+    let changes = log.bypass_change_detection().update(&database, &mut inventory, &mut flags, &mut variables);
+    if !changes.is_empty() {
+        log.set_changed();
+        events.send_batch(changes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::database::{ItemReward, QuestRow};
+    use crate::data::story::ConditionOperator;
+    use serde_json::json;
+
+    fn database() -> Database {
+        let mut herbs = QuestRow::new("quest_fetch_herbs", "Lost Herbs");
+        herbs.start_conditions.push(QuestCondition::Flag { flag: "met_merchant".to_string(), value: true });
+        herbs.completion_conditions.push(QuestCondition::HasItem { item_id: "herb".to_string(), quantity: 3 });
+        herbs.rewards = QuestRewards {
+            gold: 100,
+            experience: 50,
+            item_rewards: vec![ItemReward { item_id: "potion_hp".to_string(), quantity: 2 }],
+            flags: [("herbs_done".to_string(), json!(true))].into(),
+        };
+        let mut sequel = QuestRow::new("quest_sequel", "Sequel");
+        sequel.completion_conditions.push(QuestCondition::Variable {
+            variable: "gold".to_string(),
+            operator: ConditionOperator::GreaterThanOrEquals,
+            value: json!(500),
+        });
+
+        let mut database = Database::new();
+        database.quests = vec![herbs, sequel];
+        database
+    }
+
+    #[test]
+    fn test_quest_lifecycle() {
+        let database = database();
+        let mut log = QuestLog::default();
+        let mut inventory = QuestInventory::default();
+        let mut flags = StoryFlags::default();
+        let mut variables = StoryVariables::default();
+        let mut update = |log: &mut QuestLog, inventory: &mut QuestInventory, flags: &mut StoryFlags| {
+            log.update(&database, inventory, flags, &mut variables)
+        };
+
+        assert!(update(&mut log, &mut inventory, &mut flags).is_empty());
+        flags.set("met_merchant", true);
+        inventory.add("herb", 1);
+        let progress = |current| ObjectiveProgress { current, required: 3 };
+        assert_eq!(
+            update(&mut log, &mut inventory, &mut flags),
+            [
+                QuestEvent::StateChanged {
+                    quest_id: "quest_fetch_herbs".to_string(),
+                    old: QuestState::Inactive,
+                    new: QuestState::Active,
+                },
+                QuestEvent::Progress { quest_id: "quest_fetch_herbs".to_string(), objective: 0, progress: progress(1) },
+            ]
+        );
+        assert!(update(&mut log, &mut inventory, &mut flags).is_empty());
+
+        inventory.add("herb", 5);
+        let events = update(&mut log, &mut inventory, &mut flags);
+        assert_eq!(events.len(), 3);
+        assert_eq!(
+            events[0],
+            QuestEvent::Progress { quest_id: "quest_fetch_herbs".to_string(), objective: 0, progress: progress(3) }
+        );
+        assert!(matches!(&events[2], QuestEvent::Rewarded { rewards, .. } if rewards.gold == 100));
+        assert_eq!(log.state("quest_fetch_herbs"), QuestState::Completed);
+        assert_eq!(inventory.count("potion_hp"), 2);
+        assert!(flags.get("herbs_done"));
+
+        // Objectives only complete quests that are active.
+        log.set_state("quest_sequel", QuestState::Active);
+        log.set_state("quest_missing", QuestState::Active);
+        let events = update(&mut log, &mut inventory, &mut flags);
+        assert_eq!(events.len(), 2);
+        assert_eq!(log.get("quest_sequel").unwrap().objectives, [ObjectiveProgress { current: 0, required: 1 }]);
+        assert_eq!(log.state("quest_missing"), QuestState::Inactive);
+        assert_eq!(variables.get(GOLD_VARIABLE), Some(&StoryValue::Int(100)));
+        assert_eq!(variables.get(EXPERIENCE_VARIABLE), Some(&StoryValue::Int(50)));
+    }
+
+    #[test]
+    fn test_inventory_add_saturates() {
+        let mut inventory = QuestInventory::default();
+        inventory.add("herb", u32::MAX);
+        inventory.add("herb", 3);
+        assert_eq!(inventory.count("herb"), u32::MAX);
+    }
+}
@@ -0,0 +1,298 @@
+//! Reloading story graphs from disk while the game runs.
+//!
+//! Insert a [`StoryGraphWatcher`] for a project's `story_graphs` directory
+//! and [`StoryGraphPlugin`](super::StoryGraphPlugin) polls it for changed
+//! files (anything [`load_story_graph`] reads). A changed graph replaces the
+//! one in the [`StoryGraphLibrary`] and in every executor running or
+//! suspended in it. Executors stay on the node they were on, matched by
+//! authored id; if it was deleted they move to the graph's start node and a
+//! [`StoryReloadEvent::Fallback`] reports it. A line or choice on screen is
+//! shown again, so edited text appears right away.
+
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use super::{ExecutionStatus, ExecutorId, GraphExecutor, Reentry, StoryGraph, StoryGraphLibrary, StoryVariables};
+use crate::data::load_story_graph;
+use crate::data::loader::is_story_graph_file;
+
+/// Seconds between scans of the watched directory.
+pub const POLL_INTERVAL: f32 = 0.5;
+
+/// Watches a directory of story graph files for changes.
+#[derive(Resource, Debug)]
+pub struct StoryGraphWatcher {
+    dir: PathBuf,
+    timer: Timer,
+    modified: HashMap<PathBuf, SystemTime>,
+    /// Whether the first scan, which only records what's there, has run.
+    scanned: bool,
+}
+
+impl StoryGraphWatcher {
+    /// Watch `dir` for changes from now on.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        let mut watcher = Self {
+            dir: dir.into(),
+            timer: Timer::from_seconds(POLL_INTERVAL, TimerMode::Repeating),
+            modified: HashMap::new(),
+            scanned: false,
+        };
+        watcher.changed_files();
+        watcher
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Files created or modified since the last scan.
+    pub fn changed_files(&mut self) -> Vec<PathBuf> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                if !self.scanned {
+                    warn!("Story: can't watch {}: {}", self.dir.display(), e);
+                }
+                self.scanned = true;
+                return Vec::new();
+            }
+        };
+
+        let mut changed = Vec::new();
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if !is_story_graph_file(&path) {
+                continue;
+            }
+            let Ok(modified) = path.metadata().and_then(|meta| meta.modified()) else {
+                continue;
+            };
+            if self.modified.insert(path.clone(), modified) != Some(modified) && self.scanned {
+                changed.push(path);
+            }
+        }
+        self.scanned = true;
+        changed.sort();
+        changed
+    }
+}
+
+/// Sent by the reload system for each changed file.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub enum StoryReloadEvent {
+    /// The graph was reloaded and is in use everywhere it was before.
+    Reloaded { graph_id: String, path: PathBuf },
+    /// An executor's node (or a caller's return node) no longer exists, so
+    /// it moved to `fallback`, the graph's start node.
+    Fallback { executor: ExecutorId, graph_id: String, missing_node: String, fallback: Option<String> },
+    /// The file couldn't be loaded; the previous version stays in use.
+    Failed { path: PathBuf, error: String },
+}
+
+/// A node that disappeared from a reloaded graph.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeFallback {
+    /// Authored id of the missing node
+    pub missing_node: String,
+    /// Authored id of the node used instead
+    pub fallback: Option<String>,
+}
+
+impl GraphExecutor {
+    /// Swap in a new version of `graph` wherever this executor runs it or
+    /// has it suspended, matching nodes by authored id.
+    ///
+    /// Returns the nodes that no longer exist; those fall back to the
+    /// graph's start node. A waiting line or choice is re-entered so it is
+    /// shown again; if it still exists it keeps its read state, voice and
+    /// timers. A timed wait whose node moved runs the new node instead of
+    /// finishing its countdown.
+    pub fn reload_graph(&mut self, graph: &StoryGraph) -> Vec<NodeFallback> {
+        let mut fallbacks = Vec::new();
+        let mut rematch = |old: &StoryGraph, node: Option<usize>| {
+            let key = old.node_key(node?);
+            let found = graph.find_node(&key);
+            if found.is_none() {
+                fallbacks.push(NodeFallback {
+                    missing_node: key,
+                    fallback: graph.start_node.map(|id| graph.node_key(id)),
+                });
+            }
+            found.or(graph.start_node)
+        };
+
+        for frame in self.call_stack.iter_mut().filter(|frame| frame.graph.id == graph.id) {
+            frame.return_node = rematch(&frame.graph, frame.return_node);
+            frame.graph = graph.clone();
+        }
+        if let Some(active) = self.active_graph.as_mut().filter(|active| active.id == graph.id) {
+            let kind = |g: &StoryGraph, node: Option<usize>| node.and_then(|id| g.nodes.get(&id)).map(std::mem::discriminant);
+            let kept = self.current_node.is_some_and(|id| graph.find_node(&active.node_key(id)).is_some());
+            let current = rematch(active, self.current_node);
+            let moved = !kept || kind(graph, current) != kind(active, self.current_node);
+            self.current_node = current;
+            *active = graph.clone();
+            if self.status == ExecutionStatus::WaitingForInput {
+                self.presented_choices.clear();
+                if moved {
+                    self.clear_line();
+                } else {
+                    // Same line or choice: show the edited text, keeping its state.
+                    self.reentry = Reentry::Reshow;
+                }
+                self.status = ExecutionStatus::Running;
+            } else if self.status == ExecutionStatus::WaitingForTimer && moved {
+                self.wait_timer.reset();
+                self.status = ExecutionStatus::Running;
+            }
+        }
+        fallbacks
+    }
+
+    /// Whether this executor runs `graph_id` or has it suspended.
+    pub fn uses_graph(&self, graph_id: &str) -> bool {
+        self.active_graph.iter().chain(self.call_stack.iter().map(|frame| &frame.graph)).any(|g| g.id == graph_id)
+    }
+}
+
+/// Reload graphs whose files changed since the last poll.
+pub(super) fn reload_changed_graphs(
+    watcher: Option<ResMut<StoryGraphWatcher>>,
+    time: Res<Time<Real>>,
+    mut library: ResMut<StoryGraphLibrary>,
+    mut variables: ResMut<StoryVariables>,
+    mut main: ResMut<GraphExecutor>,
+    mut instances: Query<(Entity, &mut GraphExecutor)>,
+    mut events: EventWriter<StoryReloadEvent>,
+) {
+    let Some(mut watcher) = watcher else {
+        return;
+    };
+    if !watcher.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    for path in watcher.changed_files() {
+        let data = match load_story_graph(&path) {
+            Ok(data) => data,
+            Err(e) => {
+                warn!("Story: failed to reload {}: {}", path.display(), e);
+                events.send(StoryReloadEvent::Failed { path, error: e.to_string() });
+                continue;
+            }
+        };
+        let graph = StoryGraph::from_data(&data);
+        info!("Story: reloaded '{}' from {}", graph.id, path.display());
+
+        let executors = std::iter::once((ExecutorId::Main, main.reborrow()))
+            .chain(instances.iter_mut().map(|(entity, executor)| (ExecutorId::Entity(entity), executor)));
+        for (executor_id, mut executor) in executors {
+            if !executor.uses_graph(&graph.id) {
+                continue;
+            }
+            variables.seed(&graph.variables);
+            for fallback in executor.reload_graph(&graph) {
+                warn!(
+                    "Story: node '{}' was removed from '{}'; {:?} continues at {:?}",
+                    fallback.missing_node, graph.id, executor_id, fallback.fallback
+                );
+                events.send(StoryReloadEvent::Fallback {
+                    executor: executor_id,
+                    graph_id: graph.id.clone(),
+                    missing_node: fallback.missing_node,
+                    fallback: fallback.fallback,
+                });
+            }
+        }
+        events.send(StoryReloadEvent::Reloaded { graph_id: graph.id.clone(), path });
+        library.insert(graph);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::story_graph::{CallFrame, StoryNode};
+
+    fn graph(lines: &[&str]) -> StoryGraph {
+        let mut graph = StoryGraph::new();
+        graph.id = "intro".to_string();
+        let mut next = None;
+        for key in lines.iter().rev() {
+            let id = graph.add(StoryNode::Dialogue {
+                speaker: "Guide".to_string(),
+                text: key.to_string().into(),
+                portrait: None,
+                voice: None,
+                duration: None,
+                next,
+            });
+            graph.node_keys.insert(id, key.to_string());
+            next = Some(id);
+        }
+        graph.start_node = next;
+        graph
+    }
+
+    #[test]
+    fn test_reload_graph_keeps_or_falls_back() {
+        let old = graph(&["hello", "bye"]);
+        let mut executor = GraphExecutor::default();
+        executor.start(old.clone());
+        executor.current_node = old.find_node("bye");
+        executor.status = ExecutionStatus::WaitingForInput;
+
+        // Node ids shift, but the authored id still matches.
+        let new = graph(&["welcome", "hello", "bye"]);
+        assert!(executor.reload_graph(&new).is_empty());
+        assert_eq!(executor.current_node, new.find_node("bye"));
+        assert_eq!(executor.status, ExecutionStatus::Running);
+
+        executor.call_stack.push(CallFrame { graph: new.clone(), return_node: new.find_node("hello") });
+        let newer = graph(&["welcome"]);
+        let fallbacks = executor.reload_graph(&newer);
+        assert_eq!(
+            fallbacks,
+            [
+                NodeFallback { missing_node: "hello".to_string(), fallback: Some("welcome".to_string()) },
+                NodeFallback { missing_node: "bye".to_string(), fallback: Some("welcome".to_string()) },
+            ]
+        );
+        assert_eq!(executor.current_node, newer.start_node);
+        assert_eq!(executor.call_stack[0].return_node, newer.start_node);
+    }
+
+    #[test]
+    fn test_reload_graph_restarts_removed_wait() {
+        let mut old = graph(&["hello"]);
+        let pause = old.add(StoryNode::Wait { duration: 2.0, next: old.start_node });
+        old.node_keys.insert(pause, "pause".to_string());
+        let mut executor = GraphExecutor::default();
+        executor.start(old);
+        executor.current_node = Some(pause);
+        executor.status = ExecutionStatus::WaitingForTimer;
+        executor.wait_timer = Timer::from_seconds(2.0, TimerMode::Once);
+        executor.wait_timer.tick(std::time::Duration::from_secs(1));
+
+        // Finishing the countdown would advance past the start node.
+        let new = graph(&["hello"]);
+        assert_eq!(executor.reload_graph(&new).len(), 1);
+        assert_eq!(executor.current_node, new.start_node);
+        assert_eq!(executor.status, ExecutionStatus::Running);
+        assert_eq!(executor.wait_timer.elapsed_secs(), 0.0);
+    }
+
+    #[test]
+    fn test_watcher_sees_every_graph_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut watcher = StoryGraphWatcher::new(dir.path());
+        for name in ["notes.txt", "a.json", "b.ron", "c.toml", "d.story", "e.twee", "f.yarn"] {
+            std::fs::write(dir.path().join(name), "").unwrap();
+        }
+        let changed: Vec<PathBuf> = watcher.changed_files();
+        let names: Vec<_> = changed.iter().filter_map(|path| path.file_name()?.to_str()).collect();
+        assert_eq!(names, ["a.json", "b.ron", "c.toml", "d.story", "e.twee", "f.yarn"]);
+    }
+}
@@ -0,0 +1,231 @@
+//! Saving and resuming a [`GraphExecutor`] mid-story.
+//!
+//! Runtime [`NodeId`]s are allocation order and shift whenever a graph is
+//! edited, so snapshots refer to graphs by id and to nodes by their authored
+//! string ids (see [`StoryGraph::node_key`]). Restoring asks the caller for
+//! the graphs again; nodes that were waiting on the player are re-entered so
+//! the UI receives the same [`StoryFlowEvent`](super::StoryFlowEvent), with
+//! their auto-advance and choice countdowns resuming where they were. A line
+//! still waiting for its voice clip's length loads the clip again.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::{CallFrame, ExecutionStatus, GraphExecutor, NodeId, Reentry, StoryFlags, StoryGraph, StoryVariables};
+
+/// Serializable state of a [`GraphExecutor`], the story variables and the
+/// story flags.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StorySnapshot {
+    /// Id of the active graph
+    pub graph_id: String,
+    /// Authored id of the current node
+    pub current_node: Option<String>,
+    pub status: ExecutionStatus,
+    /// Seconds left on the wait timer
+    #[serde(default)]
+    pub wait_remaining: f32,
+    /// Seconds left before the waiting line advances on its own
+    #[serde(default)]
+    pub line_remaining: Option<f32>,
+    /// Seconds left on a timed choice
+    #[serde(default)]
+    pub choice_remaining: Option<f32>,
+    /// Whether the waiting line advances once its voice clip has loaded
+    #[serde(default)]
+    pub voice_pending: bool,
+    /// Suspended callers, innermost last
+    #[serde(default)]
+    pub call_stack: Vec<CallFrameSnapshot>,
+    #[serde(default)]
+    pub variables: StoryVariables,
+    #[serde(default)]
+    pub flags: StoryFlags,
+}
+
+/// Serializable form of a [`CallFrame`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CallFrameSnapshot {
+    pub graph_id: String,
+    /// Authored id of the node to resume at
+    pub return_node: Option<String>,
+}
+
+/// Errors when restoring a [`StorySnapshot`] or rewinding
+/// [`GraphExecutor::history`].
+#[derive(Error, Debug, PartialEq)]
+pub enum SnapshotError {
+    #[error("Unknown story graph: {0}")]
+    UnknownGraph(String),
+
+    #[error("Story graph '{graph_id}' has no node '{node}'")]
+    UnknownNode { graph_id: String, node: String },
+
+    #[error("No history line {0}")]
+    UnknownLine(usize),
+}
+
+impl GraphExecutor {
+    /// Capture the executor, variables and flags, or `None` when no graph is
+    /// loaded.
+    pub fn snapshot(&self, variables: &StoryVariables, flags: &StoryFlags) -> Option<StorySnapshot> {
+        let graph = self.active_graph.as_ref()?;
+        let wait_remaining = match self.status {
+            ExecutionStatus::WaitingForTimer => self.wait_timer.remaining_secs(),
+            _ => 0.0,
+        };
+        let waiting = self.status == ExecutionStatus::WaitingForInput;
+        let remaining = |timer: &Option<Timer>| timer.as_ref().filter(|_| waiting).map(Timer::remaining_secs);
+
+        Some(StorySnapshot {
+            graph_id: graph.id.clone(),
+            current_node: self.current_node.map(|id| graph.node_key(id)),
+            status: self.status,
+            wait_remaining,
+            line_remaining: remaining(&self.line_timer),
+            choice_remaining: remaining(&self.choice_timer),
+            voice_pending: waiting && self.pending_voice.is_some(),
+            call_stack: self
+                .call_stack
+                .iter()
+                .map(|frame| CallFrameSnapshot {
+                    graph_id: frame.graph.id.clone(),
+                    return_node: frame.return_node.map(|id| frame.graph.node_key(id)),
+                })
+                .collect(),
+            variables: variables.clone(),
+            flags: flags.clone(),
+        })
+    }
+
+    /// Resume from a snapshot.
+    ///
+    /// `find_graph` returns the graph for an id, e.g. via
+    /// [`StoryGraph::from_data`]. Nothing is modified if any graph or node
+    /// can't be resolved.
+    pub fn restore(
+        &mut self,
+        snapshot: &StorySnapshot,
+        mut find_graph: impl FnMut(&str) -> Option<StoryGraph>,
+        variables: &mut StoryVariables,
+        flags: &mut StoryFlags,
+    ) -> Result<(), SnapshotError> {
+        let (graph, current_node) =
+            resolve(&mut find_graph, &snapshot.graph_id, snapshot.current_node.as_deref())?;
+
+        let call_stack = snapshot
+            .call_stack
+            .iter()
+            .map(|frame| {
+                let (graph, return_node) =
+                    resolve(&mut find_graph, &frame.graph_id, frame.return_node.as_deref())?;
+                Ok(CallFrame { graph, return_node })
+            })
+            .collect::<Result<Vec<_>, SnapshotError>>()?;
+
+        self.active_graph = Some(graph);
+        self.current_node = current_node;
+        self.call_stack = call_stack;
+        self.presented_choices.clear();
+        self.history.clear();
+        self.clear_line();
+        self.seed_variables = false;
+        self.wait_timer = Timer::from_seconds(snapshot.wait_remaining.max(0.0), TimerMode::Once);
+        self.status = match snapshot.status {
+            // Re-enter the node so dialogue or choices are shown again,
+            // keeping their countdowns or waiting for the voice clip again.
+            ExecutionStatus::WaitingForInput => {
+                let timer = |seconds: f32| Timer::from_seconds(seconds.max(0.0), TimerMode::Once);
+                self.line_timer = snapshot.line_remaining.map(timer);
+                self.choice_timer = snapshot.choice_remaining.map(timer);
+                self.reentry = if snapshot.voice_pending { Reentry::ReloadVoice } else { Reentry::Reshow };
+                ExecutionStatus::Running
+            }
+            status => status,
+        };
+
+        variables.restore(&snapshot.variables);
+        *flags = snapshot.flags.clone();
+        Ok(())
+    }
+}
+
+pub(super) fn resolve(
+    find_graph: &mut impl FnMut(&str) -> Option<StoryGraph>,
+    graph_id: &str,
+    node: Option<&str>,
+) -> Result<(StoryGraph, Option<NodeId>), SnapshotError> {
+    let graph = find_graph(graph_id).ok_or_else(|| SnapshotError::UnknownGraph(graph_id.to_string()))?;
+    let node_id = match node {
+        Some(key) => Some(graph.find_node(key).ok_or_else(|| SnapshotError::UnknownNode {
+            graph_id: graph_id.to_string(),
+            node: key.to_string(),
+        })?),
+        None => None,
+    };
+    Ok((graph, node_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::story_graph::StoryNode;
+
+    fn graph() -> StoryGraph {
+        let mut graph = StoryGraph::new();
+        graph.id = "intro".to_string();
+        let end = graph.add(StoryNode::End);
+        let wait = graph.add(StoryNode::Wait { duration: 3.0, next: Some(end) });
+        graph.node_keys.insert(wait, "pause".to_string());
+        graph.set_start(wait);
+        graph
+    }
+
+    #[test]
+    fn test_snapshot_json_round_trip() {
+        let mut executor = GraphExecutor::default();
+        executor.start(graph());
+        executor.status = ExecutionStatus::WaitingForTimer;
+        executor.wait_timer = Timer::from_seconds(3.0, TimerMode::Once);
+        executor.wait_timer.tick(std::time::Duration::from_secs(1));
+        let mut variables = StoryVariables::default();
+        variables.set("gold", 7);
+        let mut flags = StoryFlags::default();
+        flags.set("met_guard", true);
+
+        let snapshot = executor.snapshot(&variables, &flags).unwrap();
+        assert_eq!(snapshot.current_node.as_deref(), Some("pause"));
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let loaded: StorySnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, snapshot);
+
+        let mut resumed = GraphExecutor::default();
+        let mut resumed_vars = StoryVariables::default();
+        let mut resumed_flags = StoryFlags::default();
+        resumed
+            .restore(&loaded, |id| (id == "intro").then(graph), &mut resumed_vars, &mut resumed_flags)
+            .unwrap();
+        assert_eq!(resumed.current_node, executor.current_node);
+        assert_eq!(resumed.status, ExecutionStatus::WaitingForTimer);
+        assert_eq!(resumed.wait_timer.remaining_secs(), 2.0);
+        assert_eq!(resumed_vars, variables);
+        assert_eq!(resumed_flags, flags);
+    }
+
+    #[test]
+    fn test_restore_unknown_node() {
+        let mut snapshot = GraphExecutor::default();
+        snapshot.start(graph());
+        let mut snapshot = snapshot.snapshot(&StoryVariables::default(), &StoryFlags::default()).unwrap();
+        snapshot.current_node = Some("deleted".to_string());
+
+        let mut executor = GraphExecutor::default();
+        let err = executor
+            .restore(&snapshot, |_| Some(graph()), &mut StoryVariables::default(), &mut StoryFlags::default())
+            .unwrap_err();
+        assert!(matches!(err, SnapshotError::UnknownNode { .. }));
+        assert!(executor.active_graph.is_none());
+    }
+}
@@ -0,0 +1,475 @@
+//! Typed story variables and condition evaluation.
+//!
+//! Story conditions authored in [`StoryGraphData`](crate::data::story::StoryGraphData)
+//! compare a named variable against a JSON value. This module provides the
+//! runtime store those conditions are evaluated against, seeded from each
+//! graph's `variables` map when it is loaded.
+//!
+//! Every write through [`StoryVariables::set`] and every removal is reported
+//! as a [`StoryVariableChanged`] event so games (and Lua, via the scripting plugin)
+//! can react to story state.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::data::story::{ConditionOperator, StoryCondition};
+
+/// A typed value stored in [`StoryVariables`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Reflect)]
+#[reflect(no_field_bounds)] // `List` is recursive.
+#[serde(untagged)]
+pub enum StoryValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    List(Vec<StoryValue>),
+}
+
+impl Default for StoryValue {
+    fn default() -> Self {
+        StoryValue::Bool(false)
+    }
+}
+
+impl StoryValue {
+    /// Convert a JSON value into a story value.
+    ///
+    /// Returns `None` for `null`, objects, and arrays containing either.
+    pub fn from_json(value: &serde_json::Value) -> Option<Self> {
+        match value {
+            serde_json::Value::Bool(b) => Some(StoryValue::Bool(*b)),
+            serde_json::Value::Number(n) => match n.as_i64() {
+                Some(i) => Some(StoryValue::Int(i)),
+                None => n.as_f64().map(StoryValue::Float),
+            },
+            serde_json::Value::String(s) => Some(StoryValue::String(s.clone())),
+            serde_json::Value::Array(items) => items
+                .iter()
+                .map(StoryValue::from_json)
+                .collect::<Option<Vec<_>>>()
+                .map(StoryValue::List),
+            _ => None,
+        }
+    }
+
+    /// Convert this value back into JSON.
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            StoryValue::Bool(b) => serde_json::Value::Bool(*b),
+            StoryValue::Int(i) => serde_json::Value::from(*i),
+            StoryValue::Float(f) => serde_json::Value::from(*f),
+            StoryValue::String(s) => serde_json::Value::String(s.clone()),
+            StoryValue::List(items) => {
+                serde_json::Value::Array(items.iter().map(StoryValue::to_json).collect())
+            }
+        }
+    }
+
+    /// The "empty" value of the same type (`false`, `0`, `0.0`, `""`, `[]`).
+    ///
+    /// Used when a condition reads a variable that was never set.
+    pub fn zero_like(&self) -> Self {
+        match self {
+            StoryValue::Bool(_) => StoryValue::Bool(false),
+            StoryValue::Int(_) => StoryValue::Int(0),
+            StoryValue::Float(_) => StoryValue::Float(0.0),
+            StoryValue::String(_) => StoryValue::String(String::new()),
+            StoryValue::List(_) => StoryValue::List(Vec::new()),
+        }
+    }
+
+    /// Numeric view of the value, if it is a number.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            StoryValue::Int(i) => Some(*i as f64),
+            StoryValue::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+
+    /// Add `amount` to this value.
+    ///
+    /// Numbers add (staying integral when both sides are ints, saturating at
+    /// the `i64` limits), strings concatenate and lists append. Returns
+    /// `None` for other combinations.
+    pub fn add(&self, amount: &StoryValue) -> Option<StoryValue> {
+        match (self, amount) {
+            (StoryValue::Int(a), StoryValue::Int(b)) => Some(StoryValue::Int(a.saturating_add(*b))),
+            (StoryValue::String(a), StoryValue::String(b)) => Some(StoryValue::String(format!("{a}{b}"))),
+            (StoryValue::List(items), item) => {
+                let mut items = items.clone();
+                items.push(item.clone());
+                Some(StoryValue::List(items))
+            }
+            _ => Some(StoryValue::Float(self.as_f64()? + amount.as_f64()?)),
+        }
+    }
+
+    /// Equality that treats `1` and `1.0` as the same value.
+    fn loosely_equals(&self, other: &StoryValue) -> bool {
+        match (self.as_f64(), other.as_f64()) {
+            (Some(a), Some(b)) => a == b,
+            _ => self == other,
+        }
+    }
+
+    /// Compare two values, treating ints and floats as the same number line.
+    fn partial_cmp_value(&self, other: &StoryValue) -> Option<Ordering> {
+        match (self, other) {
+            (StoryValue::String(a), StoryValue::String(b)) => Some(a.cmp(b)),
+            (StoryValue::Bool(a), StoryValue::Bool(b)) => Some(a.cmp(b)),
+            _ => self.as_f64()?.partial_cmp(&other.as_f64()?),
+        }
+    }
+
+    /// Whether this value contains `needle`.
+    ///
+    /// Strings match substrings; lists match elements.
+    fn contains(&self, needle: &StoryValue) -> bool {
+        match (self, needle) {
+            (StoryValue::String(haystack), StoryValue::String(n)) => haystack.contains(n.as_str()),
+            (StoryValue::List(items), needle) => items.iter().any(|item| item.loosely_equals(needle)),
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for StoryValue {
+    /// Formats the value as shown in story text; lists are comma-separated.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StoryValue::Bool(b) => write!(f, "{}", b),
+            StoryValue::Int(i) => write!(f, "{}", i),
+            StoryValue::Float(x) => write!(f, "{}", x),
+            StoryValue::String(s) => f.write_str(s),
+            StoryValue::List(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl From<bool> for StoryValue {
+    fn from(value: bool) -> Self {
+        StoryValue::Bool(value)
+    }
+}
+
+impl From<i64> for StoryValue {
+    fn from(value: i64) -> Self {
+        StoryValue::Int(value)
+    }
+}
+
+impl From<i32> for StoryValue {
+    fn from(value: i32) -> Self {
+        StoryValue::Int(value as i64)
+    }
+}
+
+impl From<f64> for StoryValue {
+    fn from(value: f64) -> Self {
+        StoryValue::Float(value)
+    }
+}
+
+impl From<&str> for StoryValue {
+    fn from(value: &str) -> Self {
+        StoryValue::String(value.to_string())
+    }
+}
+
+impl From<String> for StoryValue {
+    fn from(value: String) -> Self {
+        StoryValue::String(value)
+    }
+}
+
+impl From<Vec<StoryValue>> for StoryValue {
+    fn from(value: Vec<StoryValue>) -> Self {
+        StoryValue::List(value)
+    }
+}
+
+/// Sent whenever a story variable is written with a new value or removed.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct StoryVariableChanged {
+    pub name: String,
+    /// Previous value (`None` if the variable was unset)
+    pub old: Option<StoryValue>,
+    /// New value (`None` if the variable was removed)
+    pub value: Option<StoryValue>,
+}
+
+/// Typed variable store used to evaluate story conditions.
+///
+/// Replaces the bool-only [`StoryFlags`](super::StoryFlags) for authored
+/// graphs: `SetFlag` nodes write here as well, so conditions can read flags.
+#[derive(Resource, Default, Debug, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Resource)]
+pub struct StoryVariables {
+    values: HashMap<String, StoryValue>,
+    /// Writes not yet broadcast as [`StoryVariableChanged`] events.
+    #[serde(skip)]
+    #[reflect(ignore)]
+    pending_changes: Vec<StoryVariableChanged>,
+}
+
+impl PartialEq for StoryVariables {
+    fn eq(&self, other: &Self) -> bool {
+        self.values == other.values
+    }
+}
+
+impl StoryVariables {
+    /// Set a variable, recording a change event if the value differs.
+    pub fn set(&mut self, name: &str, value: impl Into<StoryValue>) {
+        let value = value.into();
+        let old = self.values.insert(name.to_string(), value.clone());
+        if old.as_ref() != Some(&value) {
+            self.pending_changes.push(StoryVariableChanged {
+                name: name.to_string(),
+                old,
+                value: Some(value),
+            });
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&StoryValue> {
+        self.values.get(name)
+    }
+
+    /// Add `amount` to a variable (see [`StoryValue::add`]).
+    ///
+    /// An unset variable starts from the zero value of `amount`'s type.
+    /// Returns `false` if the types cannot be added.
+    pub fn add(&mut self, name: &str, amount: impl Into<StoryValue>) -> bool {
+        let amount = amount.into();
+        let current = self.get(name).cloned().unwrap_or_else(|| amount.zero_like());
+        match current.add(&amount) {
+            Some(sum) => {
+                self.set(name, sum);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove a variable, returning its last value. Recorded as a change
+    /// with no new value.
+    pub fn remove(&mut self, name: &str) -> Option<StoryValue> {
+        let old = self.values.remove(name)?;
+        self.pending_changes.push(StoryVariableChanged {
+            name: name.to_string(),
+            old: Some(old.clone()),
+            value: None,
+        });
+        Some(old)
+    }
+
+    /// Iterate over all variables.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &StoryValue)> {
+        self.values.iter()
+    }
+
+    /// Seed initial values from a graph's `variables` map.
+    ///
+    /// Variables that already have a value keep it, so re-entering a graph
+    /// does not reset progress.
+    pub fn seed(&mut self, initial: &HashMap<String, StoryValue>) {
+        for (name, value) in initial {
+            if !self.values.contains_key(name) {
+                self.set(name, value.clone());
+            }
+        }
+    }
+
+    /// Replace every value with those in `saved` (e.g. from a snapshot).
+    ///
+    /// Values that differ and variables missing from `saved`, which are
+    /// removed, are reported as changes.
+    pub fn restore(&mut self, saved: &StoryVariables) {
+        let removed: Vec<String> =
+            self.values.keys().filter(|name| !saved.values.contains_key(*name)).cloned().collect();
+        for name in removed {
+            self.remove(&name);
+        }
+        for (name, value) in &saved.values {
+            self.set(name, value.clone());
+        }
+    }
+
+    /// Writes recorded since the last [`StoryVariables::drain_changes`].
+    pub(crate) fn pending_changes(&self) -> &[StoryVariableChanged] {
+        &self.pending_changes
+    }
+
+    /// Take the writes recorded since the last call.
+    pub fn drain_changes(&mut self) -> Vec<StoryVariableChanged> {
+        std::mem::take(&mut self.pending_changes)
+    }
+
+    /// Evaluate a story condition against the current variable values.
+    ///
+    /// A variable that has never been set compares as the zero value of the
+    /// condition's type, mirroring how unset [`StoryFlags`](super::StoryFlags)
+    /// read as `false`.
+    pub fn evaluate(&self, condition: &StoryCondition) -> bool {
+        let Some(expected) = StoryValue::from_json(&condition.value) else {
+            warn!(
+                "Story condition on '{}' has unsupported value {}",
+                condition.variable, condition.value
+            );
+            return false;
+        };
+
+        let actual = self
+            .get(&condition.variable)
+            .cloned()
+            .unwrap_or_else(|| expected.zero_like());
+
+        compare(&actual, condition.operator, &expected)
+    }
+}
+
+/// Broadcasts writes to [`StoryVariables`] as [`StoryVariableChanged`] events.
+pub fn emit_variable_changes(
+    mut variables: ResMut<StoryVariables>,
+    mut events: EventWriter<StoryVariableChanged>,
+) {
+    // Avoid tripping change detection when nothing was written.
+    if variables.pending_changes.is_empty() {
+        return;
+    }
+    for change in variables.drain_changes() {
+        events.send(change);
+    }
+}
+
+/// Apply a condition operator to two values.
+pub fn compare(actual: &StoryValue, operator: ConditionOperator, expected: &StoryValue) -> bool {
+    let ordering = actual.partial_cmp_value(expected);
+    match operator {
+        ConditionOperator::Equals => actual.loosely_equals(expected),
+        ConditionOperator::NotEquals => !actual.loosely_equals(expected),
+        ConditionOperator::LessThan => ordering == Some(Ordering::Less),
+        ConditionOperator::LessThanOrEquals => {
+            matches!(ordering, Some(Ordering::Less | Ordering::Equal))
+        }
+        ConditionOperator::GreaterThan => ordering == Some(Ordering::Greater),
+        ConditionOperator::GreaterThanOrEquals => {
+            matches!(ordering, Some(Ordering::Greater | Ordering::Equal))
+        }
+        ConditionOperator::Contains => actual.contains(expected),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn condition(variable: &str, operator: ConditionOperator, value: serde_json::Value) -> StoryCondition {
+        StoryCondition {
+            variable: variable.to_string(),
+            operator,
+            value,
+        }
+    }
+
+    #[test]
+    fn test_evaluate_all_operators() {
+        let mut vars = StoryVariables::default();
+        vars.set("gold", 10);
+        vars.set("speed", 1.5);
+        vars.set("name", "Hamster Narrator");
+
+        assert!(vars.evaluate(&condition("gold", ConditionOperator::Equals, json!(10))));
+        assert!(vars.evaluate(&condition("gold", ConditionOperator::NotEquals, json!(11))));
+        assert!(vars.evaluate(&condition("gold", ConditionOperator::LessThan, json!(10.5))));
+        assert!(vars.evaluate(&condition("gold", ConditionOperator::LessThanOrEquals, json!(10))));
+        assert!(vars.evaluate(&condition("speed", ConditionOperator::GreaterThan, json!(1))));
+        assert!(vars.evaluate(&condition("speed", ConditionOperator::GreaterThanOrEquals, json!(1.5))));
+        assert!(vars.evaluate(&condition("name", ConditionOperator::Contains, json!("Narrator"))));
+        assert!(!vars.evaluate(&condition("name", ConditionOperator::Contains, json!("Glitch"))));
+    }
+
+    #[test]
+    fn test_unset_variable_reads_as_zero() {
+        let vars = StoryVariables::default();
+        assert!(vars.evaluate(&condition("met_hamster", ConditionOperator::Equals, json!(false))));
+        assert!(vars.evaluate(&condition("gold", ConditionOperator::LessThan, json!(1))));
+        assert!(!vars.evaluate(&condition("gold", ConditionOperator::GreaterThan, json!(0))));
+    }
+
+    #[test]
+    fn test_list_values() {
+        let mut vars = StoryVariables::default();
+        vars.set("party", StoryValue::from_json(&json!(["hamster", "glitch"])).unwrap());
+        assert!(vars.evaluate(&condition("party", ConditionOperator::Contains, json!("glitch"))));
+        assert!(vars.evaluate(&condition("party", ConditionOperator::Equals, json!(["hamster", "glitch"]))));
+
+        assert!(vars.add("party", "narrator"));
+        assert!(vars.evaluate(&condition("party", ConditionOperator::Contains, json!("narrator"))));
+        assert_eq!(vars.get("party").unwrap().to_json(), json!(["hamster", "glitch", "narrator"]));
+    }
+
+    #[test]
+    fn test_add_and_change_tracking() {
+        let mut vars = StoryVariables::default();
+        vars.seed(&HashMap::from([("gold".to_string(), StoryValue::Int(5))]));
+        assert!(vars.add("gold", 10));
+        assert!(vars.add("speed", 0.5));
+        assert!(!vars.add("gold", "coins"));
+        vars.set("gold", 15); // Unchanged value, no event
+
+        // Seeding never overwrites progress
+        vars.seed(&HashMap::from([("gold".to_string(), StoryValue::Int(5))]));
+
+        assert_eq!(vars.get("gold"), Some(&StoryValue::Int(15)));
+        assert_eq!(vars.get("speed"), Some(&StoryValue::Float(0.5)));
+
+        let changes = vars.drain_changes();
+        assert_eq!(changes.len(), 3);
+        assert_eq!(changes[1].old, Some(StoryValue::Int(5)));
+        assert_eq!(changes[1].value, Some(StoryValue::Int(15)));
+        assert!(vars.drain_changes().is_empty());
+
+        // Removing reports the old value, and restoring removes what the
+        // saved store lacks.
+        assert_eq!(vars.remove("speed"), Some(StoryValue::Float(0.5)));
+        assert_eq!(vars.remove("speed"), None);
+        vars.set("met_hamster", true);
+        vars.drain_changes();
+        vars.restore(&StoryVariables::default());
+        let mut removed: Vec<_> = vars.drain_changes().into_iter().filter(|c| c.value.is_none()).map(|c| c.name).collect();
+        removed.sort();
+        assert_eq!(removed, ["gold", "met_hamster"]);
+    }
+
+    #[test]
+    fn test_int_add_saturates() {
+        let mut vars = StoryVariables::default();
+        vars.set("gold", i64::MAX - 1);
+        assert!(vars.add("gold", 10));
+        assert_eq!(vars.get("gold"), Some(&StoryValue::Int(i64::MAX)));
+        assert_eq!(StoryValue::Int(i64::MIN).add(&StoryValue::Int(-1)), Some(StoryValue::Int(i64::MIN)));
+    }
+
+    #[test]
+    fn test_mismatched_types_never_equal() {
+        let mut vars = StoryVariables::default();
+        vars.set("flag", true);
+        assert!(!vars.evaluate(&condition("flag", ConditionOperator::Equals, json!("true"))));
+        assert!(vars.evaluate(&condition("flag", ConditionOperator::NotEquals, json!("true"))));
+    }
+}
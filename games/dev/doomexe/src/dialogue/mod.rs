@@ -1,4 +1,5 @@
 use bevy::prelude::*;
+use dj_engine::story_graph::ChoiceView;
 use crate::state::GameState;
 
 mod ui;
@@ -9,7 +10,7 @@ pub struct DialogueUiState {
     pub speaker: String,
     pub text: String,
     pub _portrait: Option<String>,
-    pub choices: Vec<String>,
+    pub choices: Vec<ChoiceView>,
     pub prompt: String,
     pub selected_index: usize,
     pub is_choice_mode: bool,
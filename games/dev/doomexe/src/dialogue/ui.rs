@@ -2,7 +2,7 @@ use bevy::prelude::*;
 use crate::state::GameState;
 use crate::dialogue::DialogueUiState;
 use dj_engine::input::{ActionState, InputAction};
-use dj_engine::story_graph::{StoryFlowEvent, StoryInputEvent};
+use dj_engine::story_graph::{ChoiceView, ExecutorId, MarkupSpan, StoryFlowEvent, StoryInputEvent};
 
 #[derive(Component)]
 pub struct DialogueUI;
@@ -24,12 +24,97 @@ pub struct ChoiceButton {
 #[derive(Component)]
 pub struct ChoiceSelector;
 
+/// Reveals dialogue spans one character at a time.
+///
+/// Each text span is drawn by a `TextSpan` child (listed in `sections`), so
+/// colors apply per span; `[speed]` scales the per-character timer and
+/// `[pause]` holds the reveal.
 #[derive(Component)]
 pub struct Typewriter {
-    pub full_text: String,
-    pub current_len: usize,
-    pub timer: Timer,
-    pub _speed: f32, // Seconds per character
+    pub spans: Vec<MarkupSpan>,
+    /// `TextSpan` entity for each text span, in order
+    pub sections: Vec<Entity>,
+    /// Span currently being revealed
+    pub span_index: usize,
+    /// Characters revealed in the current span
+    pub span_chars: usize,
+    pub timer: Timer, // Seconds per character at speed 1
+    pub pause: Timer,
+}
+
+impl Typewriter {
+    fn new(seconds_per_char: f32) -> Self {
+        Self {
+            spans: Vec::new(),
+            sections: Vec::new(),
+            span_index: 0,
+            span_chars: 0,
+            timer: Timer::from_seconds(seconds_per_char, TimerMode::Repeating),
+            pause: Timer::default(),
+        }
+    }
+
+    fn start(&mut self, spans: Vec<MarkupSpan>, sections: Vec<Entity>) {
+        self.spans = spans;
+        self.sections = sections;
+        self.span_index = 0;
+        self.span_chars = 0;
+        self.timer.reset();
+        self.pause = Timer::default();
+    }
+
+    fn finish(&mut self) {
+        self.span_index = self.spans.len();
+        self.span_chars = 0;
+    }
+
+    fn is_finished(&self) -> bool {
+        self.span_index >= self.spans.len()
+    }
+
+    /// Whether the span being revealed is marked `[shake]`.
+    fn is_shaking(&self) -> bool {
+        matches!(self.spans.get(self.span_index), Some(MarkupSpan::Text { style, .. }) if style.shake)
+    }
+
+    fn tick(&mut self, delta: std::time::Duration) {
+        if !self.pause.finished() {
+            self.pause.tick(delta);
+            return;
+        }
+        match self.spans.get(self.span_index) {
+            Some(MarkupSpan::Pause(seconds)) => {
+                self.pause = Timer::from_seconds(*seconds, TimerMode::Once);
+                self.span_index += 1;
+            }
+            Some(MarkupSpan::Text { text, style }) => {
+                let len = text.chars().count();
+                self.timer.tick(delta.mul_f32(style.speed));
+                self.span_chars = (self.span_chars + self.timer.times_finished_this_tick() as usize).min(len);
+                if self.span_chars == len {
+                    self.span_index += 1;
+                    self.span_chars = 0;
+                }
+            }
+            None => {}
+        }
+    }
+
+    /// Revealed text of each text span, matching `sections`.
+    fn revealed(&self) -> Vec<String> {
+        self.spans
+            .iter()
+            .enumerate()
+            .filter_map(|(i, span)| match span {
+                MarkupSpan::Text { text, .. } if i < self.span_index => Some(text.clone()),
+                MarkupSpan::Text { text, .. } if i == self.span_index => {
+                    Some(text.chars().take(self.span_chars).collect())
+                }
+                MarkupSpan::Text { .. } => Some(String::new()),
+                MarkupSpan::Pause(_) => None,
+            })
+            .collect()
+    }
 }
 
 pub fn setup_dialogue_ui(mut commands: Commands, _asset_server: Res<AssetServer>) {
@@ -129,12 +214,7 @@ pub fn setup_dialogue_ui(mut commands: Commands, _asset_server: Res<AssetServer>
                         },
                         TextColor(Color::WHITE),
                         DialogueText,
-                        Typewriter {
-                            full_text: "".to_string(),
-                            current_len: 0,
-                            timer: Timer::from_seconds(0.02, TimerMode::Repeating), // Fast text
-                            _speed: 0.02,
-                        }
+                        Typewriter::new(0.02), // Fast text
                     ));
                 });
             });
@@ -146,16 +226,17 @@ pub fn update_dialogue_ui(
     mut events: EventReader<StoryFlowEvent>,
     mut ui_state: ResMut<DialogueUiState>,
     mut ui_query: Query<&mut Node, With<DialogueUI>>,
-    mut text_query: Query<(&mut Text, &mut Typewriter), With<DialogueText>>,
+    mut text_query: Query<(Entity, &mut Text, &mut Typewriter), With<DialogueText>>,
     mut speaker_query: Query<&mut Text, (With<SpeakerText>, Without<DialogueText>)>,
     mut portrait_query: Query<(&mut ImageNode, &mut BackgroundColor), With<PortraitImage>>,
     choice_query: Query<Entity, With<ChoiceSelector>>,
     mut next_state: ResMut<NextState<GameState>>,
     asset_server: Res<AssetServer>,
 ) {
-    for event in events.read() {
+    // Only the main conversation uses the dialogue box.
+    for event in events.read().filter(|e| e.executor() == ExecutorId::Main) {
         match event {
-            StoryFlowEvent::ShowDialogue { speaker, text, portrait } => {
+            StoryFlowEvent::ShowDialogue { speaker, text, spans, portrait, .. } => {
                 ui_state.visible = true;
                 ui_state.is_choice_mode = false;
                 ui_state.speaker = speaker.clone();
@@ -167,12 +248,24 @@ pub fn update_dialogue_ui(
                     node.display = Display::Flex;
                 }
 
-                // Reset Typewriter
-                for (mut t, mut writer) in text_query.iter_mut() {
+                // Reset Typewriter with one empty section per styled span
+                for (entity, mut t, mut writer) in text_query.iter_mut() {
                     t.0 = "".to_string(); // Start empty
-                    writer.full_text = text.clone();
-                    writer.current_len = 0;
-                    writer.timer.reset();
+                    commands.entity(entity).despawn_descendants();
+                    let mut sections = Vec::new();
+                    commands.entity(entity).with_children(|p| {
+                        for span in spans {
+                            if let MarkupSpan::Text { style, .. } = span {
+                                let color = style.color.unwrap_or(Color::WHITE);
+                                sections.push(p.spawn((
+                                    TextSpan::default(),
+                                    TextFont { font_size: 24.0, ..default() },
+                                    TextColor(color),
+                                )).id());
+                            }
+                        }
+                    });
+                    writer.start(spans.clone(), sections);
                 }
 
                 // Update Speaker
@@ -195,7 +288,7 @@ pub fn update_dialogue_ui(
                     commands.entity(container).despawn_descendants();
                 }
             }
-            StoryFlowEvent::ShowChoices { prompt, options } => {
+            StoryFlowEvent::ShowChoices { prompt, options, .. } => {
                 ui_state.visible = true;
                 ui_state.is_choice_mode = true;
                 ui_state.prompt = prompt.clone();
@@ -208,10 +301,10 @@ pub fn update_dialogue_ui(
 
                  // Text (Prompt) - Instant show, no typewriter for prompt usually? 
                  // Or yes? Let's Instant show for prompt to avoid delay.
-                for (mut t, mut writer) in text_query.iter_mut() { 
+                for (entity, mut t, mut writer) in text_query.iter_mut() { 
                     t.0 = prompt.clone(); 
-                    writer.full_text = prompt.clone();
-                    writer.current_len = prompt.len(); // Skip effect
+                    commands.entity(entity).despawn_descendants();
+                    writer.start(Vec::new(), Vec::new()); // Skip effect
                 }
                 
                 // Render choices
@@ -219,7 +312,8 @@ pub fn update_dialogue_ui(
                     spawn_choices(&mut commands, container, options, 0);
                 }
             }
-            StoryFlowEvent::GraphComplete => {
+            StoryFlowEvent::ChoiceTimer { .. } => {}
+            StoryFlowEvent::GraphComplete { .. } => {
                 ui_state.visible = false;
                 if let Ok(mut node) = ui_query.get_single_mut() {
                     node.display = Display::None;
@@ -232,31 +326,47 @@ pub fn update_dialogue_ui(
 
 pub fn typewriter_system(
     time: Res<Time>,
-    mut query: Query<(&mut Text, &mut Typewriter)>,
+    mut query: Query<(&mut Typewriter, &mut Node)>,
+    mut sections: Query<&mut TextSpan>,
 ) {
-    for (mut text, mut writer) in query.iter_mut() {
-        if writer.current_len < writer.full_text.len() {
-            writer.timer.tick(time.delta());
-            if writer.timer.finished() {
-                writer.current_len += 1;
-                // Determine byte index for safe slicing (UTF-8)
-                // writer.full_text.chars().take(writer.current_len).collect() is slow.
-                // Better: maintain byte index. For now, simple char iteration.
-                let display_text: String = writer.full_text.chars().take(writer.current_len).collect();
-                text.0 = display_text;
+    for (mut writer, mut node) in query.iter_mut() {
+        if !writer.is_finished() {
+            writer.tick(time.delta());
+        }
+
+        // Jitter the text box while a [shake] span is being typed.
+        let offset = if writer.is_shaking() {
+            (time.elapsed_secs() * 60.0).sin() * 2.0
+        } else {
+            0.0
+        };
+        let left = Val::Px(offset);
+        if node.left != left {
+            node.left = left;
+        }
+
+        if writer.is_changed() {
+            for (entity, text) in writer.sections.iter().zip(writer.revealed()) {
+                if let Ok(mut section) = sections.get_mut(*entity) {
+                    section.0 = text;
+                }
             }
         }
     }
 }
 
 // Helper to spawn choices
-fn spawn_choices(commands: &mut Commands, parent: Entity, choices: &[String], selected_idx: usize) {
+fn spawn_choices(commands: &mut Commands, parent: Entity, choices: &[ChoiceView], selected_idx: usize) {
     commands.entity(parent).despawn_descendants();
     
     commands.entity(parent).with_children(|p| {
-        for (i, text) in choices.iter().enumerate() {
+        for (i, choice) in choices.iter().enumerate() {
             let is_selected = i == selected_idx;
-            let color = if is_selected { Color::srgb(1.0, 1.0, 0.0) } else { Color::WHITE };
+            let color = match (choice.available, is_selected) {
+                (false, _) => Color::srgb(0.45, 0.45, 0.45), // Greyed out
+                (true, true) => Color::srgb(1.0, 1.0, 0.0),
+                (true, false) => Color::WHITE,
+            };
             let bg_color = if is_selected { Color::srgba(0.2, 0.2, 0.2, 0.9) } else { Color::srgba(0.0, 0.0, 0.0, 0.8) };
 
             p.spawn((
@@ -271,7 +381,7 @@ fn spawn_choices(commands: &mut Commands, parent: Entity, choices: &[String], se
                 ChoiceButton { _index: i },
             )).with_children(|btn| {
                 btn.spawn((
-                    Text::new(text),
+                    Text::new(&choice.text),
                     TextFont { font_size: 20.0, ..default() },
                     TextColor(color),
                 ));
@@ -285,7 +395,7 @@ pub fn dialogue_input(
     actions: Res<ActionState>,
     mut ui_state: ResMut<DialogueUiState>,
     mut input_events: EventWriter<StoryInputEvent>,
-    mut text_query: Query<(&mut Text, &mut Typewriter), With<DialogueText>>,
+    mut text_query: Query<&mut Typewriter, With<DialogueText>>,
     choice_query: Query<Entity, With<ChoiceSelector>>,
 ) {
     if !ui_state.visible { return; }
@@ -310,8 +420,12 @@ pub fn dialogue_input(
                     spawn_choices(&mut commands, container, &ui_state.choices, ui_state.selected_index);
                 }
             }
-            if actions.just_pressed(InputAction::Confirm) {
-                input_events.send(StoryInputEvent::SelectChoice(ui_state.selected_index));
+            let selectable = ui_state.choices.get(ui_state.selected_index).is_some_and(|c| c.available);
+            if actions.just_pressed(InputAction::Confirm) && selectable {
+                input_events.send(StoryInputEvent::SelectChoice {
+                    executor: ExecutorId::Main,
+                    index: ui_state.selected_index,
+                });
             }
         }
     } else {
@@ -319,17 +433,16 @@ pub fn dialogue_input(
         if actions.just_pressed(InputAction::Confirm) {
             // Check if typewriter finished?
             let mut all_finished = true;
-            for (mut text, mut writer) in text_query.iter_mut() {
-                if writer.current_len < writer.full_text.len() {
+            for mut writer in text_query.iter_mut() {
+                if !writer.is_finished() {
                     // Fast forward
-                    writer.current_len = writer.full_text.len();
-                    text.0 = writer.full_text.clone();
+                    writer.finish();
                     all_finished = false;
                 }
             }
 
             if all_finished {
-                input_events.send(StoryInputEvent::Advance);
+                input_events.send(StoryInputEvent::Advance { executor: ExecutorId::Main });
             }
         }
     }
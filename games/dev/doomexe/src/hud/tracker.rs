@@ -1,4 +1,6 @@
 use bevy::prelude::*;
+use dj_engine::data::Database;
+use dj_engine::story_graph::{QuestEvent, QuestLog};
 use crate::story::StoryState;
 use crate::overworld::NPC;
 use super::minimap::MapTarget;
@@ -37,11 +39,17 @@ pub fn setup_tracker(mut commands: Commands) {
 
 pub fn update_tracker(
     story: Res<StoryState>,
+    mut quest_events: EventReader<QuestEvent>,
+    quests: Res<QuestLog>,
+    database: Option<Res<Database>>,
     mut query: Query<&mut Text, With<ObjectiveText>>,
 ) {
-    if story.is_changed() {
+    let quests_changed = quest_events.read().count() > 0;
+    if story.is_changed() || quests_changed {
         for mut text in &mut query {
-            if !story.has_flag("MetHamster") {
+            if let Some(objective) = quest_objective(&quests, database.as_deref()) {
+                text.0 = objective;
+            } else if !story.has_flag("MetHamster") {
                 text.0 = "Objective: Find the Narrator (East)".to_string();
             } else if !story.has_flag("DefeatedGlitch") {
                 text.0 = "Objective: Investigate Glitch (South-West)".to_string();
@@ -52,6 +60,20 @@ pub fn update_tracker(
     }
 }
 
+/// Objective text for the first active quest: its name and how far along
+/// its first unfinished objective is.
+fn quest_objective(quests: &QuestLog, database: Option<&Database>) -> Option<String> {
+    let (id, progress) = quests.active().next()?;
+    let name = database
+        .and_then(|db| db.find_quest(id))
+        .and_then(|quest| quest.name.get("en").cloned())
+        .unwrap_or_else(|| id.to_string());
+    match progress.objectives.iter().find(|objective| !objective.is_complete()) {
+        Some(objective) => Some(format!("Objective: {} ({}/{})", name, objective.current, objective.required)),
+        None => Some(format!("Objective: {}", name)),
+    }
+}
+
 pub fn update_objective_markers(
     mut commands: Commands,
     story: Res<StoryState>,
@@ -30,28 +30,36 @@ pub fn interaction_check(
                             // Path A: Already Won
                             let win2 = graph.add(StoryNode::Dialogue {
                                 speaker: "Hamster Narrator".to_string(),
-                                text: "But the corruption runs deeper...".to_string(),
+                                text: "But the corruption runs deeper...".into(),
                                 portrait: None,
+                                voice: None,
+                                duration: None,
                                 next: Some(end),
                             });
                             let win1 = graph.add(StoryNode::Dialogue {
                                 speaker: "Hamster Narrator".to_string(),
-                                text: "Incredible! You purged the glitch.".to_string(),
+                                text: "Incredible! You purged the glitch.".into(),
                                 portrait: None,
+                                voice: None,
+                                duration: None,
                                 next: Some(win2),
                             });
 
                             // Path B: Need to fight
                             let quest2 = graph.add(StoryNode::Dialogue {
                                 speaker: "Hamster Narrator".to_string(),
-                                text: "Go investigate that purple puddle.".to_string(),
+                                text: "Go investigate that purple puddle.".into(),
                                 portrait: None,
+                                voice: None,
+                                duration: None,
                                 next: Some(end),
                             });
                             let quest1 = graph.add(StoryNode::Dialogue {
                                 speaker: "Hamster Narrator".to_string(),
-                                text: "There is a corruption to the south-west.".to_string(),
+                                text: "There is a corruption to the south-west.".into(),
                                 portrait: None,
+                                voice: None,
+                                duration: None,
                                 next: Some(quest2),
                             });
 
@@ -69,20 +77,26 @@ pub fn interaction_check(
                             });
                             let intro3 = graph.add(StoryNode::Dialogue {
                                 speaker: "Hamster Narrator".to_string(),
-                                text: "I am the Narrator. I will guide you.".to_string(),
+                                text: "I am the Narrator. I will guide you.".into(),
                                 portrait: None,
+                                voice: None,
+                                duration: None,
                                 next: Some(set_met),
                             });
                              let intro2 = graph.add(StoryNode::Dialogue {
                                 speaker: "Hamster Narrator".to_string(),
-                                text: "This prototype was scraped from the internet after it caused too much... doom.".to_string(),
+                                text: "This prototype was scraped from the internet after it caused too much... doom.".into(),
                                 portrait: None,
+                                voice: None,
+                                duration: None,
                                 next: Some(intro3),
                             });
                             let intro1 = graph.add(StoryNode::Dialogue {
                                 speaker: "Hamster Narrator".to_string(),
-                                text: "Oh you managed to find this lost exe.".to_string(),
+                                text: "Oh you managed to find this lost exe.".into(),
                                 portrait: None,
+                                voice: None,
+                                duration: None,
                                 next: Some(intro2),
                             });
 
@@ -104,8 +118,10 @@ pub fn interaction_check(
                             // Path C: Already Defeated
                             let inert = graph.add(StoryNode::Dialogue {
                                 speaker: "Glitch".to_string(),
-                                text: "The puddle is inert.".to_string(),
+                                text: "The puddle is inert.".into(),
                                 portrait: None,
+                                voice: None,
+                                duration: None,
                                 next: Some(end),
                             });
 
@@ -117,14 +133,18 @@ pub fn interaction_check(
                             });
                             let battle_warn = graph.add(StoryNode::Dialogue {
                                 speaker: "System".to_string(),
-                                text: "Initiating Battle Protocol...".to_string(),
+                                text: "Initiating Battle Protocol...".into(),
                                 portrait: None,
+                                voice: None,
+                                duration: None,
                                 next: Some(trigger_battle),
                             });
                             let screech = graph.add(StoryNode::Dialogue {
                                 speaker: "Glitch".to_string(),
-                                text: "The glitch screeches!".to_string(),
+                                text: "The glitch screeches!".into(),
                                 portrait: None,
+                                voice: None,
+                                duration: None,
                                 next: Some(battle_warn),
                             });
 
@@ -137,14 +157,18 @@ pub fn interaction_check(
                             // Path A: Not Met Hamster (Warning)
                             let warn2 = graph.add(StoryNode::Dialogue {
                                 speaker: "Glitch".to_string(),
-                                text: "It seems dangerous to touch without guidance.".to_string(),
+                                text: "It seems dangerous to touch without guidance.".into(),
                                 portrait: None,
+                                voice: None,
+                                duration: None,
                                 next: Some(end),
                             });
                              let warn1 = graph.add(StoryNode::Dialogue {
                                 speaker: "Glitch".to_string(),
-                                text: "It's a writhing mass of corrupted data.".to_string(),
+                                text: "It's a writhing mass of corrupted data.".into(),
                                 portrait: None,
+                                voice: None,
+                                duration: None,
                                 next: Some(warn2),
                             });
 